@@ -3,6 +3,7 @@ use std::cmp::{self, Ordering, PartialEq, PartialOrd, Eq, Ord};
 use super::algorithms::{self, Limb, LIMB_BITS};
 use super::montgomery;
 use utils::slice_ext::SliceExt;
+use utils::zeroize::Zeroize;
 
 #[derive(Clone)]
 pub struct UBigInt {
@@ -97,7 +98,6 @@ impl PartialOrd for UBigInt {
 
 impl Drop for UBigInt {
     fn drop(&mut self) {
-        // May need to prevent this from being optimized away
-        self.limbs.fill_copy(0);
+        self.limbs.zeroize();
     }
 }
@@ -0,0 +1,139 @@
+// Adler-32, the checksum used by zlib, as defined in RFC 1950. Like CRC-32 it
+// only detects accidental corruption, and it is weaker than CRC-32 on short
+// inputs, but it is quicker to compute.
+//
+// Digests hold the checksum as a big-endian number, the byte order zlib
+// stores it in.
+
+use std::io::{self, Write};
+use super::DigestAlgorithm;
+
+// Largest prime below 2^16
+const MOD: u32 = 65521;
+
+// Most bytes that can be summed before `b` could overflow 32 bits
+const NMAX: usize = 5552;
+
+define_digest!(Adler32Digest, 4);
+
+impl Adler32Digest {
+    // The checksum as a number
+    pub fn value(&self) -> u32 {
+        u32::from_be_bytes(self.0)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Adler32 {
+    // One more than the sum of the bytes
+    a: u32,
+    // Sum of the successive values of `a`
+    b: u32
+}
+
+impl Adler32 {
+    // Continues from the checksum of some earlier input
+    pub fn from_value(value: u32) -> Self {
+        Adler32 {
+            a: value & 0xffff,
+            b: value >> 16
+        }
+    }
+
+    // Checksum of the input so far
+    pub fn value(&self) -> u32 {
+        self.b << 16 | self.a
+    }
+
+    // Checksum of the concatenation of two inputs, from the checksums of each
+    // and the length of the second. Each byte of the first input is counted
+    // `len2` more times in `b`, and the initial 1 in `a` is counted twice.
+    pub fn combine(value1: u32, value2: u32, len2: u64) -> u32 {
+        let rem = (len2 % MOD as u64) as u32;
+        let (a1, b1) = (value1 & 0xffff, value1 >> 16);
+        let (a2, b2) = (value2 & 0xffff, value2 >> 16);
+        let a = (a1 + a2 + MOD - 1) % MOD;
+        let b = (rem*a1 % MOD + b1 + b2 + MOD - rem) % MOD;
+        b << 16 | a
+    }
+}
+
+impl DigestAlgorithm for Adler32 {
+    type Digest = Adler32Digest;
+
+    fn new() -> Self {
+        Adler32::from_value(1)
+    }
+
+    // There are no blocks, but `Hmac` needs room for a hashed key
+    fn block_len() -> usize {
+        4
+    }
+
+    // The sums are only reduced every `NMAX` bytes
+    fn update(&mut self, input: &[u8]) {
+        for chunk in input.chunks(NMAX) {
+            for &byte in chunk {
+                self.a += byte as u32;
+                self.b += self.a;
+            }
+            self.a %= MOD;
+            self.b %= MOD;
+        }
+    }
+
+    fn digest(self) -> Self::Digest {
+        Adler32Digest(self.value().to_be_bytes())
+    }
+}
+
+impl Write for Adler32 {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.update(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_adler32(input: &[u8], expected: &[u8]) {
+        let actual = Adler32::compute(input);
+        println!("A: {:?}", actual);
+        println!("B: {:?}", expected);
+        assert!(actual.as_ref() == expected)
+    }
+
+    #[test]
+    fn adler32() {
+        test_adler32(b"", base16!("00000001"));
+        test_adler32(b"Wikipedia", base16!("11E60398"));
+        test_adler32(b"123456789", base16!("091E01DE"));
+
+        // Long enough to need reducing part way through, checked against zlib
+        let data: Vec<u8> = (0..100000u64).map(|i| (i*i % 251) as u8).collect();
+        test_adler32(&data, base16!("4BE71801"));
+        test_adler32(&[0xff; 100000], base16!("149A302C"));
+    }
+
+    #[test]
+    fn combine() {
+        let data: Vec<u8> = (0..100000u64).map(|i| (i*i % 251) as u8).collect();
+        for &split in &[0, 1, 5552, 65521, 65522, 99999, 100000] {
+            let (a, b) = data.split_at(split);
+            assert_eq!(
+                Adler32::combine(Adler32::compute(a).value(), Adler32::compute(b).value(), b.len() as u64),
+                Adler32::compute(&data).value()
+            );
+        }
+
+        let mut state = Adler32::from_value(Adler32::compute(&data[..300]).value());
+        state.update(&data[300..]);
+        assert_eq!(state.digest(), Adler32::compute(&data));
+    }
+}
@@ -0,0 +1,84 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+// Implementations of the SHA-1 and SHA-256 compression functions
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Backend {
+    // Portable code, always available
+    Scalar,
+    // Message schedule computed with SSSE3, rounds in portable code
+    Ssse3,
+    // Message schedules of two chunks at once with AVX2, rounds in portable
+    // code
+    Avx2,
+    // Intel SHA extensions
+    ShaNi
+}
+
+impl Backend {
+    pub const ALL: &'static [Backend] = &[Backend::Scalar, Backend::Ssse3, Backend::Avx2, Backend::ShaNi];
+
+    pub fn is_available(self) -> bool {
+        match self {
+            Backend::Scalar => true,
+            Backend::Ssse3 => ssse3_detected(),
+            Backend::Avx2 => avx2_detected(),
+            Backend::ShaNi => sha_ni_detected()
+        }
+    }
+
+    // Fastest backend supported by the current CPU. Detection only happens
+    // on the first call.
+    pub fn detect() -> Backend {
+        static DETECTED: AtomicUsize = AtomicUsize::new(0);
+
+        match DETECTED.load(Ordering::Relaxed) {
+            1 => Backend::Scalar,
+            2 => Backend::Ssse3,
+            3 => Backend::Avx2,
+            4 => Backend::ShaNi,
+            _ => {
+                let backend = if Backend::ShaNi.is_available() {
+                    Backend::ShaNi
+                } else if Backend::Avx2.is_available() {
+                    Backend::Avx2
+                } else if Backend::Ssse3.is_available() {
+                    Backend::Ssse3
+                } else {
+                    Backend::Scalar
+                };
+                DETECTED.store(backend as usize + 1, Ordering::Relaxed);
+                backend
+            }
+        }
+    }
+}
+
+#[cfg(target_arch = "x86_64")]
+fn ssse3_detected() -> bool {
+    is_x86_feature_detected!("ssse3")
+}
+
+#[cfg(target_arch = "x86_64")]
+fn avx2_detected() -> bool {
+    is_x86_feature_detected!("avx2")
+}
+
+#[cfg(target_arch = "x86_64")]
+fn sha_ni_detected() -> bool {
+    is_x86_feature_detected!("sha") && is_x86_feature_detected!("ssse3") && is_x86_feature_detected!("sse4.1")
+}
+
+#[cfg(not(target_arch = "x86_64"))]
+fn ssse3_detected() -> bool {
+    false
+}
+
+#[cfg(not(target_arch = "x86_64"))]
+fn avx2_detected() -> bool {
+    false
+}
+
+#[cfg(not(target_arch = "x86_64"))]
+fn sha_ni_detected() -> bool {
+    false
+}
@@ -0,0 +1,527 @@
+// BLAKE2b and BLAKE2s, as defined in RFC 7693, with the full parameter block
+// from the BLAKE2 specification for keying, salting, personalisation and
+// tree hashing.
+//
+// The final block is compressed with a flag set rather than padded with the
+// message length, so input is buffered until more arrives to show that the
+// current block isn't the last.
+
+use std::cmp;
+use std::fmt;
+use std::io::{self, Write};
+use byteorder::{ByteOrder, LittleEndian};
+use utils::slice_ext::SliceExt;
+use utils::zeroize::Zeroize;
+use super::DigestAlgorithm;
+
+// Message word permutation for each round
+const SIGMA: [[usize; 16]; 10] = [
+    [ 0,  1,  2,  3,  4,  5,  6,  7,  8,  9, 10, 11, 12, 13, 14, 15],
+    [14, 10,  4,  8,  9, 15, 13,  6,  1, 12,  0,  2, 11,  7,  5,  3],
+    [11,  8, 12,  0,  5,  2, 15, 13, 10, 14,  3,  6,  7,  1,  9,  4],
+    [ 7,  9,  3,  1, 13, 12, 11, 14,  2,  6,  5, 10,  4,  0, 15,  8],
+    [ 9,  0,  5,  7,  2,  4, 10, 15, 14,  1, 11, 12,  6,  8,  3, 13],
+    [ 2, 12,  6, 10,  0, 11,  8,  3,  4, 13,  7,  5, 15, 14,  1,  9],
+    [12,  5,  1, 15, 14, 13,  4, 10,  0,  7,  6,  3,  9,  2,  8, 11],
+    [13, 11,  7, 14, 12,  1,  3,  9,  5,  0, 15,  4,  8,  6,  2, 10],
+    [ 6, 15, 14,  9, 11,  3,  0,  8, 12,  2, 13,  7,  1,  4, 10,  5],
+    [10,  2,  8,  4,  7,  6,  1,  5, 15, 11,  9, 14,  3, 12, 13,  0]
+];
+
+// Configuration for BLAKE2b and BLAKE2s. The defaults give sequential
+// hashing with the longest output, and no key, salt or personalisation.
+#[derive(Clone, PartialEq, Eq)]
+pub struct Blake2Params {
+    // From 1 to 64 bytes for BLAKE2b, or 1 to 32 for BLAKE2s
+    pub output_len: usize,
+    // At most 64 bytes for BLAKE2b, or 32 for BLAKE2s
+    pub key: Vec<u8>,
+    // At most 16 bytes for BLAKE2b, or 8 for BLAKE2s, padded with zeros
+    pub salt: Vec<u8>,
+    pub personal: Vec<u8>,
+
+    // Tree hashing parameters, where a fanout of 0 means unlimited
+    pub fanout: u8,
+    pub max_depth: u8,
+    pub leaf_len: u32,
+    // Limited to 48 bits for BLAKE2s
+    pub node_offset: u64,
+    pub node_depth: u8,
+    pub inner_len: u8,
+    // Set when hashing the last node at each level of the tree
+    pub last_node: bool
+}
+
+impl Blake2Params {
+    pub fn new(output_len: usize) -> Self {
+        Blake2Params {
+            output_len,
+            key: Vec::new(),
+            salt: Vec::new(),
+            personal: Vec::new(),
+            fanout: 1,
+            max_depth: 1,
+            leaf_len: 0,
+            node_offset: 0,
+            node_depth: 0,
+            inner_len: 0,
+            last_node: false
+        }
+    }
+}
+
+// Everything except the key
+impl fmt::Debug for Blake2Params {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Blake2Params")
+            .field("output_len", &self.output_len)
+            .field("salt", &self.salt)
+            .field("personal", &self.personal)
+            .field("fanout", &self.fanout)
+            .field("max_depth", &self.max_depth)
+            .field("leaf_len", &self.leaf_len)
+            .field("node_offset", &self.node_offset)
+            .field("node_depth", &self.node_depth)
+            .field("inner_len", &self.inner_len)
+            .field("last_node", &self.last_node)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Drop for Blake2Params {
+    fn drop(&mut self) {
+        self.key.zeroize();
+    }
+}
+
+macro_rules! define_blake2 {
+    (
+        $name:ident, $word:ty, $read:ident, $write:ident, $rounds:expr, $rotations:expr,
+        $iv:expr, $salt_len:expr, $node_offset_bits:expr
+    ) => {
+        #[derive(Clone)]
+        pub struct $name {
+            h: [$word; 8],
+            // Number of bytes compressed so far
+            t: u128,
+            // The last block seen, which isn't compressed until more input arrives
+            buffer: [u8; 16*::std::mem::size_of::<$word>()],
+            buffer_len: usize,
+            output_len: usize,
+            last_node: bool
+        }
+
+        impl $name {
+            const WORD_LEN: usize = ::std::mem::size_of::<$word>();
+            pub const BLOCK_LEN: usize = 16*Self::WORD_LEN;
+            pub const MAX_OUTPUT_LEN: usize = 8*Self::WORD_LEN;
+            pub const MAX_KEY_LEN: usize = 8*Self::WORD_LEN;
+            pub const SALT_LEN: usize = $salt_len;
+
+            pub fn new(output_len: usize) -> Self {
+                Self::with_params(&Blake2Params::new(output_len))
+            }
+
+            pub fn new_keyed(key: &[u8], output_len: usize) -> Self {
+                let mut params = Blake2Params::new(output_len);
+                params.key = key.to_vec();
+                Self::with_params(&params)
+            }
+
+            pub fn with_params(params: &Blake2Params) -> Self {
+                assert!((1..=Self::MAX_OUTPUT_LEN).contains(&params.output_len), "invalid output length");
+                assert!(params.key.len() <= Self::MAX_KEY_LEN, "key too long");
+                assert!(params.salt.len() <= Self::SALT_LEN, "salt too long");
+                assert!(params.personal.len() <= Self::SALT_LEN, "personalisation too long");
+                assert!(params.node_offset.checked_shr($node_offset_bits).unwrap_or(0) == 0, "node offset too large");
+
+                // Parameter block, XOR-ed into the initial state
+                let mut block = [0; 8*Self::WORD_LEN];
+                block[0] = params.output_len as u8;
+                block[1] = params.key.len() as u8;
+                block[2] = params.fanout;
+                block[3] = params.max_depth;
+                LittleEndian::write_u32(&mut block[4..8], params.leaf_len);
+                let node_offset_end = 8 + $node_offset_bits/8;
+                LittleEndian::write_uint(&mut block[8..node_offset_end], params.node_offset, $node_offset_bits/8);
+                block[node_offset_end] = params.node_depth;
+                block[node_offset_end + 1] = params.inner_len;
+                let salt_start = 4*Self::WORD_LEN;
+                block[salt_start..salt_start + params.salt.len()].copy_from_slice(&params.salt);
+                let personal_start = salt_start + Self::SALT_LEN;
+                block[personal_start..personal_start + params.personal.len()].copy_from_slice(&params.personal);
+
+                let mut h = $iv;
+                for (h, bytes) in h.iter_mut().zip(block.chunks(Self::WORD_LEN)) {
+                    *h ^= LittleEndian::$read(bytes);
+                }
+
+                let mut result = $name {
+                    h,
+                    t: 0,
+                    buffer: [0; 16*Self::WORD_LEN],
+                    buffer_len: 0,
+                    output_len: params.output_len,
+                    last_node: params.last_node
+                };
+                // The key is padded to a full block and hashed before the message
+                if !params.key.is_empty() {
+                    result.buffer[..params.key.len()].copy_from_slice(&params.key);
+                    result.buffer_len = Self::BLOCK_LEN;
+                }
+                result
+            }
+
+            pub fn output_len(&self) -> usize {
+                self.output_len
+            }
+
+            fn compress(&mut self, last: bool) {
+                let mut m = [0; 16];
+                for (m, bytes) in m.iter_mut().zip(self.buffer.chunks(Self::WORD_LEN)) {
+                    *m = LittleEndian::$read(bytes);
+                }
+
+                let mut v = [0; 16];
+                v[..8].copy_from_slice(&self.h);
+                v[8..].copy_from_slice(&$iv);
+                v[12] ^= self.t as $word;
+                v[13] ^= (self.t >> (8*Self::WORD_LEN)) as $word;
+                if last {
+                    v[14] = !v[14];
+                    if self.last_node {
+                        v[15] = !v[15];
+                    }
+                }
+
+                let [r1, r2, r3, r4] = $rotations;
+                let mut g = |a: usize, b: usize, c: usize, d: usize, x: $word, y: $word| {
+                    v[a] = v[a].wrapping_add(v[b]).wrapping_add(x);
+                    v[d] = (v[d] ^ v[a]).rotate_right(r1);
+                    v[c] = v[c].wrapping_add(v[d]);
+                    v[b] = (v[b] ^ v[c]).rotate_right(r2);
+                    v[a] = v[a].wrapping_add(v[b]).wrapping_add(y);
+                    v[d] = (v[d] ^ v[a]).rotate_right(r3);
+                    v[c] = v[c].wrapping_add(v[d]);
+                    v[b] = (v[b] ^ v[c]).rotate_right(r4);
+                };
+                for round in 0..$rounds {
+                    let s = &SIGMA[round % 10];
+                    // Columns, then diagonals
+                    g(0, 4,  8, 12, m[s[ 0]], m[s[ 1]]);
+                    g(1, 5,  9, 13, m[s[ 2]], m[s[ 3]]);
+                    g(2, 6, 10, 14, m[s[ 4]], m[s[ 5]]);
+                    g(3, 7, 11, 15, m[s[ 6]], m[s[ 7]]);
+                    g(0, 5, 10, 15, m[s[ 8]], m[s[ 9]]);
+                    g(1, 6, 11, 12, m[s[10]], m[s[11]]);
+                    g(2, 7,  8, 13, m[s[12]], m[s[13]]);
+                    g(3, 4,  9, 14, m[s[14]], m[s[15]]);
+                }
+
+                for (i, h) in self.h.iter_mut().enumerate() {
+                    *h ^= v[i] ^ v[i + 8];
+                }
+                m.zeroize();
+                v.zeroize();
+            }
+
+            pub fn update(&mut self, mut input: &[u8]) {
+                while !input.is_empty() {
+                    // Only compress a full buffer once there's more input after it
+                    if self.buffer_len == Self::BLOCK_LEN {
+                        self.t += Self::BLOCK_LEN as u128;
+                        self.compress(false);
+                        self.buffer_len = 0;
+                    }
+                    let n = cmp::min(Self::BLOCK_LEN - self.buffer_len, input.len());
+                    self.buffer[self.buffer_len..self.buffer_len + n].copy_from_slice(&input[..n]);
+                    self.buffer_len += n;
+                    input = &input[n..];
+                }
+            }
+
+            // Writes the first `out.len()` bytes of the output, which must be
+            // no more than `output_len`
+            pub fn finalize_into(mut self, out: &mut [u8]) {
+                assert!(out.len() <= self.output_len, "output longer than the configured length");
+                self.t += self.buffer_len as u128;
+                self.buffer[self.buffer_len..].fill_copy(0);
+                self.compress(true);
+
+                let mut bytes = [0; 8*Self::WORD_LEN];
+                for (bytes, &h) in bytes.chunks_mut(Self::WORD_LEN).zip(&self.h) {
+                    LittleEndian::$write(bytes, h);
+                }
+                out.copy_from_slice(&bytes[..out.len()]);
+                bytes.zeroize();
+            }
+
+            pub fn finalize(self) -> Vec<u8> {
+                let mut result = vec![0; self.output_len];
+                self.finalize_into(&mut result);
+                result
+            }
+
+            pub fn compute(input: &[u8], output_len: usize) -> Vec<u8> {
+                let mut state = Self::new(output_len);
+                state.update(input);
+                state.finalize()
+            }
+
+            pub fn compute_keyed(key: &[u8], input: &[u8], output_len: usize) -> Vec<u8> {
+                let mut state = Self::new_keyed(key, output_len);
+                state.update(input);
+                state.finalize()
+            }
+        }
+
+        impl Zeroize for $name {
+            fn zeroize(&mut self) {
+                self.h.zeroize();
+                self.t.zeroize();
+                self.buffer.zeroize();
+                self.buffer_len.zeroize();
+            }
+        }
+
+        // In keyed mode the buffer starts out holding the padded key, so only
+        // the output length is shown
+        impl fmt::Debug for $name {
+            fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.debug_struct(stringify!($name))
+                    .field("output_len", &self.output_len)
+                    .finish_non_exhaustive()
+            }
+        }
+
+        impl Drop for $name {
+            fn drop(&mut self) {
+                self.zeroize();
+            }
+        }
+
+        impl Write for $name {
+            fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+                self.update(buf);
+                Ok(buf.len())
+            }
+
+            fn flush(&mut self) -> io::Result<()> {
+                Ok(())
+            }
+        }
+    }
+}
+
+define_blake2!(
+    BLAKE2b, u64, read_u64, write_u64, 12, [32, 24, 16, 63],
+    [
+        0x6a09e667f3bcc908, 0xbb67ae8584caa73b, 0x3c6ef372fe94f82b, 0xa54ff53a5f1d36f1,
+        0x510e527fade682d1, 0x9b05688c2b3e6c1f, 0x1f83d9abfb41bd6b, 0x5be0cd19137e2179
+    ],
+    16, 64
+);
+
+define_blake2!(
+    BLAKE2s, u32, read_u32, write_u32, 10, [16, 12, 8, 7],
+    [
+        0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a,
+        0x510e527f, 0x9b05688c, 0x1f83d9ab, 0x5be0cd19
+    ],
+    8, 48
+);
+
+// Unkeyed BLAKE2 with a fixed output length, as a `DigestAlgorithm`
+macro_rules! define_blake2_digest {
+    ($name:ident, $digest:ident, $inner:ident, $size:expr) => {
+        define_digest!($digest, $size);
+
+        #[derive(Clone)]
+        pub struct $name($inner);
+
+        impl fmt::Debug for $name {
+            fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.debug_struct(stringify!($name)).finish_non_exhaustive()
+            }
+        }
+
+        impl DigestAlgorithm for $name {
+            type Digest = $digest;
+
+            fn new() -> Self {
+                $name($inner::new($size))
+            }
+
+            fn block_len() -> usize {
+                $inner::BLOCK_LEN
+            }
+
+            fn update(&mut self, input: &[u8]) {
+                self.0.update(input);
+            }
+
+            fn digest(self) -> Self::Digest {
+                let mut result = [0; $size];
+                self.0.finalize_into(&mut result);
+                $digest(result)
+            }
+        }
+
+        impl Write for $name {
+            fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+                self.update(buf);
+                Ok(buf.len())
+            }
+
+            fn flush(&mut self) -> io::Result<()> {
+                Ok(())
+            }
+        }
+    }
+}
+
+define_blake2_digest!(BLAKE2b256, BLAKE2b256Digest, BLAKE2b, 32);
+define_blake2_digest!(BLAKE2b512, BLAKE2b512Digest, BLAKE2b, 64);
+define_blake2_digest!(BLAKE2s256, BLAKE2s256Digest, BLAKE2s, 32);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test<A: DigestAlgorithm>(input: &[u8], expected: &[u8]) {
+        let actual = A::compute(input);
+        println!("A: {:?}", actual.as_ref());
+        println!("B: {:?}", expected);
+        assert!(actual.as_ref() == expected)
+    }
+
+    // RFC 7693 appendices A and B
+    #[test]
+    fn rfc7693() {
+        test::<BLAKE2b512>(b"abc", base16!("BA80A53F981C4D0D6A2797B69F12F6E94C212F14685AC4B74B12BB6FDBFFA2D17D87C5392AAB792DC252D5DE4533CC9518D38AA8DBF1925AB92386EDD4009923"));
+        test::<BLAKE2s256>(b"abc", base16!("508C5E8C327C14E2E1A72BA34EEB452F37458B209ED63A294D999B4C86675982"));
+        test::<BLAKE2b512>(b"", base16!("786A02F742015903C6C6FD852552D272912F4740E15847618A86E217F71F5419D25E1031AFEE585313896444934EB04B903A685B1448B755D56F701AFE9BE2CE"));
+        test::<BLAKE2b256>(b"abc", base16!("BDDD813C634239723171EF3FEE98579B94964E3BB1CB3E427262C8C068D52319"));
+    }
+
+    // Deterministic input generator from RFC 7693 appendix E
+    fn selftest_seq(len: usize, seed: u32) -> Vec<u8> {
+        let mut a = 0xDEAD4BADu32.wrapping_mul(seed);
+        let mut b = 1u32;
+        (0..len).map(|_| {
+            let t = a.wrapping_add(b);
+            a = b;
+            b = t;
+            (t >> 24) as u8
+        }).collect()
+    }
+
+    // Self-tests from RFC 7693 appendix E, hashing the digests of many
+    // keyed and unkeyed inputs together
+    #[test]
+    fn rfc7693_selftest() {
+        let mut state = BLAKE2b::new(32);
+        for &output_len in &[20, 32, 48, 64] {
+            for &input_len in &[0, 3, 128, 129, 255, 1024] {
+                let input = selftest_seq(input_len, input_len as u32);
+                state.update(&BLAKE2b::compute(&input, output_len));
+                let key = selftest_seq(output_len, output_len as u32);
+                state.update(&BLAKE2b::compute_keyed(&key, &input, output_len));
+            }
+        }
+        assert!(state.finalize()[..] == base16!("C23A7800D98123BD10F506C61E29DA5603D763B8BBAD2E737F5E765A7BCCD475")[..]);
+
+        let mut state = BLAKE2s::new(32);
+        for &output_len in &[16, 20, 28, 32] {
+            for &input_len in &[0, 3, 64, 65, 255, 1024] {
+                let input = selftest_seq(input_len, input_len as u32);
+                state.update(&BLAKE2s::compute(&input, output_len));
+                let key = selftest_seq(output_len, output_len as u32);
+                state.update(&BLAKE2s::compute_keyed(&key, &input, output_len));
+            }
+        }
+        assert!(state.finalize()[..] == base16!("6A411F08CE25ADCDFB02ABA641451CEC53C598B24F4FC787FBDC88797F4C1DFE")[..]);
+    }
+
+    // Keyed vectors from the reference blake2b-kat.txt and blake2s-kat.txt,
+    // with the key and input both counting up from zero
+    #[test]
+    fn keyed_kat() {
+        let data: Vec<u8> = (0..=255).collect();
+        let cases: &[(usize, &[u8], &[u8])] = &[
+            (0, base16!("10EBB67700B1868EFB4417987ACF4690AE9D972FB7A590C2F02871799AAA4786B5E996E8F0F4EB981FC214B005F42D2FF4233499391653DF7AEFCBC13FC51568"), base16!("48A8997DA407876B3D79C0D92325AD3B89CBB754D86AB71AEE047AD345FD2C49")),
+            (1, base16!("961F6DD1E4DD30F63901690C512E78E4B45E4742ED197C3C5E45C549FD25F2E4187B0BC9FE30492B16B0D0BC4EF9B0F34C7003FAC09A5EF1532E69430234CEBD"), base16!("40D15FEE7C328830166AC3F918650F807E7E01E177258CDC0A39B11F598066F1")),
+            (255, base16!("142709D62E28FCCCD0AF97FAD0F8465B971E82201DC51070FAA0372AA43E92484BE1C1E73BA10906D5D1853DB6A4106E0A7BF9800D373D6DEE2D46D62EF2A461"), base16!("3FB735061ABC519DFE979E54C1EE5BFAD0A9D858B3315BAD34BDE999EFD724DD"))
+        ];
+        for &(len, expected_b, expected_s) in cases {
+            assert!(BLAKE2b::compute_keyed(&data[..64], &data[..len], 64)[..] == expected_b[..]);
+            assert!(BLAKE2s::compute_keyed(&data[..32], &data[..len], 32)[..] == expected_s[..]);
+        }
+    }
+
+    #[test]
+    fn params() {
+        let mut params = Blake2Params::new(20);
+        params.key = b"secret key".to_vec();
+        params.salt = b"0123456789abcdef".to_vec();
+        params.personal = b"personalisation!".to_vec();
+        params.fanout = 2;
+        params.max_depth = 3;
+        params.leaf_len = 4096;
+        params.node_offset = 5;
+        params.node_depth = 1;
+        params.inner_len = 32;
+        params.last_node = true;
+        let mut state = BLAKE2b::with_params(&params);
+        state.update(b"abc");
+        assert!(state.finalize()[..] == base16!("E0CF20504D4845B99FD0D6EFF8D80891CA58D069")[..]);
+
+        params.salt.truncate(8);
+        params.personal = b"personal".to_vec();
+        params.node_offset = (1 << 48) - 1;
+        let mut state = BLAKE2s::with_params(&params);
+        state.update(b"abc");
+        assert!(state.finalize()[..] == base16!("75086EB43E4C9C3855DB17C3AF5D26DB5C8950C3")[..]);
+
+        // Short salts and personalisations are padded with zeros
+        let mut params = Blake2Params::new(32);
+        params.salt = b"salt".to_vec();
+        params.personal = b"me".to_vec();
+        let mut state = BLAKE2s::with_params(&params);
+        state.update(b"abc");
+        assert!(state.finalize()[..] == base16!("FF95505F4AD2116CF216992C95DDC13EA3F0743A40E811A37C47E47BB2098B8A")[..]);
+    }
+
+    #[test]
+    fn incremental() {
+        // Block-sized pieces shouldn't be compressed as the last block early
+        let data: Vec<u8> = (0..=255).collect();
+        for &split in &[0, 1, 64, 127, 128, 129, 200, 256] {
+            let mut state = BLAKE2b::new(64);
+            state.update(&data[..split]);
+            state.update(&data[split..]);
+            assert_eq!(state.finalize(), BLAKE2b::compute(&data, 64));
+        }
+    }
+
+    #[test]
+    fn debug_hides_key() {
+        let key = [0xab; 32];
+        let state = BLAKE2b::new_keyed(&key, 32);
+        assert_eq!(format!("{:?}", state), "BLAKE2b { output_len: 32, .. }");
+        assert_eq!(format!("{:?}", BLAKE2s256::new()), "BLAKE2s256 { .. }");
+
+        let mut params = Blake2Params::new(32);
+        params.key = key.to_vec();
+        // 171 is 0xab in decimal
+        assert!(!format!("{:?}", params).contains("171"));
+    }
+
+    #[test]
+    #[should_panic(expected = "node offset too large")]
+    fn blake2s_node_offset_limit() {
+        let mut params = Blake2Params::new(32);
+        params.node_offset = 1 << 48;
+        BLAKE2s::with_params(&params);
+    }
+}
@@ -0,0 +1,646 @@
+// BLAKE3, with keyed hashing, key derivation and extendable output.
+//
+// Input is split into 1 KiB chunks, each hashed with its index as a counter,
+// and the chaining values of the chunks are combined by a binary tree of
+// parent nodes. Whole subtrees don't depend on each other, so large inputs
+// can be hashed on several threads at once.
+
+use std::cmp;
+use std::fmt;
+use std::io::{self, Write};
+use std::thread;
+use byteorder::{ByteOrder, LittleEndian};
+use utils::zeroize::Zeroize;
+use super::{DigestAlgorithm, XofAlgorithm, XofReader};
+
+const BLOCK_LEN: usize = 64;
+const CHUNK_LEN: usize = 1024;
+
+// Domain separation flags
+const CHUNK_START: u32 = 1 << 0;
+const CHUNK_END: u32 = 1 << 1;
+const PARENT: u32 = 1 << 2;
+const ROOT: u32 = 1 << 3;
+const KEYED_HASH: u32 = 1 << 4;
+const DERIVE_KEY_CONTEXT: u32 = 1 << 5;
+const DERIVE_KEY_MATERIAL: u32 = 1 << 6;
+
+const IV: [u32; 8] = [
+    0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a,
+    0x510e527f, 0x9b05688c, 0x1f83d9ab, 0x5be0cd19
+];
+
+// Applied to the message words between rounds
+const MSG_PERMUTATION: [usize; 16] = [2, 6, 3, 10, 7, 0, 4, 13, 1, 11, 12, 5, 9, 14, 15, 8];
+
+// Subtrees smaller than this are hashed on the current thread, since starting
+// another would cost more than it saves
+const MIN_PARALLEL_LEN: usize = 64*CHUNK_LEN;
+
+fn compress(cv: &[u32; 8], block: &[u32; 16], counter: u64, block_len: u32, flags: u32) -> [u32; 16] {
+    let mut v = [
+        cv[0], cv[1], cv[2], cv[3], cv[4], cv[5], cv[6], cv[7],
+        IV[0], IV[1], IV[2], IV[3], counter as u32, (counter >> 32) as u32, block_len, flags
+    ];
+    let mut m = *block;
+
+    let mut g = |a: usize, b: usize, c: usize, d: usize, x: u32, y: u32| {
+        v[a] = v[a].wrapping_add(v[b]).wrapping_add(x);
+        v[d] = (v[d] ^ v[a]).rotate_right(16);
+        v[c] = v[c].wrapping_add(v[d]);
+        v[b] = (v[b] ^ v[c]).rotate_right(12);
+        v[a] = v[a].wrapping_add(v[b]).wrapping_add(y);
+        v[d] = (v[d] ^ v[a]).rotate_right(8);
+        v[c] = v[c].wrapping_add(v[d]);
+        v[b] = (v[b] ^ v[c]).rotate_right(7);
+    };
+    for round in 0..7 {
+        // Columns, then diagonals
+        g(0, 4,  8, 12, m[ 0], m[ 1]);
+        g(1, 5,  9, 13, m[ 2], m[ 3]);
+        g(2, 6, 10, 14, m[ 4], m[ 5]);
+        g(3, 7, 11, 15, m[ 6], m[ 7]);
+        g(0, 5, 10, 15, m[ 8], m[ 9]);
+        g(1, 6, 11, 12, m[10], m[11]);
+        g(2, 7,  8, 13, m[12], m[13]);
+        g(3, 4,  9, 14, m[14], m[15]);
+
+        if round < 6 {
+            let mut permuted = [0; 16];
+            for (word, &i) in permuted.iter_mut().zip(&MSG_PERMUTATION) {
+                *word = m[i];
+            }
+            m = permuted;
+        }
+    }
+
+    // The second half is only used for extended output
+    for (i, &h) in cv.iter().enumerate() {
+        v[i] ^= v[i + 8];
+        v[i + 8] ^= h;
+    }
+    m.zeroize();
+    v
+}
+
+fn first_8_words(words: &[u32; 16]) -> [u32; 8] {
+    let mut result = [0; 8];
+    result.copy_from_slice(&words[..8]);
+    result
+}
+
+// Inputs to the last compression of a chunk or parent node, kept so that it
+// can either produce a chaining value or be repeated as the root
+#[derive(Clone)]
+struct Output {
+    cv: [u32; 8],
+    block: [u32; 16],
+    counter: u64,
+    block_len: u32,
+    flags: u32
+}
+
+impl Output {
+    fn chaining_value(&self) -> [u32; 8] {
+        first_8_words(&compress(&self.cv, &self.block, self.counter, self.block_len, self.flags))
+    }
+
+    // Block `counter` of the output stream, when this is the root node
+    fn root_block(&self, counter: u64) -> [u8; BLOCK_LEN] {
+        let mut words = compress(&self.cv, &self.block, counter, self.block_len, self.flags | ROOT);
+        let mut result = [0; BLOCK_LEN];
+        LittleEndian::write_u32_into(&words, &mut result);
+        words.zeroize();
+        result
+    }
+}
+
+impl Zeroize for Output {
+    fn zeroize(&mut self) {
+        self.cv.zeroize();
+        self.block.zeroize();
+    }
+}
+
+impl Drop for Output {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
+fn parent_output(left: &[u32; 8], right: &[u32; 8], key: &[u32; 8], flags: u32) -> Output {
+    let mut block = [0; 16];
+    block[..8].copy_from_slice(left);
+    block[8..].copy_from_slice(right);
+    Output {
+        cv: *key,
+        block,
+        counter: 0,
+        block_len: BLOCK_LEN as u32,
+        flags: flags | PARENT
+    }
+}
+
+#[derive(Clone)]
+struct ChunkState {
+    cv: [u32; 8],
+    counter: u64,
+    // The last block seen, which isn't compressed until more input arrives
+    block: [u8; BLOCK_LEN],
+    block_len: usize,
+    blocks_compressed: usize,
+    flags: u32
+}
+
+impl ChunkState {
+    fn new(key: &[u32; 8], counter: u64, flags: u32) -> Self {
+        ChunkState {
+            cv: *key,
+            counter,
+            block: [0; BLOCK_LEN],
+            block_len: 0,
+            blocks_compressed: 0,
+            flags
+        }
+    }
+
+    fn len(&self) -> usize {
+        BLOCK_LEN*self.blocks_compressed + self.block_len
+    }
+
+    fn start_flag(&self) -> u32 {
+        if self.blocks_compressed == 0 { CHUNK_START } else { 0 }
+    }
+
+    fn block_words(&self) -> [u32; 16] {
+        let mut words = [0; 16];
+        LittleEndian::read_u32_into(&self.block, &mut words);
+        words
+    }
+
+    fn update(&mut self, mut input: &[u8]) {
+        while !input.is_empty() {
+            if self.block_len == BLOCK_LEN {
+                let mut words = self.block_words();
+                let flags = self.flags | self.start_flag();
+                self.cv = first_8_words(&compress(&self.cv, &words, self.counter, BLOCK_LEN as u32, flags));
+                self.blocks_compressed += 1;
+                self.block = [0; BLOCK_LEN];
+                self.block_len = 0;
+                words.zeroize();
+            }
+            let n = cmp::min(BLOCK_LEN - self.block_len, input.len());
+            self.block[self.block_len..self.block_len + n].copy_from_slice(&input[..n]);
+            self.block_len += n;
+            input = &input[n..];
+        }
+    }
+
+    // The last block is zero padded, with its length passed separately
+    fn output(&self) -> Output {
+        Output {
+            cv: self.cv,
+            block: self.block_words(),
+            counter: self.counter,
+            block_len: self.block_len as u32,
+            flags: self.flags | self.start_flag() | CHUNK_END
+        }
+    }
+}
+
+impl Zeroize for ChunkState {
+    fn zeroize(&mut self) {
+        self.cv.zeroize();
+        self.block.zeroize();
+        self.block_len.zeroize();
+    }
+}
+
+impl Drop for ChunkState {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
+fn chunk_cv(key: &[u32; 8], input: &[u8], counter: u64, flags: u32) -> [u32; 8] {
+    let mut state = ChunkState::new(key, counter, flags);
+    state.update(input);
+    state.output().chaining_value()
+}
+
+// Chaining values of the two halves of `input`, which must be a power of two
+// number of whole chunks, at least two, starting at chunk `counter`. While
+// more than one thread is allowed, the halves are hashed on separate threads.
+fn subtree_children(key: &[u32; 8], input: &[u8], counter: u64, flags: u32, threads: usize) -> ([u32; 8], [u32; 8]) {
+    let (left, right) = input.split_at(input.len()/2);
+    let right_counter = counter + (left.len()/CHUNK_LEN) as u64;
+    if threads > 1 && input.len() >= MIN_PARALLEL_LEN {
+        thread::scope(|scope| {
+            let left_cv = scope.spawn(move || subtree_cv(key, left, counter, flags, threads/2));
+            let right_cv = subtree_cv(key, right, right_counter, flags, threads - threads/2);
+            (left_cv.join().unwrap(), right_cv)
+        })
+    } else {
+        (subtree_cv(key, left, counter, flags, 1), subtree_cv(key, right, right_counter, flags, 1))
+    }
+}
+
+fn subtree_cv(key: &[u32; 8], input: &[u8], counter: u64, flags: u32, threads: usize) -> [u32; 8] {
+    if input.len() <= CHUNK_LEN {
+        chunk_cv(key, input, counter, flags)
+    } else {
+        let (left, right) = subtree_children(key, input, counter, flags, threads);
+        parent_output(&left, &right, key, flags).chaining_value()
+    }
+}
+
+// BLAKE3 in any of its three modes. The `DigestAlgorithm` implementation
+// gives the default 32 bytes of output, and `finalize_xof` any amount.
+#[derive(Clone)]
+pub struct BLAKE3 {
+    key: [u32; 8],
+    chunk: ChunkState,
+    // Chaining values of completed subtrees, largest first
+    cv_stack: Vec<[u32; 8]>,
+    flags: u32
+}
+
+impl BLAKE3 {
+    fn with_key(key: [u32; 8], flags: u32) -> Self {
+        BLAKE3 {
+            key,
+            chunk: ChunkState::new(&key, 0, flags),
+            cv_stack: Vec::new(),
+            flags
+        }
+    }
+
+    pub fn new_keyed(key: &[u8; 32]) -> Self {
+        let mut words = [0; 8];
+        LittleEndian::read_u32_into(key, &mut words);
+        let result = BLAKE3::with_key(words, KEYED_HASH);
+        words.zeroize();
+        result
+    }
+
+    // Derives keys from the key material passed to `update`. The context
+    // should be a hardcoded string unique to the application and purpose.
+    pub fn new_derive_key(context: &str) -> Self {
+        let mut context_state = BLAKE3::with_key(IV, DERIVE_KEY_CONTEXT);
+        context_state.update(context.as_bytes());
+        let mut context_key = [0; 32];
+        context_state.finalize_xof().squeeze(&mut context_key);
+
+        let mut words = [0; 8];
+        LittleEndian::read_u32_into(&context_key, &mut words);
+        let result = BLAKE3::with_key(words, DERIVE_KEY_MATERIAL);
+        context_key.zeroize();
+        words.zeroize();
+        result
+    }
+
+    pub fn update(&mut self, input: &[u8]) {
+        self.update_with_threads(input, 1);
+    }
+
+    // Same result as `update`, but large inputs are split between as many
+    // threads as the system can run at once
+    pub fn update_parallel(&mut self, input: &[u8]) {
+        let threads = thread::available_parallelism().map_or(1, |n| n.get());
+        self.update_with_threads(input, threads);
+    }
+
+    fn update_with_threads(&mut self, mut input: &[u8], threads: usize) {
+        // Fill any partial chunk first, keeping it unless more input follows
+        if self.chunk.len() > 0 {
+            let n = cmp::min(CHUNK_LEN - self.chunk.len(), input.len());
+            self.chunk.update(&input[..n]);
+            input = &input[n..];
+            if input.is_empty() {
+                return;
+            }
+            let counter = self.chunk.counter;
+            let cv = self.chunk.output().chaining_value();
+            self.push_cv(cv, counter);
+            self.chunk = ChunkState::new(&self.key, counter + 1, self.flags);
+        }
+
+        // Hash the largest subtrees that fit, always leaving some input after
+        // them or splitting them into two, so that the root is never hashed
+        // as a chaining value
+        while input.len() > CHUNK_LEN {
+            let counter = self.chunk.counter;
+            // Subtrees must start at a multiple of their size
+            let mut subtree_chunks = (1 << input.len().ilog2())/CHUNK_LEN;
+            while !counter.is_multiple_of(subtree_chunks as u64) {
+                subtree_chunks /= 2;
+            }
+            let (subtree, rest) = input.split_at(subtree_chunks*CHUNK_LEN);
+            if subtree_chunks == 1 {
+                let cv = chunk_cv(&self.key, subtree, counter, self.flags);
+                self.push_cv(cv, counter);
+            } else {
+                let (left, right) = subtree_children(&self.key, subtree, counter, self.flags, threads);
+                self.push_cv(left, counter);
+                self.push_cv(right, counter + subtree_chunks as u64/2);
+            }
+            self.chunk = ChunkState::new(&self.key, counter + subtree_chunks as u64, self.flags);
+            input = rest;
+        }
+
+        if !input.is_empty() {
+            self.chunk.update(input);
+            let counter = self.chunk.counter;
+            self.merge_cv_stack(counter);
+        }
+    }
+
+    fn push_cv(&mut self, cv: [u32; 8], counter: u64) {
+        self.merge_cv_stack(counter);
+        self.cv_stack.push(cv);
+    }
+
+    // After `total_chunks` chunks, the stack holds one subtree for each set
+    // bit of the count. Merging is put off until more input arrives, since
+    // the last two subtrees might become the children of the root.
+    fn merge_cv_stack(&mut self, total_chunks: u64) {
+        while self.cv_stack.len() > total_chunks.count_ones() as usize {
+            let right = self.cv_stack.pop().unwrap();
+            let left = self.cv_stack.pop().unwrap();
+            let cv = parent_output(&left, &right, &self.key, self.flags).chaining_value();
+            self.cv_stack.push(cv);
+        }
+    }
+
+    fn root_output(&self) -> Output {
+        let mut remaining = self.cv_stack.len();
+        let mut output = if self.chunk.len() > 0 || remaining == 0 {
+            self.chunk.output()
+        } else {
+            remaining -= 2;
+            parent_output(&self.cv_stack[remaining], &self.cv_stack[remaining + 1], &self.key, self.flags)
+        };
+        while remaining > 0 {
+            remaining -= 1;
+            output = parent_output(&self.cv_stack[remaining], &output.chaining_value(), &self.key, self.flags);
+        }
+        output
+    }
+
+    pub fn finalize_xof(self) -> BLAKE3Reader {
+        BLAKE3Reader {
+            output: self.root_output(),
+            position: 0
+        }
+    }
+}
+
+impl Zeroize for BLAKE3 {
+    fn zeroize(&mut self) {
+        self.key.zeroize();
+        self.chunk.zeroize();
+        self.cv_stack.zeroize();
+    }
+}
+
+// The key and chaining values are secret in the keyed and key derivation
+// modes, so none of the state is shown
+impl fmt::Debug for BLAKE3 {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("BLAKE3").finish_non_exhaustive()
+    }
+}
+
+impl Drop for BLAKE3 {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
+define_digest!(BLAKE3Digest, 32);
+
+impl DigestAlgorithm for BLAKE3 {
+    type Digest = BLAKE3Digest;
+
+    fn new() -> Self {
+        BLAKE3::with_key(IV, 0)
+    }
+
+    fn block_len() -> usize {
+        BLOCK_LEN
+    }
+
+    fn update(&mut self, input: &[u8]) {
+        self.update(input);
+    }
+
+    fn digest(self) -> Self::Digest {
+        let mut result = [0; 32];
+        self.finalize_xof().squeeze(&mut result);
+        BLAKE3Digest(result)
+    }
+}
+
+impl XofAlgorithm for BLAKE3 {
+    type Reader = BLAKE3Reader;
+
+    fn new() -> Self {
+        BLAKE3::with_key(IV, 0)
+    }
+
+    fn block_len() -> usize {
+        BLOCK_LEN
+    }
+
+    fn update(&mut self, input: &[u8]) {
+        self.update(input);
+    }
+
+    fn finalize(self) -> Self::Reader {
+        self.finalize_xof()
+    }
+}
+
+impl Write for BLAKE3 {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.update(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+// Output stream of up to 2^64 bytes, produced by repeating the root
+// compression with increasing counters
+#[derive(Clone)]
+pub struct BLAKE3Reader {
+    output: Output,
+    // Bytes squeezed so far
+    position: u64
+}
+
+impl BLAKE3Reader {
+    // Moves to `position` bytes from the start of the output stream
+    pub fn seek(&mut self, position: u64) {
+        self.position = position;
+    }
+}
+
+impl fmt::Debug for BLAKE3Reader {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("BLAKE3Reader")
+            .field("position", &self.position)
+            .finish_non_exhaustive()
+    }
+}
+
+impl XofReader for BLAKE3Reader {
+    fn squeeze(&mut self, output: &mut [u8]) {
+        let mut written = 0;
+        while written < output.len() {
+            let mut block = self.output.root_block(self.position/BLOCK_LEN as u64);
+            let offset = (self.position % BLOCK_LEN as u64) as usize;
+            let n = cmp::min(BLOCK_LEN - offset, output.len() - written);
+            output[written..written + n].copy_from_slice(&block[offset..offset + n]);
+            block.zeroize();
+            self.position += n as u64;
+            written += n;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KEY: &[u8; 32] = b"whats the Elephant doing in the ";
+    const CONTEXT: &str = "BLAKE3 2019-12-27 16:29:52 test vectors context";
+
+    // Input used by the official test vectors
+    fn input(len: usize) -> Vec<u8> {
+        (0..len).map(|i| (i % 251) as u8).collect()
+    }
+
+    fn squeeze(state: BLAKE3, len: usize) -> Vec<u8> {
+        let mut result = vec![0; len];
+        state.finalize_xof().squeeze(&mut result);
+        result
+    }
+
+    // From test_vectors.json in the BLAKE3 repository, truncated to the
+    // default output length
+    #[test]
+    fn test_vectors() {
+        let cases: &[(usize, [&[u8]; 3])] = &[
+            (0, [base16!("AF1349B9F5F9A1A6A0404DEA36DCC9499BCB25C9ADC112B7CC9A93CAE41F3262"), base16!("E3971E0B0FABEFCB4D399AD1E7B2F040156DF2635A985C483DC2D3746EEEEBAB"), base16!("2CC39783C223154FEA8DFB7C1B1660F2AC2DCBD1C1DE8277B0B0DD39B7E50D7D")]),
+            (1, [base16!("2D3ADEDFF11B61F14C886E35AFA036736DCD87A74D27B5C1510225D0F592E213"), base16!("775FE3185611E92E7A484130DA7E523FD483A840B2A23C66703A0DB611BA6AAB"), base16!("B3E2E340A117A499C6CF2398A19EE0D29CCA2BB7404C73063382693BF66CB06C")]),
+            (1023, [base16!("10108970EEDA3EB932BAAC1428C7A2163B0E924C9A9E25B35BBA72B28F70BD11"), base16!("A80A8D6D282F0F3C00C83687E47F0769F96E98AB7E1F01FA46E7BCA3A44AA14D"), base16!("74A16C1C3D44368A86E1CA6DF64BE6A2F64CCE8F09220787450722D85725DEA5")]),
+            (1024, [base16!("42214739F095A406F3FC83DEB889744AC00DF831C10DAA55189B5D121C855AF7"), base16!("841359FC67A884B9C38E493C073FED9E0794B825A00C27B298C69451DC97AE40"), base16!("7356CD7720D5B66B6D0697EB3177D9F8D73A4A5C5E968896EB6A689684302706")]),
+            (1025, [base16!("D00278AE47EB27B34FAECF67B4FE263F82D5412916C1FFD97C8CB7FB814B8444"), base16!("4460A7AB30762D08BB56CFD69D4B4881285CF4441DB3FADF961439068853BB4E"), base16!("EFFAA245F065FBF82AC186839A249707C3BDDF6D3FDDA22D1B95A3C970379BCB")]),
+            (2048, [base16!("E776B6028C7CD22A4D0BA182A8BF62205D2EF576467E838ED6F2529B85FBA24A"), base16!("59F8B7DA4FDADF06DA8876AC84A2AD3077D82BD7468702EF2B33FE90207E76AE"), base16!("7B2945CB4FEF70885CC5D78A87BF6F6207DD901FF239201351FFAC04E1088A23")]),
+            (2049, [base16!("5F4D72F40D7A5F82B15CA2B2E44B1DE3C2EF86C426C95C1AF0B6879522563030"), base16!("B478C534BA234E340D881F495C99CB87E1814F2EF11B0B2897D9A5B7E43D4ACC"), base16!("2EA477C5515CC3DD606512EE72BB3E0E758CFAE7232826F35FB98CA1BCBDF273")]),
+            (3072, [base16!("B98CB0FF3623BE03326B373DE6B9095218513E64F1EE2EDD2525C7AD1E5CFFD2"), base16!("CB64D22410C13B07A3432BC2B3B52917D480BB853BADDC402B4A97D2BDAEA801"), base16!("050DF97F8C2EAD654D9BB3AB8C9178EDCD902A32F8495949FEADCC1E0480C46B")]),
+            (3073, [base16!("7124B49501012F81CC7F11CA069EC9226CECB8A2C850CFE644E327D22D3E1CD3"), base16!("EFAA885A8CB72353B7E1ACA51A9F32987E9FE4B2E09F5280CE9E68AFDFC67A4E"), base16!("72613C9EC9FF7E40F8F5C173784C532AD852E827DBA2BF85B2AB4B76F7079081")]),
+            (4096, [base16!("015094013F57A5277B59D8475C0501042C0B642E531B0A1C8F58D2163229E969"), base16!("402DDC51B86706FCCB680889ACBD6CCC8EB565B3BF45CFE4A9688AACAC4ECF4A"), base16!("1E0D7F3DB8C414C97C6307CBDA6CD27AC3B030949DA8E23BE1A1A924AD2F25B9")]),
+            (4097, [base16!("9B4052B38F1C5FC8B1F9FF7AC7B27CD242487B3D890D15C96A1C25B8AA0FB995"), base16!("738BB1931F11ED72B924880B7F923AB942362569786C335D04A3238DD6C336CF"), base16!("ACA51029626B55FDA7117B42A7C211F8C6E9BA4FE5B7A8CA922F34299500EAD8")]),
+            (5120, [base16!("9CADC15FED8B5D854562B26A9536D9707CADEDA9B143978F319AB34230535833"), base16!("A949FFEDBFE89A3930455DE816CEB7A0D9B1AB369CA7C7247C0B1FEDB78AB1E4"), base16!("7A7ACAC8A02ADCF3038D74CDD1D34527DE8A0FCC0EE3399D1262397CE5817F60")]),
+            (5121, [base16!("628BD2CB2004694ADAAB7BBD778A25DF25C47B9D4155A55F8FBD79F2FE154CFF"), base16!("ABA8BFA4B85CDCCDFAE90EAA03693D4142DAF1C0417F88B0E940846CBC29EB70"), base16!("B07F01E518E702F7CCB44A267E9E112D403A7B3F4883A47FFBED4B48339B3C34")]),
+            (6144, [base16!("3E2E5B74E048F3ADD6D21FAAB3F83AA44D3B2278AFB83B80B3C35164EBECA205"), base16!("4285F86FA222C207C73D772F345A49A2534C0473544A8584C19C3572004B31D6"), base16!("2A95BEAE63DDCE523762355CF4B9C1D8F131465780A391286A5D01ABB5683A15")]),
+            (6145, [base16!("F1323A8631446CC50536A9F705EE5CB619424D46887F3C376C695B70E0F0507F"), base16!("9149E39C029588437B438209E6949BD7ED6AAD15B3E72B17B2CFCDCB76B8D04A"), base16!("379BCC61D0051DD489F686C13DE00D5B14C505245103DC040D9E4DD1FACAB8E5")]),
+            (7168, [base16!("61DA957EC2499A95D6B8023E2B0E604EC7F6B50E80A9678B89D2628E99ADA77A"), base16!("E5E5DD6326AC71D6B4D6426830227D34D602EEA7862F521032F82B58DF415256"), base16!("11C37A112765370C94A51415D0D651190C288566E295D505DEFDAD895DAE2237")]),
+            (7169, [base16!("A003FC7A51754A9B3C7FAE0367AB3D782DCCF28855A03D435F8CFE74605E7817"), base16!("E126F784C3C2570B921D91A8F86E259C257E3AD9293B89FE67AF4357BA368821"), base16!("554B0A5EFEA9EF183F2F9B931B7497995D9EB26F5C5C6DAD2B97D62FC5AC31D9")]),
+            (8192, [base16!("AAE792484C8EFE4F19E2CA7D371D8C467FFB10748D8A5A1AE579948F718A2A63"), base16!("6B45A6D050A960536880695FE6B669C3B1704613EA705A403DE803EDA5FE56A0"), base16!("AD01D7AE4AD059B0D33BAA3C01319DCF8088094D0359E5FD45D6AEAA8B2D0C3D")]),
+            (8193, [base16!("BAB6C09CB8CE8CF459261398D2E7AEF35700BF488116CEB94A36D0F5F1B7BC3B"), base16!("4B98C32FDFAE137EB919F5B2685E85E57D178005784BDFD8C231413FF4473DD4"), base16!("AF1E0346E389B17C23200270A64AA4E1EAD98C61695D917DE7D5B00491C9B0F1")]),
+            (16384, [base16!("F875D6646DE28985646F34EE13BE9A576FD515F76B5B0A26BB324735041DDDE4"), base16!("4654F82379B3BD69642D34088ABC9841DBFB9166DB0A3FB6024FA080399C3E1D"), base16!("160E18B5878CD0DF1C3AF85EB25A0DB5344D43A6FBD7A8EF4ED98D0714C3F7E1")]),
+            (31744, [base16!("62B6960E1A44BCC1EB1A611A8D6235B6B4B78F32E7ABC4FB4C6CDCCE94895C47"), base16!("B0B4657FEBF41AC5D159ED6D11A0DDFA0DDB8BBF2F69DB396A55036A65FE9D53"), base16!("39772AEF80E0EBE60596361E45B061E8F417429D529171B6764468C22928E28E")]),
+            (102400, [base16!("BC3E3D41A1146B069ABFFAD3C0D44860CF664390AFCE4D9661F7902E7943E085"), base16!("3D43D2EBFD174FB99BC9DAE7BE22AC75F418ACFD98F80688BE402EFE1D045C54"), base16!("4652CFF7A3F385A6103B5C260FC1593E13C778DBE608EFB092FE7EE69DF6E9C6")])
+        ];
+        for &(len, [hash, keyed_hash, derive_key]) in cases {
+            let input = input(len);
+            println!("len: {}", len);
+            assert!(<BLAKE3 as DigestAlgorithm>::compute(&input).as_ref() == hash);
+
+            let mut state = BLAKE3::new_keyed(KEY);
+            state.update(&input);
+            assert!(state.digest().as_ref() == keyed_hash);
+
+            let mut state = BLAKE3::new_derive_key(CONTEXT);
+            state.update(&input);
+            assert!(state.digest().as_ref() == derive_key);
+        }
+    }
+
+    // Full extended outputs from test_vectors.json
+    #[test]
+    fn xof() {
+        let expected = base16!("AF1349B9F5F9A1A6A0404DEA36DCC9499BCB25C9ADC112B7CC9A93CAE41F3262E00F03E7B69AF26B7FAAF09FCD333050338DDFE085B8CC869CA98B206C08243A26F5487789E8F660AFE6C99EF9E0C52B92E7393024A80459CF91F476F9FFDBDA7001C22E159B402631F277CA96F2DEFDF1078282314E763699A31C5363165421CCE14D");
+        assert!(<BLAKE3 as XofAlgorithm>::compute(b"", expected.len())[..] == expected[..]);
+
+        let expected = base16!("3D43D2EBFD174FB99BC9DAE7BE22AC75F418ACFD98F80688BE402EFE1D045C54E77118F0A9A0EDA77B44F9E81268DAFD50C242AF23924803493F6D2D1E2AE7C893D58438463A4E4EE6337989EB4CDA97564D191B98891A583F6C06A0953A7520002FD401619F177CA34268403FB8194C0F658910FB55C51420A32B11F27D5431C5F10E");
+        let mut state = BLAKE3::new_keyed(KEY);
+        state.update(&input(102400));
+        assert!(squeeze(state, expected.len())[..] == expected[..]);
+
+        // Squeezing in pieces that cross block boundaries
+        let expected = base16!("EFFAA245F065FBF82AC186839A249707C3BDDF6D3FDDA22D1B95A3C970379BCB5D31013A167509E9066273AB6E2123BC835B408B067D88F96ADDB550D96B6852DAD38E320B9D940F86DB74D398C770F462118B35D2724EFA13DA97194491D96DD37C3C09CBEF665953F2EE85EC83D88B88D11547A6F911C8217CCA46DEFA2751E7F3AD");
+        let mut state = BLAKE3::new_derive_key(CONTEXT);
+        state.update(&input(1025));
+        let mut reader = state.finalize_xof();
+        let mut actual = vec![0; expected.len()];
+        for piece in actual.chunks_mut(50) {
+            reader.squeeze(piece);
+        }
+        assert!(actual[..] == expected[..]);
+
+        reader.seek(100);
+        let mut tail = [0; 31];
+        reader.squeeze(&mut tail);
+        assert!(tail[..] == expected[100..]);
+    }
+
+    #[test]
+    fn incremental() {
+        let data = input(102400);
+        let expected = <BLAKE3 as DigestAlgorithm>::compute(&data);
+        for &split in &[0, 1, 64, 1023, 1024, 1025, 3000, 4096, 65536, 102399] {
+            let mut state = <BLAKE3 as DigestAlgorithm>::new();
+            state.update(&data[..split]);
+            state.update(&data[split..]);
+            assert_eq!(state.digest(), expected);
+        }
+
+        let mut state = <BLAKE3 as DigestAlgorithm>::new();
+        for piece in data.chunks(1000) {
+            state.update(piece);
+        }
+        assert_eq!(state.digest(), expected);
+    }
+
+    #[test]
+    fn debug_hides_key() {
+        let state = BLAKE3::new_keyed(KEY);
+        assert_eq!(format!("{:?}", state), "BLAKE3 { .. }");
+        assert_eq!(format!("{:?}", state.finalize_xof()), "BLAKE3Reader { position: 0, .. }");
+    }
+
+    #[test]
+    fn parallel() {
+        let data = input(1 << 20 | 12345);
+        for &split in &[0, 1000, 1 << 16, 1 << 19] {
+            let mut serial = BLAKE3::new_keyed(KEY);
+            serial.update(&data[..split]);
+            serial.update(&data[split..]);
+
+            let mut parallel = BLAKE3::new_keyed(KEY);
+            parallel.update_with_threads(&data[..split], 4);
+            parallel.update_with_threads(&data[split..], 4);
+            assert_eq!(serial.digest(), parallel.digest());
+        }
+
+        let mut state = <BLAKE3 as DigestAlgorithm>::new();
+        state.update_parallel(&input(102400));
+        assert!(state.digest().as_ref() == base16!("BC3E3D41A1146B069ABFFAD3C0D44860CF664390AFCE4D9661F7902E7943E085"));
+    }
+}
@@ -0,0 +1,371 @@
+// Support for the NIST CAVP response files (`.rsp`) used by the Secure Hash
+// Algorithm Validation System, e.g. `SHA256ShortMsg.rsp`,
+// `SHA256LongMsg.rsp` and `SHA256Monte.rsp`.
+
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+
+use utils::hex;
+use super::DigestAlgorithm;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RspError {
+    // A line that is not blank, a comment, a `[section]` or a `name = value` pair
+    InvalidLine(usize)
+}
+
+impl fmt::Display for RspError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            RspError::InvalidLine(line) => write!(f, "invalid response file syntax on line {}", line)
+        }
+    }
+}
+
+impl Error for RspError {}
+
+// A group of `name = value` lines, separated from other records by blank lines
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Record {
+    // Line number of the first field
+    pub line: usize,
+    // Parameters from the enclosing `[name = value]` section headers
+    pub params: Vec<(String, String)>,
+    pub fields: Vec<(String, String)>
+}
+
+fn lookup<'a>(pairs: &'a [(String, String)], name: &str) -> Option<&'a str> {
+    pairs.iter().find(|pair| pair.0 == name).map(|pair| &pair.1[..])
+}
+
+impl Record {
+    pub fn get(&self, name: &str) -> Option<&str> {
+        lookup(&self.fields, name)
+    }
+
+    pub fn param(&self, name: &str) -> Option<&str> {
+        lookup(&self.params, name)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RspFile {
+    pub records: Vec<Record>
+}
+
+fn split_pair(s: &str) -> Option<(String, String)> {
+    let mut parts = s.splitn(2, '=');
+    let name = parts.next()?.trim();
+    let value = parts.next()?.trim();
+    if name.is_empty() {
+        return None;
+    }
+    Some((name.to_string(), value.to_string()))
+}
+
+impl RspFile {
+    pub fn parse(input: &str) -> Result<RspFile, RspError> {
+        let mut records = Vec::new();
+        let mut params = Vec::new();
+        // Whether the current parameters apply to a previous record, in
+        // which case the next section header starts a new set
+        let mut params_used = false;
+        let mut current: Option<Record> = None;
+
+        for (i, line) in input.lines().enumerate() {
+            let line_number = i + 1;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                records.extend(current.take());
+            } else if line.starts_with('[') && line.ends_with(']') {
+                records.extend(current.take());
+                if params_used {
+                    params.clear();
+                    params_used = false;
+                }
+                // Some files use bare section names, such as `[SHA-1]`
+                let inner = &line[1..line.len() - 1];
+                params.push(split_pair(inner).unwrap_or_else(|| (inner.trim().to_string(), String::new())));
+            } else {
+                let pair = split_pair(line).ok_or(RspError::InvalidLine(line_number))?;
+                params_used = true;
+                current.get_or_insert_with(|| Record {
+                    line: line_number,
+                    params: params.clone(),
+                    fields: Vec::new()
+                }).fields.push(pair);
+            }
+        }
+        records.extend(current);
+        Ok(RspFile {
+            records
+        })
+    }
+}
+
+impl FromStr for RspFile {
+    type Err = RspError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        RspFile::parse(s)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FailureKind {
+    // The computed digest differs from the expected one
+    Mismatch { expected: Vec<u8>, actual: Vec<u8> },
+    // A required field is absent
+    MissingField(&'static str),
+    // A field could not be decoded
+    InvalidField(&'static str),
+    // A Monte Carlo checkpoint that does not follow on from the previous one
+    OutOfSequence
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Failure {
+    // Line number of the failing record
+    pub line: usize,
+    pub kind: FailureKind
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Report {
+    pub passed: usize,
+    // Vectors that cannot be run, such as bit-oriented messages
+    pub skipped: usize,
+    pub failures: Vec<Failure>
+}
+
+impl Report {
+    pub fn is_success(&self) -> bool {
+        self.failures.is_empty()
+    }
+
+    fn check(&mut self, record: &Record, result: Result<bool, FailureKind>) {
+        match result {
+            Ok(true) => self.passed += 1,
+            Ok(false) => self.skipped += 1,
+            Err(kind) => self.failures.push(Failure {
+                line: record.line,
+                kind
+            })
+        }
+    }
+}
+
+fn field<'a>(record: &'a Record, name: &'static str) -> Result<&'a str, FailureKind> {
+    record.get(name).ok_or(FailureKind::MissingField(name))
+}
+
+fn hex_field(record: &Record, name: &'static str) -> Result<Vec<u8>, FailureKind> {
+    hex::decode(field(record, name)?).ok_or(FailureKind::InvalidField(name))
+}
+
+fn number_field(record: &Record, name: &'static str) -> Result<usize, FailureKind> {
+    field(record, name)?.parse().map_err(|_| FailureKind::InvalidField(name))
+}
+
+fn compare(expected: Vec<u8>, actual: &[u8]) -> Result<bool, FailureKind> {
+    if expected[..] == *actual {
+        Ok(true)
+    } else {
+        Err(FailureKind::Mismatch {
+            expected,
+            actual: actual.to_vec()
+        })
+    }
+}
+
+// Runs the vectors from a ShortMsg or LongMsg file. `Len` is in bits, so
+// vectors which don't cover a whole number of bytes are skipped.
+pub fn run_msg<D: DigestAlgorithm>(file: &RspFile) -> Report {
+    let mut report = Report::default();
+    for record in &file.records {
+        let result = (|| {
+            let len = number_field(record, "Len")?;
+            let msg = hex_field(record, "Msg")?;
+            let expected = hex_field(record, "MD")?;
+            if len % 8 != 0 {
+                return Ok(false);
+            }
+            // An empty message is written as a single zero byte
+            let msg = msg.get(..len/8).ok_or(FailureKind::InvalidField("Msg"))?;
+            compare(expected, D::compute(msg).as_ref())
+        })();
+        report.check(record, result);
+    }
+    report
+}
+
+// One checkpoint of the SHA-1/SHA-2 Monte Carlo test: each message is the
+// concatenation of the previous three digests.
+fn monte_step<D: DigestAlgorithm>(seed: &[u8]) -> Vec<u8> {
+    let mut md = [seed.to_vec(), seed.to_vec(), seed.to_vec()];
+    for _ in 3..1003 {
+        let mut state = D::new();
+        for m in &md {
+            state.update(m);
+        }
+        md.rotate_left(1);
+        md[2] = state.digest().as_ref().to_vec();
+    }
+    md[2].clone()
+}
+
+// Runs a Monte file: a `Seed` record followed by `COUNT`/`MD` checkpoints,
+// each seeded with the digest from the one before.
+pub fn run_monte<D: DigestAlgorithm>(file: &RspFile) -> Report {
+    let mut report = Report::default();
+    let mut seed = None;
+    let mut count = 0;
+    for record in &file.records {
+        if record.get("Seed").is_some() {
+            match hex_field(record, "Seed") {
+                Ok(value) => seed = Some(value),
+                Err(kind) => report.check(record, Err(kind))
+            }
+            count = 0;
+            continue;
+        }
+        let result = (|| {
+            let expected = hex_field(record, "MD")?;
+            let current = seed.take().ok_or(FailureKind::MissingField("Seed"))?;
+            // Later checkpoints follow on from the expected value, even if
+            // this one fails, so that a single failure is not repeated for
+            // the rest of the file
+            seed = Some(expected.clone());
+            count += 1;
+            if number_field(record, "COUNT")? != count - 1 {
+                return Err(FailureKind::OutOfSequence);
+            }
+            let actual = monte_step::<D>(&current);
+            compare(expected, &actual)
+        })();
+        report.check(record, result);
+    }
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::sha1::SHA1;
+    use super::super::sha2::SHA256;
+    use super::*;
+
+    // Excerpt of SHA256ShortMsg.rsp
+    const SHORT_MSG: &str = "
+#  CAVS 11.0
+#  \"SHA-256 ShortMsg\" information
+#  Generated on Tue Mar 15 08:23:38 2011
+
+[L = 32]
+
+Len = 0
+Msg = 00
+MD = e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855
+
+Len = 8
+Msg = d3
+MD = 28969cdfa74a12c82f3bad960b0b000aca2ac329deea5c2328ebc6f2ba9802c1
+
+Len = 16
+Msg = 11af
+MD = 5ca7133fa735326081558ac312c620eeca9970d1e70a4b95533d956f072d1f98
+
+Len = 24
+Msg = b4190e
+MD = dff2e73091f6c05e528896c4c831b9448653dc2ff043528f6769437bc7b975c2
+";
+
+    // Excerpt of SHA1Monte.rsp
+    const MONTE: &str = "
+#  CAVS 11.0
+#  \"SHA-1 Monte\" information
+
+[L = 20]
+
+Seed = dd4df644eaf3d85bace2b21accaa22b28821f5cd
+
+COUNT = 0
+MD = 11f5c38b4479d4ad55cb69fadf62de0b036d5163
+
+COUNT = 1
+MD = 5c26de848c21586bec36995809cb02d3677423d9
+
+COUNT = 2
+MD = 453b5fcf263d01c891d7897d4013990f7c1fb0ab
+";
+
+    #[test]
+    fn parse() {
+        let file: RspFile = SHORT_MSG.parse().unwrap();
+        assert_eq!(file.records.len(), 4);
+        let record = &file.records[1];
+        assert_eq!(record.line, 12);
+        assert_eq!(record.param("L"), Some("32"));
+        assert_eq!(record.get("Msg"), Some("d3"));
+        assert_eq!(record.get("Count"), None);
+
+        assert_eq!("[L = 20]\n\nnot a field\n".parse::<RspFile>(), Err(RspError::InvalidLine(3)));
+    }
+
+    #[test]
+    fn short_msg() {
+        let file = SHORT_MSG.parse().unwrap();
+        let report = run_msg::<SHA256>(&file);
+        assert!(report.is_success());
+        assert_eq!(report.passed, 4);
+
+        // Failures are reported against the vector's line
+        let report = run_msg::<SHA1>(&file);
+        assert_eq!(report.passed, 0);
+        assert_eq!(report.failures.len(), 4);
+        assert_eq!(report.failures[1].line, 12);
+        match report.failures[1].kind {
+            FailureKind::Mismatch { ref actual, .. } => assert!(actual[..] == *SHA1::compute(&[0xd3]).as_ref()),
+            ref other => panic!("unexpected failure {:?}", other)
+        }
+    }
+
+    #[test]
+    fn bit_oriented_skipped() {
+        let file = "Len = 5\nMsg = 68\nMD = 00\n".parse().unwrap();
+        let report = run_msg::<SHA256>(&file);
+        assert_eq!(report, Report { passed: 0, skipped: 1, failures: Vec::new() });
+    }
+
+    #[test]
+    fn monte() {
+        let report = run_monte::<SHA1>(&MONTE.parse().unwrap());
+        assert!(report.is_success());
+        assert_eq!(report.passed, 3);
+
+        let out_of_sequence = MONTE.replace("COUNT = 1", "COUNT = 7");
+        let report = run_monte::<SHA1>(&out_of_sequence.parse().unwrap());
+        assert_eq!(report.passed, 2);
+        assert_eq!(report.failures.len(), 1);
+        assert_eq!(report.failures[0].kind, FailureKind::OutOfSequence);
+
+        // Excerpt of SHA256Monte.rsp
+        let file = "
+[L = 32]
+
+Seed = 6d1e72ad03ddeb5de891e572e2396f8da015d899ef0e79503152d6010a3fe691
+
+COUNT = 0
+MD = e93c330ae5447738c8aa85d71a6c80f2a58381d05872d26bdd39f1fcd4f2b788
+
+COUNT = 1
+MD = 2e78f8c8772ea7c9331d41ed3f9cdf27d8f514a99342ee766ee3b8b0d0b121c0
+
+COUNT = 2
+MD = d6a23dff1b7f2eddc1a212f8a218397523a799b07386a30692fd6fe9d2bf0944
+".parse().unwrap();
+        let report = run_monte::<SHA256>(&file);
+        assert!(report.is_success());
+        assert_eq!(report.passed, 3);
+    }
+}
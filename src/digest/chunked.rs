@@ -1,32 +1,109 @@
-use std::fmt::Debug;
-use std::io::Cursor;
+use std::error::Error;
+use std::fmt::{self, Debug};
+use std::io::{self, Cursor, Read, Write};
 
-use byteorder::{BigEndian, WriteBytesExt};
+use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
 
 use utils::slice_ext::SliceExt;
+use utils::zeroize::Zeroize;
 use super::{Digest, DigestAlgorithm};
 
 
-pub trait Chunk: AsMut<[u8]> + Debug + Clone {
+pub trait Chunk: AsRef<[u8]> + AsMut<[u8]> + Debug + Clone + Zeroize {
     fn new() -> Self;
     fn len() -> usize;
 }
 
-pub trait ChunkedDigestAlgorithm {
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Endianness {
+    Big,
+    Little
+}
+
+// Merkle-Damgård strengthening: the message is followed by a single 1 bit,
+// then zeros, then the message length in bits in a field at the end of the
+// final chunk.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LengthPadding {
+    // Width of the length field in bytes
+    pub length_bytes: usize,
+    pub endianness: Endianness
+}
+
+impl LengthPadding {
+    pub const BIG_ENDIAN_64: LengthPadding = LengthPadding { length_bytes: 8, endianness: Endianness::Big };
+    pub const BIG_ENDIAN_128: LengthPadding = LengthPadding { length_bytes: 16, endianness: Endianness::Big };
+    pub const LITTLE_ENDIAN_64: LengthPadding = LengthPadding { length_bytes: 8, endianness: Endianness::Little };
+
+    fn write_length(&self, field: &mut [u8], ml: u128) {
+        for (i, byte) in field.iter_mut().enumerate() {
+            let shift = match self.endianness {
+                Endianness::Big => 8*(self.length_bytes - 1 - i),
+                Endianness::Little => 8*i
+            };
+            *byte = (ml >> shift) as u8;
+        }
+    }
+}
+
+// Implementations should also wipe their state in `Drop`
+pub trait ChunkedDigestAlgorithm: Zeroize {
     type Chunk: Chunk;
     type Digest: Digest;
 
+    const PADDING: LengthPadding;
+    // Identifies the algorithm in exported states
+    const NAME: &'static str;
+
     fn new() -> Self;
     fn update_chunk(&mut self, chunk: &[u8]);
+    // Processes several whole chunks in order. Some backends work on more
+    // than one chunk at a time.
+    fn update_chunks(&mut self, chunks: &[u8]) {
+        for chunk in chunks.chunks_exact(Self::Chunk::len()) {
+            self.update_chunk(chunk);
+        }
+    }
     fn digest(self) -> Self::Digest;
+
+    // Serializes the intermediate hash value
+    fn export_state(&self, out: &mut Vec<u8>);
+    // Inverse of `export_state`, returning `None` if the state is malformed
+    fn import_state(state: &[u8]) -> Option<Self> where Self: Sized;
+}
+
+// Version of the exported state format
+const STATE_VERSION: u8 = 1;
+const STATE_MAGIC: &[u8; 4] = b"srcl";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StateError {
+    InvalidFormat,
+    UnsupportedVersion,
+    // The state was exported from a different algorithm
+    AlgorithmMismatch,
+    InvalidState
 }
 
+impl fmt::Display for StateError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            StateError::InvalidFormat => write!(f, "malformed digest state"),
+            StateError::UnsupportedVersion => write!(f, "unsupported digest state version"),
+            StateError::AlgorithmMismatch => write!(f, "digest state belongs to a different algorithm"),
+            StateError::InvalidState => write!(f, "inconsistent digest state")
+        }
+    }
+}
+
+impl Error for StateError {}
+
 #[derive(Debug, Clone)]
 pub struct ChunkedDigestWrapper<Inner: ChunkedDigestAlgorithm> {
-    // Hash state
-    inner: Inner,
-    // Message length
-    ml: u64,
+    // Hash state, which is only taken out by `finish`
+    inner: Option<Inner>,
+    // Message length (in bits)
+    ml: u128,
     // In-progress chunk
     buffer_len: usize,
     buffer: Inner::Chunk
@@ -37,20 +114,30 @@ impl<Inner: ChunkedDigestAlgorithm> DigestAlgorithm for ChunkedDigestWrapper<Inn
 
     fn new() -> Self {
         ChunkedDigestWrapper {
-            inner: Inner::new(),
+            inner: Some(Inner::new()),
             ml: 0,
             buffer_len: 0,
             buffer: Inner::Chunk::new()
         }
     }
 
+    fn block_len() -> usize {
+        Inner::Chunk::len()
+    }
+
     fn update(&mut self, mut input: &[u8]) {
+        if input.is_empty() {
+            return;
+        }
+        assert!(self.ml.is_multiple_of(8), "input after a partial byte");
+
         // Update message length (in bits)
-        self.ml += (input.len() as u64)*8;
+        self.ml += (input.len() as u128)*8;
 
         let buffer_len = self.buffer_len;
         let chunk_len = Inner::Chunk::len();
-        let mut buffer = self.buffer.as_mut();
+        let inner = self.inner.as_mut().unwrap();
+        let buffer = self.buffer.as_mut();
 
         // If buffer is already partially filled
         if buffer_len > 0 {
@@ -65,41 +152,261 @@ impl<Inner: ChunkedDigestAlgorithm> DigestAlgorithm for ChunkedDigestWrapper<Inn
                 // Fill the remaining space with input data and process the chunk
                 buffer[buffer_len..].copy_from_slice(&input[0..remaining]);
                 input = &input[remaining..];
-                self.inner.update_chunk(buffer);
+                inner.update_chunk(buffer);
             }
         }
 
         // Buffer is empty at this point, so can directly read chunks from input
-        while input.len() >= chunk_len {
-            self.inner.update_chunk(&input[0..chunk_len]);
-            input = &input[chunk_len..];
-        }
+        let whole = input.len() - input.len() % chunk_len;
+        inner.update_chunks(&input[..whole]);
+        input = &input[whole..];
 
         // Partially fill the buffer with any left-over input
         buffer[0..input.len()].copy_from_slice(input);
         self.buffer_len = input.len();
     }
 
-    fn digest(mut self) -> Self::Digest {
+    fn digest(self) -> Self::Digest {
+        self.finish().digest()
+    }
+}
+
+impl<Inner: ChunkedDigestAlgorithm> ChunkedDigestWrapper<Inner> {
+    // Pads the message and processes the final chunks, returning the
+    // resulting hash state
+    pub(super) fn finish(mut self) -> Inner {
         let chunk_len = Inner::Chunk::len();
-        let mut buffer = self.buffer.as_mut();
+        let length_bytes = Inner::PADDING.length_bytes;
+        let mut inner = self.inner.take().unwrap();
+        let buffer = self.buffer.as_mut();
 
-        // Append 1 bit
-        buffer[self.buffer_len] = 0x80;
+        // Append 1 bit, following any bits of a partial final byte
+        let extra_bits = (self.ml % 8) as u32;
+        let partial = if extra_bits > 0 { buffer[self.buffer_len] } else { 0 };
+        buffer[self.buffer_len] = partial | (0x80 >> extra_bits);
         self.buffer_len += 1;
 
         // Ensure there's room to write the message length
-        if self.buffer_len + 8 > chunk_len {
+        if self.buffer_len + length_bytes > chunk_len {
             buffer[self.buffer_len..].fill_copy(0);
             self.buffer_len = 0;
-            self.inner.update_chunk(buffer);
+            inner.update_chunk(buffer);
         }
 
         // Fill remaining space with zeros followed by the message length
-        buffer[self.buffer_len..chunk_len-8].fill_copy(0);
-        Cursor::new(&mut buffer[chunk_len-8..]).write_u64::<BigEndian>(self.ml).unwrap();
+        buffer[self.buffer_len..chunk_len-length_bytes].fill_copy(0);
+        Inner::PADDING.write_length(&mut buffer[chunk_len-length_bytes..], self.ml);
         // Final chunk computation
-        self.inner.update_chunk(buffer);
-        self.inner.digest()
+        inner.update_chunk(buffer);
+        // The rest of the wrapper is also wiped when dropped, but the padded
+        // chunk holds message data, so clear it straight away
+        self.buffer.zeroize();
+        inner
+    }
+}
+
+impl<Inner: ChunkedDigestAlgorithm> Zeroize for ChunkedDigestWrapper<Inner> {
+    fn zeroize(&mut self) {
+        if let Some(inner) = &mut self.inner {
+            inner.zeroize();
+        }
+        self.ml.zeroize();
+        self.buffer_len.zeroize();
+        self.buffer.zeroize();
+    }
+}
+
+impl<Inner: ChunkedDigestAlgorithm> Drop for ChunkedDigestWrapper<Inner> {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
+// Exported states have the following layout:
+//
+// - magic bytes `srcl`
+// - format version (1 byte)
+// - algorithm name length (1 byte), followed by the name
+// - message length in bits (16 bytes, big-endian)
+// - in-progress chunk length (2 bytes, big-endian), followed by the chunk data,
+//   including any partial final byte
+// - intermediate hash value, as written by `ChunkedDigestAlgorithm::export_state`
+impl<Inner: ChunkedDigestAlgorithm> ChunkedDigestWrapper<Inner> {
+    // Appends the first `bit_len` bits of `data`, taking the bits of each
+    // byte from most significant to least significant. If `bit_len` is not a
+    // multiple of 8, this must be the last input before `digest`.
+    pub fn update_bits(&mut self, data: &[u8], bit_len: usize) {
+        assert!(bit_len <= data.len()*8, "bit_len exceeds the length of data");
+        assert!(bit_len == 0 || self.ml.is_multiple_of(8), "input after a partial byte");
+        let extra_bits = bit_len % 8;
+        self.update(&data[..bit_len/8]);
+        if extra_bits > 0 {
+            // Unused low bits are cleared so that padding can be OR-ed in
+            self.buffer.as_mut()[self.buffer_len] = data[bit_len/8] & !(0xff >> extra_bits);
+            self.ml += extra_bits as u128;
+        }
+    }
+
+    // Number of buffered bytes, counting a partial final byte
+    fn stored_len(&self) -> usize {
+        self.buffer_len + !self.ml.is_multiple_of(8) as usize
+    }
+
+    pub fn export_state(&self) -> Vec<u8> {
+        let name = Inner::NAME.as_bytes();
+        let mut result = Vec::new();
+        result.extend_from_slice(STATE_MAGIC);
+        result.push(STATE_VERSION);
+        result.push(name.len() as u8);
+        result.extend_from_slice(name);
+        result.write_u64::<BigEndian>((self.ml >> 64) as u64).unwrap();
+        result.write_u64::<BigEndian>(self.ml as u64).unwrap();
+        result.write_u16::<BigEndian>(self.stored_len() as u16).unwrap();
+        result.extend_from_slice(&self.buffer.as_ref()[..self.stored_len()]);
+        self.inner.as_ref().unwrap().export_state(&mut result);
+        result
+    }
+
+    pub fn import_state(state: &[u8]) -> Result<Self, StateError> {
+        let mut reader = Cursor::new(state);
+        let mut magic = [0; 4];
+        reader.read_exact(&mut magic).map_err(|_| StateError::InvalidFormat)?;
+        if &magic != STATE_MAGIC {
+            return Err(StateError::InvalidFormat);
+        }
+        let version = reader.read_u8().map_err(|_| StateError::InvalidFormat)?;
+        if version != STATE_VERSION {
+            return Err(StateError::UnsupportedVersion);
+        }
+        let mut name = vec![0; reader.read_u8().map_err(|_| StateError::InvalidFormat)? as usize];
+        reader.read_exact(&mut name).map_err(|_| StateError::InvalidFormat)?;
+        if name != Inner::NAME.as_bytes() {
+            return Err(StateError::AlgorithmMismatch);
+        }
+        let ml_high = reader.read_u64::<BigEndian>().map_err(|_| StateError::InvalidFormat)?;
+        let ml_low = reader.read_u64::<BigEndian>().map_err(|_| StateError::InvalidFormat)?;
+        let ml = ((ml_high as u128) << 64) | ml_low as u128;
+        let stored_len = reader.read_u16::<BigEndian>().map_err(|_| StateError::InvalidFormat)? as usize;
+
+        // The buffer must hold exactly the bytes not yet processed
+        let chunk_len = Inner::Chunk::len();
+        let buffer_len = ((ml/8) % chunk_len as u128) as usize;
+        if stored_len != buffer_len + !ml.is_multiple_of(8) as usize {
+            return Err(StateError::InvalidState);
+        }
+        let mut buffer = Inner::Chunk::new();
+        reader.read_exact(&mut buffer.as_mut()[..stored_len]).map_err(|_| StateError::InvalidFormat)?;
+        // The unused low bits of a partial final byte must be clear, since
+        // `finish` ORs the padding into them
+        if !ml.is_multiple_of(8) && buffer.as_ref()[stored_len - 1] & (0xff >> (ml % 8)) != 0 {
+            return Err(StateError::InvalidState);
+        }
+
+        let offset = reader.position() as usize;
+        let inner = Inner::import_state(&state[offset..]).ok_or(StateError::InvalidState)?;
+        Ok(ChunkedDigestWrapper {
+            inner: Some(inner),
+            ml,
+            buffer_len,
+            buffer
+        })
+    }
+}
+
+impl<Inner: ChunkedDigestAlgorithm> Write for ChunkedDigestWrapper<Inner> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if !buf.is_empty() && !self.ml.is_multiple_of(8) {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "input after a partial byte"));
+        }
+        self.update(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::DigestAlgorithm;
+    use super::super::sha2::{SHA224, SHA256};
+    use super::*;
+
+    fn partial_state() -> Vec<u8> {
+        let mut state = SHA256::new();
+        state.update(&[b'a'; 100]);
+        state.export_state()
+    }
+
+    #[test]
+    fn export_import_round_trip() {
+        let mut state = SHA256::import_state(&partial_state()).unwrap();
+        state.update(&[b'a'; 50]);
+        assert!(state.digest().as_ref() == SHA256::compute(&[b'a'; 150]).as_ref());
+    }
+
+    #[test]
+    fn export_import_partial_byte() {
+        let mut state = SHA256::new();
+        state.update_bits(&[b'a'; 65], 64*8 + 5);
+        let state = SHA256::import_state(&state.export_state()).unwrap();
+
+        let mut expected = SHA256::new();
+        expected.update(&[b'a'; 64]);
+        expected.update_bits(b"a", 5);
+        assert!(state.digest() == expected.digest());
+    }
+
+    #[test]
+    fn import_partial_byte_with_low_bits_set() {
+        let mut state = SHA256::new();
+        state.update_bits(&[0x68], 5);
+        let mut exported = state.export_state();
+        assert!(SHA256::import_state(&exported).is_ok());
+
+        // The partial byte comes just before the 32-byte hash state
+        let offset = exported.len() - 33;
+        exported[offset] |= 0x01;
+        assert_eq!(SHA256::import_state(&exported).unwrap_err(), StateError::InvalidState);
+    }
+
+    #[test]
+    fn write_after_partial_byte() {
+        let mut state = SHA256::new();
+        state.update_bits(&[0x68], 5);
+        state.update(b"");
+        assert_eq!(state.write(b"").unwrap(), 0);
+        assert_eq!(state.write(b"a").unwrap_err().kind(), io::ErrorKind::InvalidInput);
+
+        let mut expected = SHA256::new();
+        expected.update_bits(&[0x68], 5);
+        assert!(state.digest() == expected.digest());
+    }
+
+    #[test]
+    fn import_wrong_algorithm() {
+        assert_eq!(SHA224::import_state(&partial_state()).unwrap_err(), StateError::AlgorithmMismatch);
+    }
+
+    #[test]
+    fn import_malformed() {
+        let state = partial_state();
+        assert_eq!(SHA256::import_state(&state[..state.len() - 1]).unwrap_err(), StateError::InvalidState);
+        assert_eq!(SHA256::import_state(&state[..20]).unwrap_err(), StateError::InvalidFormat);
+
+        let mut bad_magic = state.clone();
+        bad_magic[0] ^= 1;
+        assert_eq!(SHA256::import_state(&bad_magic).unwrap_err(), StateError::InvalidFormat);
+
+        let mut bad_version = state.clone();
+        bad_version[4] = STATE_VERSION + 1;
+        assert_eq!(SHA256::import_state(&bad_version).unwrap_err(), StateError::UnsupportedVersion);
+
+        // Claim one more buffered byte than the message length allows
+        let mut bad_buffer_len = state.clone();
+        let offset = 4 + 1 + 1 + "sha256".len() + 16 + 1;
+        bad_buffer_len[offset] += 1;
+        assert_eq!(SHA256::import_state(&bad_buffer_len).unwrap_err(), StateError::InvalidState);
     }
 }
@@ -0,0 +1,253 @@
+// CRC-32 (IEEE 802.3, as used by zlib, gzip and PNG) and CRC-32C
+// (Castagnoli, as used by iSCSI and ext4). These detect accidental
+// corruption only: anyone can change the input without changing the checksum.
+//
+// Both use the reflected bit order with an initial value and final XOR of
+// all ones. Digests hold the checksum as a big-endian number, so they read
+// the same as the usual hex form.
+
+use std::io::{self, Write};
+use byteorder::{ByteOrder, LittleEndian};
+use super::DigestAlgorithm;
+
+// Reflected forms of the generator polynomials
+const CRC32_POLY: u32 = 0xedb88320;
+const CRC32C_POLY: u32 = 0x82f63b78;
+
+// `tables[0]` is the usual byte-at-a-time table. `tables[k]` gives the effect
+// of a byte followed by `k` zero bytes, so eight bytes can be looked up at once.
+const fn crc_tables(poly: u32) -> [[u32; 256]; 8] {
+    let mut tables = [[0; 256]; 8];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = i as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 1 != 0 { (crc >> 1) ^ poly } else { crc >> 1 };
+            bit += 1;
+        }
+        tables[0][i] = crc;
+        i += 1;
+    }
+
+    let mut k = 1;
+    while k < 8 {
+        i = 0;
+        while i < 256 {
+            let prev = tables[k - 1][i];
+            tables[k][i] = (prev >> 8) ^ tables[0][(prev & 0xff) as usize];
+            i += 1;
+        }
+        k += 1;
+    }
+    tables
+}
+
+static CRC32_TABLES: [[u32; 256]; 8] = crc_tables(CRC32_POLY);
+static CRC32C_TABLES: [[u32; 256]; 8] = crc_tables(CRC32C_POLY);
+
+fn update_bytewise(mut crc: u32, table: &[u32; 256], input: &[u8]) -> u32 {
+    for &byte in input {
+        crc = (crc >> 8) ^ table[((crc ^ byte as u32) & 0xff) as usize];
+    }
+    crc
+}
+
+fn update_slice_by_8(mut crc: u32, tables: &[[u32; 256]; 8], input: &[u8]) -> u32 {
+    let mut words = input.chunks_exact(8);
+    for word in &mut words {
+        let lo = crc ^ LittleEndian::read_u32(&word[..4]);
+        let hi = LittleEndian::read_u32(&word[4..]);
+        crc = tables[7][(lo & 0xff) as usize]
+            ^ tables[6][((lo >> 8) & 0xff) as usize]
+            ^ tables[5][((lo >> 16) & 0xff) as usize]
+            ^ tables[4][(lo >> 24) as usize]
+            ^ tables[3][(hi & 0xff) as usize]
+            ^ tables[2][((hi >> 8) & 0xff) as usize]
+            ^ tables[1][((hi >> 16) & 0xff) as usize]
+            ^ tables[0][(hi >> 24) as usize];
+    }
+    update_bytewise(crc, &tables[0], words.remainder())
+}
+
+// Product of two polynomials modulo the generator, all in reflected form
+fn mul_mod(poly: u32, a: u32, mut b: u32) -> u32 {
+    let mut result = 0;
+    let mut bit = 1 << 31;
+    while bit != 0 {
+        if a & bit != 0 {
+            result ^= b;
+        }
+        b = if b & 1 != 0 { (b >> 1) ^ poly } else { b >> 1 };
+        bit >>= 1;
+    }
+    result
+}
+
+// Appending `len2` bytes multiplies the register by x^(8*len2), and the
+// contribution of the second part's own bytes is just `crc2`. The initial
+// value and final XOR cancel out, since they are the same for both parts.
+fn combine(poly: u32, crc1: u32, crc2: u32, mut len2: u64) -> u32 {
+    // x^0 and x^8, reflected
+    let mut power = 1 << 31;
+    let mut square = 1 << 23;
+    while len2 != 0 {
+        if len2 & 1 != 0 {
+            power = mul_mod(poly, power, square);
+        }
+        square = mul_mod(poly, square, square);
+        len2 >>= 1;
+    }
+    mul_mod(poly, power, crc1) ^ crc2
+}
+
+macro_rules! define_crc32 {
+    ($name:ident, $digest:ident, $tables:ident, $poly:ident) => {
+        define_digest!($digest, 4);
+
+        impl $digest {
+            // The checksum as a number
+            pub fn value(&self) -> u32 {
+                u32::from_be_bytes(self.0)
+            }
+        }
+
+        #[derive(Debug, Clone)]
+        pub struct $name {
+            // The register, which is the checksum with the final XOR undone
+            crc: u32
+        }
+
+        impl $name {
+            // Continues from the checksum of some earlier input
+            pub fn from_value(value: u32) -> Self {
+                $name {
+                    crc: !value
+                }
+            }
+
+            // Checksum of the input so far
+            pub fn value(&self) -> u32 {
+                !self.crc
+            }
+
+            // Table-driven update, a byte at a time. `update` gives the same
+            // result several times faster on longer inputs.
+            pub fn update_bytewise(&mut self, input: &[u8]) {
+                self.crc = update_bytewise(self.crc, &$tables[0], input);
+            }
+
+            // Checksum of the concatenation of two inputs, from the checksums
+            // of each and the length of the second
+            pub fn combine(value1: u32, value2: u32, len2: u64) -> u32 {
+                combine($poly, value1, value2, len2)
+            }
+        }
+
+        impl DigestAlgorithm for $name {
+            type Digest = $digest;
+
+            fn new() -> Self {
+                $name::from_value(0)
+            }
+
+            // There are no blocks, but `Hmac` needs room for a hashed key
+            fn block_len() -> usize {
+                4
+            }
+
+            // Slice-by-8
+            fn update(&mut self, input: &[u8]) {
+                self.crc = update_slice_by_8(self.crc, &$tables, input);
+            }
+
+            fn digest(self) -> Self::Digest {
+                $digest(self.value().to_be_bytes())
+            }
+        }
+
+        impl Write for $name {
+            fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+                self.update(buf);
+                Ok(buf.len())
+            }
+
+            fn flush(&mut self) -> io::Result<()> {
+                Ok(())
+            }
+        }
+    }
+}
+
+define_crc32!(CRC32, CRC32Digest, CRC32_TABLES, CRC32_POLY);
+define_crc32!(CRC32C, CRC32CDigest, CRC32C_TABLES, CRC32C_POLY);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test<A: DigestAlgorithm>(input: &[u8], expected: &[u8]) {
+        let actual = A::compute(input);
+        println!("A: {:?}", actual.as_ref());
+        println!("B: {:?}", expected);
+        assert!(actual.as_ref() == expected)
+    }
+
+    #[test]
+    fn crc32() {
+        // Check value from the CRC catalogue
+        test::<CRC32>(b"123456789", base16!("CBF43926"));
+        test::<CRC32>(b"", base16!("00000000"));
+        test::<CRC32>(b"The quick brown fox jumps over the lazy dog", base16!("414FA339"));
+
+        // Checked against zlib
+        let data: Vec<u8> = (0..100000u64).map(|i| (i*i % 251) as u8).collect();
+        test::<CRC32>(&data, base16!("CE5022E2"));
+    }
+
+    #[test]
+    fn crc32c() {
+        test::<CRC32C>(b"123456789", base16!("E3069283"));
+
+        // RFC 3720 appendix B.4
+        test::<CRC32C>(&[0; 32], base16!("8A9136AA"));
+        test::<CRC32C>(&[0xff; 32], base16!("62A8AB43"));
+        test::<CRC32C>(&(0..32).collect::<Vec<u8>>(), base16!("46DD794E"));
+        test::<CRC32C>(&(0..32).rev().collect::<Vec<u8>>(), base16!("113FDB5C"));
+    }
+
+    #[test]
+    fn bytewise() {
+        let data: Vec<u8> = (0..1000u32).map(|i| (i*i % 251) as u8).collect();
+        for &len in &[0, 1, 7, 8, 9, 63, 64, 1000] {
+            let mut state = CRC32::new();
+            state.update_bytewise(&data[..len]);
+            assert_eq!(state.value(), CRC32::compute(&data[..len]).value());
+
+            let mut state = CRC32C::new();
+            state.update_bytewise(&data[..len]);
+            assert_eq!(state.value(), CRC32C::compute(&data[..len]).value());
+        }
+    }
+
+    #[test]
+    fn combine() {
+        let data: Vec<u8> = (0..1000u32).map(|i| (i*i % 251) as u8).collect();
+        for &split in &[0, 1, 5, 8, 100, 999, 1000] {
+            let (a, b) = data.split_at(split);
+            assert_eq!(
+                CRC32::combine(CRC32::compute(a).value(), CRC32::compute(b).value(), b.len() as u64),
+                CRC32::compute(&data).value()
+            );
+            assert_eq!(
+                CRC32C::combine(CRC32C::compute(a).value(), CRC32C::compute(b).value(), b.len() as u64),
+                CRC32C::compute(&data).value()
+            );
+        }
+
+        // Resuming from an earlier checksum
+        let mut state = CRC32::from_value(CRC32::compute(&data[..300]).value());
+        state.update(&data[300..]);
+        assert_eq!(state.digest(), CRC32::compute(&data));
+    }
+}
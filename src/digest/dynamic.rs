@@ -0,0 +1,278 @@
+use std::fmt::{self, Debug};
+
+use super::{Digest, DigestAlgorithm};
+use super::blake2::{BLAKE2b256, BLAKE2b512, BLAKE2s256};
+use super::md5::MD5;
+use super::ripemd160::RIPEMD160;
+use super::sha1::SHA1;
+use super::sha2::{SHA224, SHA256, SHA384, SHA512, SHA512T224, SHA512T256};
+use super::sha3::{SHA3_224, SHA3_256, SHA3_384, SHA3_512};
+use super::sm3::SM3;
+
+// Object-safe counterpart to `DigestAlgorithm`, for when the algorithm is
+// only known at runtime
+pub trait DynDigest: Debug {
+    fn update(&mut self, input: &[u8]);
+    fn finalize(self: Box<Self>) -> Box<[u8]>;
+    // Discards any input so far, as if newly constructed
+    fn reset(&mut self);
+    fn output_len(&self) -> usize;
+    fn block_len(&self) -> usize;
+    fn box_clone(&self) -> Box<dyn DynDigest>;
+}
+
+impl<D: DigestAlgorithm + Debug + Clone + 'static> DynDigest for D {
+    fn update(&mut self, input: &[u8]) {
+        DigestAlgorithm::update(self, input);
+    }
+
+    fn finalize(self: Box<Self>) -> Box<[u8]> {
+        self.digest().into()
+    }
+
+    fn reset(&mut self) {
+        *self = D::new();
+    }
+
+    fn output_len(&self) -> usize {
+        D::Digest::LEN
+    }
+
+    fn block_len(&self) -> usize {
+        D::block_len()
+    }
+
+    fn box_clone(&self) -> Box<dyn DynDigest> {
+        Box::new(self.clone())
+    }
+}
+
+impl Clone for Box<dyn DynDigest> {
+    fn clone(&self) -> Self {
+        self.box_clone()
+    }
+}
+
+// The built-in digest algorithms
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AnyDigest {
+    Md5,
+    Ripemd160,
+    Sha1,
+    Sha224,
+    Sha256,
+    Sha384,
+    Sha512,
+    Sha512T224,
+    Sha512T256,
+    Sha3_224,
+    Sha3_256,
+    Sha3_384,
+    Sha3_512,
+    Sm3,
+    Blake2b256,
+    Blake2b512,
+    Blake2s256
+}
+
+impl AnyDigest {
+    pub const ALL: &'static [AnyDigest] = &[
+        AnyDigest::Md5,
+        AnyDigest::Ripemd160,
+        AnyDigest::Sha1,
+        AnyDigest::Sha224,
+        AnyDigest::Sha256,
+        AnyDigest::Sha384,
+        AnyDigest::Sha512,
+        AnyDigest::Sha512T224,
+        AnyDigest::Sha512T256,
+        AnyDigest::Sha3_224,
+        AnyDigest::Sha3_256,
+        AnyDigest::Sha3_384,
+        AnyDigest::Sha3_512,
+        AnyDigest::Sm3,
+        AnyDigest::Blake2b256,
+        AnyDigest::Blake2b512,
+        AnyDigest::Blake2s256
+    ];
+
+    pub fn create(self) -> Box<dyn DynDigest> {
+        match self {
+            AnyDigest::Md5 => Box::new(MD5::new()),
+            AnyDigest::Ripemd160 => Box::new(RIPEMD160::new()),
+            AnyDigest::Sha1 => Box::new(SHA1::new()),
+            AnyDigest::Sha224 => Box::new(SHA224::new()),
+            AnyDigest::Sha256 => Box::new(SHA256::new()),
+            AnyDigest::Sha384 => Box::new(SHA384::new()),
+            AnyDigest::Sha512 => Box::new(SHA512::new()),
+            AnyDigest::Sha512T224 => Box::new(SHA512T224::new()),
+            AnyDigest::Sha512T256 => Box::new(SHA512T256::new()),
+            AnyDigest::Sha3_224 => Box::new(SHA3_224::new()),
+            AnyDigest::Sha3_256 => Box::new(SHA3_256::new()),
+            AnyDigest::Sha3_384 => Box::new(SHA3_384::new()),
+            AnyDigest::Sha3_512 => Box::new(SHA3_512::new()),
+            AnyDigest::Sm3 => Box::new(SM3::new()),
+            AnyDigest::Blake2b256 => Box::new(BLAKE2b256::new()),
+            AnyDigest::Blake2b512 => Box::new(BLAKE2b512::new()),
+            AnyDigest::Blake2s256 => Box::new(BLAKE2s256::new())
+        }
+    }
+
+    pub fn compute(self, input: &[u8]) -> Box<[u8]> {
+        let mut state = self.create();
+        state.update(input);
+        state.finalize()
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            AnyDigest::Md5 => "md5",
+            AnyDigest::Ripemd160 => "ripemd160",
+            AnyDigest::Sha1 => "sha1",
+            AnyDigest::Sha224 => "sha224",
+            AnyDigest::Sha256 => "sha256",
+            AnyDigest::Sha384 => "sha384",
+            AnyDigest::Sha512 => "sha512",
+            AnyDigest::Sha512T224 => "sha512/224",
+            AnyDigest::Sha512T256 => "sha512/256",
+            AnyDigest::Sha3_224 => "sha3-224",
+            AnyDigest::Sha3_256 => "sha3-256",
+            AnyDigest::Sha3_384 => "sha3-384",
+            AnyDigest::Sha3_512 => "sha3-512",
+            AnyDigest::Sm3 => "sm3",
+            AnyDigest::Blake2b256 => "blake2b256",
+            AnyDigest::Blake2b512 => "blake2b512",
+            AnyDigest::Blake2s256 => "blake2s256"
+        }
+    }
+
+    // Dotted form of the ASN.1 object identifier
+    pub fn oid(self) -> &'static str {
+        match self {
+            AnyDigest::Md5 => "1.2.840.113549.2.5",
+            AnyDigest::Ripemd160 => "1.3.36.3.2.1",
+            AnyDigest::Sha1 => "1.3.14.3.2.26",
+            AnyDigest::Sha224 => "2.16.840.1.101.3.4.2.4",
+            AnyDigest::Sha256 => "2.16.840.1.101.3.4.2.1",
+            AnyDigest::Sha384 => "2.16.840.1.101.3.4.2.2",
+            AnyDigest::Sha512 => "2.16.840.1.101.3.4.2.3",
+            AnyDigest::Sha512T224 => "2.16.840.1.101.3.4.2.5",
+            AnyDigest::Sha512T256 => "2.16.840.1.101.3.4.2.6",
+            AnyDigest::Sha3_224 => "2.16.840.1.101.3.4.2.7",
+            AnyDigest::Sha3_256 => "2.16.840.1.101.3.4.2.8",
+            AnyDigest::Sha3_384 => "2.16.840.1.101.3.4.2.9",
+            AnyDigest::Sha3_512 => "2.16.840.1.101.3.4.2.10",
+            AnyDigest::Sm3 => "1.2.156.10197.1.401",
+            AnyDigest::Blake2b256 => "1.3.6.1.4.1.1722.12.2.1.8",
+            AnyDigest::Blake2b512 => "1.3.6.1.4.1.1722.12.2.1.16",
+            AnyDigest::Blake2s256 => "1.3.6.1.4.1.1722.12.2.2.8"
+        }
+    }
+
+    // Code from the multicodec table
+    pub fn multihash(self) -> u64 {
+        match self {
+            AnyDigest::Md5 => 0xd5,
+            AnyDigest::Ripemd160 => 0x1053,
+            AnyDigest::Sha1 => 0x11,
+            AnyDigest::Sha224 => 0x1013,
+            AnyDigest::Sha256 => 0x12,
+            AnyDigest::Sha384 => 0x20,
+            AnyDigest::Sha512 => 0x13,
+            AnyDigest::Sha512T224 => 0x1014,
+            AnyDigest::Sha512T256 => 0x1015,
+            AnyDigest::Sha3_224 => 0x17,
+            AnyDigest::Sha3_256 => 0x16,
+            AnyDigest::Sha3_384 => 0x15,
+            AnyDigest::Sha3_512 => 0x14,
+            AnyDigest::Sm3 => 0x534d,
+            AnyDigest::Blake2b256 => 0xb220,
+            AnyDigest::Blake2b512 => 0xb240,
+            AnyDigest::Blake2s256 => 0xb260
+        }
+    }
+
+    pub fn output_len(self) -> usize {
+        match self {
+            AnyDigest::Md5 => 16,
+            AnyDigest::Ripemd160 | AnyDigest::Sha1 => 20,
+            AnyDigest::Sha224 | AnyDigest::Sha512T224 | AnyDigest::Sha3_224 => 28,
+            AnyDigest::Sha256 | AnyDigest::Sha512T256 | AnyDigest::Sha3_256 | AnyDigest::Sm3
+                | AnyDigest::Blake2b256 | AnyDigest::Blake2s256 => 32,
+            AnyDigest::Sha384 | AnyDigest::Sha3_384 => 48,
+            AnyDigest::Sha512 | AnyDigest::Sha3_512 | AnyDigest::Blake2b512 => 64
+        }
+    }
+
+    pub fn block_len(self) -> usize {
+        self.create().block_len()
+    }
+
+    // Names are matched case-insensitively, and the hyphenated forms used by
+    // the standards ("SHA-512/256") are also accepted
+    pub fn from_name(name: &str) -> Option<AnyDigest> {
+        let name = name.to_ascii_lowercase();
+        let name = if name.starts_with("sha-") {
+            name.replacen("sha-", "sha", 1)
+        } else {
+            name
+        };
+        let name = match &name[..] {
+            "ripemd-160" => "ripemd160",
+            "sha512-224" => "sha512/224",
+            "sha512-256" => "sha512/256",
+            other => other
+        };
+        AnyDigest::ALL.iter().cloned().find(|a| a.name() == name)
+    }
+
+    pub fn from_oid(oid: &str) -> Option<AnyDigest> {
+        AnyDigest::ALL.iter().cloned().find(|a| a.oid() == oid)
+    }
+
+    pub fn from_multihash(code: u64) -> Option<AnyDigest> {
+        AnyDigest::ALL.iter().cloned().find(|a| a.multihash() == code)
+    }
+}
+
+impl fmt::Display for AnyDigest {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::DigestAlgorithm;
+    use super::super::sha2::SHA512T256;
+    use super::*;
+
+    #[test]
+    fn lookup() {
+        for &algorithm in AnyDigest::ALL {
+            assert_eq!(AnyDigest::from_name(algorithm.name()), Some(algorithm));
+            assert_eq!(AnyDigest::from_oid(algorithm.oid()), Some(algorithm));
+            assert_eq!(AnyDigest::from_multihash(algorithm.multihash()), Some(algorithm));
+
+            let state = algorithm.create();
+            assert_eq!(state.output_len(), algorithm.output_len());
+            assert_eq!(algorithm.compute(b"").len(), algorithm.output_len());
+        }
+        assert_eq!(AnyDigest::from_name("SHA-512/256"), Some(AnyDigest::Sha512T256));
+        assert_eq!(AnyDigest::from_name("SHA3-256"), Some(AnyDigest::Sha3_256));
+        assert_eq!(AnyDigest::from_name("sha3"), None);
+        assert_eq!(AnyDigest::Sha3_256.block_len(), 136);
+    }
+
+    #[test]
+    fn dyn_digest() {
+        let mut state = AnyDigest::from_name("sha512/256").unwrap().create();
+        state.update(b"ab");
+        let mut other = state.clone();
+        state.update(b"c");
+        other.update(b"d");
+
+        assert!(&state.finalize()[..] == SHA512T256::compute(b"abc").as_ref());
+        assert!(&other.finalize()[..] == SHA512T256::compute(b"abd").as_ref());
+    }
+}
@@ -0,0 +1,196 @@
+use std::fmt;
+use std::io::{self, Write};
+
+use utils::constant_time;
+use utils::slice_ext::SliceExt;
+use super::DigestAlgorithm;
+
+const IPAD: u8 = 0x36;
+const OPAD: u8 = 0x5c;
+
+// HMAC as defined in RFC 2104
+#[derive(Clone)]
+pub struct Hmac<D: DigestAlgorithm> {
+    // Hash state after absorbing the key XOR ipad
+    inner: D,
+    // Hash state after absorbing the key XOR opad
+    outer: D
+}
+
+impl<D: DigestAlgorithm> Hmac<D> {
+    pub fn new(key: &[u8]) -> Self {
+        let block_len = D::block_len();
+        let mut padded_key = vec![0; block_len];
+
+        // Keys longer than a block are hashed first, shorter keys are zero-padded
+        if key.len() > block_len {
+            let digest = D::compute(key);
+            let digest = digest.as_ref();
+            padded_key[..digest.len()].copy_from_slice(digest);
+        } else {
+            padded_key[..key.len()].copy_from_slice(key);
+        }
+
+        for byte in &mut padded_key {
+            *byte ^= IPAD;
+        }
+        let mut inner = D::new();
+        inner.update(&padded_key);
+
+        for byte in &mut padded_key {
+            *byte ^= IPAD ^ OPAD;
+        }
+        let mut outer = D::new();
+        outer.update(&padded_key);
+
+        // Don't leave the key around
+        padded_key.fill_copy(0);
+
+        Hmac {
+            inner,
+            outer
+        }
+    }
+
+    pub fn update(&mut self, input: &[u8]) {
+        self.inner.update(input);
+    }
+
+    pub fn finalize(self) -> D::Digest {
+        let Hmac { inner, mut outer } = self;
+        outer.update(inner.digest().as_ref());
+        outer.digest()
+    }
+
+    // Checks the tag in constant time. The tag must be the full digest length.
+    pub fn verify(self, tag: &[u8]) -> bool {
+        constant_time::eq(self.finalize().as_ref(), tag)
+    }
+
+    pub fn compute(key: &[u8], input: &[u8]) -> D::Digest {
+        let mut state = Self::new(key);
+        state.update(input);
+        state.finalize()
+    }
+}
+
+// Leaves out the keyed states, which are enough to compute MACs
+impl<D: DigestAlgorithm> fmt::Debug for Hmac<D> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Hmac").finish_non_exhaustive()
+    }
+}
+
+impl<D: DigestAlgorithm> Write for Hmac<D> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.update(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::DigestAlgorithm;
+    use super::super::sha1::SHA1;
+    use super::super::sha2::{SHA224, SHA256, SHA384, SHA512};
+    use super::*;
+
+    fn test<A: DigestAlgorithm>(key: &[u8], input: &[u8], expected: &[u8]) {
+        let actual = Hmac::<A>::compute(key, input);
+        println!("A: {:?}", actual);
+        println!("B: {:?}", expected);
+        assert!(&actual.as_ref()[..expected.len()] == expected);
+    }
+
+    // RFC 2202 section 3
+    #[test]
+    fn hmac_sha1() {
+        test::<SHA1>(&[0x0b; 20], b"Hi There", base16!("B617318655057264E28BC0B6FB378C8EF146BE00"));
+        test::<SHA1>(b"Jefe", b"what do ya want for nothing?", base16!("EFFCDF6AE5EB2FA2D27416D5F184DF9C259A7C79"));
+        test::<SHA1>(&[0xaa; 20], &[0xdd; 50], base16!("125D7342B9AC11CD91A39AF48AA17B4F63F175D3"));
+        test::<SHA1>(base16!("0102030405060708090A0B0C0D0E0F10111213141516171819"), &[0xcd; 50], base16!("4C9007F4026250C6BC8414F9BF50C86C2D7235DA"));
+        test::<SHA1>(&[0x0c; 20], b"Test With Truncation", base16!("4C1A03424B55E07FE7F27BE1"));
+        test::<SHA1>(&[0xaa; 80], b"Test Using Larger Than Block-Size Key - Hash Key First", base16!("AA4AE5E15272D00E95705637CE8A3B55ED402112"));
+        test::<SHA1>(&[0xaa; 80], b"Test Using Larger Than Block-Size Key and Larger Than One Block-Size Data", base16!("E8E99D0F45237D786D6BBAA7965C7808BBFF1A91"));
+    }
+
+    // RFC 4231 section 4
+    const RFC4231_KEY_6: &[u8] = &[0xaa; 131];
+    const RFC4231_DATA_6: &[u8] = b"Test Using Larger Than Block-Size Key - Hash Key First";
+    const RFC4231_DATA_7: &[u8] = b"This is a test using a larger than block-size key and a larger than block-size data. The key needs to be hashed before being used by the HMAC algorithm.";
+
+    #[test]
+    fn hmac_sha224() {
+        test::<SHA224>(&[0x0b; 20], b"Hi There", base16!("896FB1128ABBDF196832107CD49DF33F47B4B1169912BA4F53684B22"));
+        test::<SHA224>(b"Jefe", b"what do ya want for nothing?", base16!("A30E01098BC6DBBF45690F3A7E9E6D0F8BBEA2A39E6148008FD05E44"));
+        test::<SHA224>(&[0xaa; 20], &[0xdd; 50], base16!("7FB3CB3588C6C1F6FFA9694D7D6AD2649365B0C1F65D69D1EC8333EA"));
+        test::<SHA224>(base16!("0102030405060708090A0B0C0D0E0F10111213141516171819"), &[0xcd; 50], base16!("6C11506874013CAC6A2ABC1BB382627CEC6A90D86EFC012DE7AFEC5A"));
+        test::<SHA224>(&[0x0c; 20], b"Test With Truncation", base16!("0E2AEA68A90C8D37C988BCDB9FCA6FA8"));
+        test::<SHA224>(RFC4231_KEY_6, RFC4231_DATA_6, base16!("95E9A0DB962095ADAEBE9B2D6F0DBCE2D499F112F2D2B7273FA6870E"));
+        test::<SHA224>(RFC4231_KEY_6, RFC4231_DATA_7, base16!("3A854166AC5D9F023F54D517D0B39DBD946770DB9C2B95C9F6F565D1"));
+    }
+
+    #[test]
+    fn hmac_sha256() {
+        test::<SHA256>(&[0x0b; 20], b"Hi There", base16!("B0344C61D8DB38535CA8AFCEAF0BF12B881DC200C9833DA726E9376C2E32CFF7"));
+        test::<SHA256>(b"Jefe", b"what do ya want for nothing?", base16!("5BDCC146BF60754E6A042426089575C75A003F089D2739839DEC58B964EC3843"));
+        test::<SHA256>(&[0xaa; 20], &[0xdd; 50], base16!("773EA91E36800E46854DB8EBD09181A72959098B3EF8C122D9635514CED565FE"));
+        test::<SHA256>(base16!("0102030405060708090A0B0C0D0E0F10111213141516171819"), &[0xcd; 50], base16!("82558A389A443C0EA4CC819899F2083A85F0FAA3E578F8077A2E3FF46729665B"));
+        test::<SHA256>(&[0x0c; 20], b"Test With Truncation", base16!("A3B6167473100EE06E0C796C2955552B"));
+        test::<SHA256>(RFC4231_KEY_6, RFC4231_DATA_6, base16!("60E431591EE0B67F0D8A26AACBF5B77F8E0BC6213728C5140546040F0EE37F54"));
+        test::<SHA256>(RFC4231_KEY_6, RFC4231_DATA_7, base16!("9B09FFA71B942FCB27635FBCD5B0E944BFDC63644F0713938A7F51535C3A35E2"));
+    }
+
+    #[test]
+    fn hmac_sha384() {
+        test::<SHA384>(&[0x0b; 20], b"Hi There", base16!("AFD03944D84895626B0825F4AB46907F15F9DADBE4101EC682AA034C7CEBC59CFAEA9EA9076EDE7F4AF152E8B2FA9CB6"));
+        test::<SHA384>(b"Jefe", b"what do ya want for nothing?", base16!("AF45D2E376484031617F78D2B58A6B1B9C7EF464F5A01B47E42EC3736322445E8E2240CA5E69E2C78B3239ECFAB21649"));
+        test::<SHA384>(&[0xaa; 20], &[0xdd; 50], base16!("88062608D3E6AD8A0AA2ACE014C8A86F0AA635D947AC9FEBE83EF4E55966144B2A5AB39DC13814B94E3AB6E101A34F27"));
+        test::<SHA384>(base16!("0102030405060708090A0B0C0D0E0F10111213141516171819"), &[0xcd; 50], base16!("3E8A69B7783C25851933AB6290AF6CA77A9981480850009CC5577C6E1F573B4E6801DD23C4A7D679CCF8A386C674CFFB"));
+        test::<SHA384>(&[0x0c; 20], b"Test With Truncation", base16!("3ABF34C3503B2A23A46EFC619BAEF897"));
+        test::<SHA384>(RFC4231_KEY_6, RFC4231_DATA_6, base16!("4ECE084485813E9088D2C63A041BC5B44F9EF1012A2B588F3CD11F05033AC4C60C2EF6AB4030FE8296248DF163F44952"));
+        test::<SHA384>(RFC4231_KEY_6, RFC4231_DATA_7, base16!("6617178E941F020D351E2F254E8FD32C602420FEB0B8FB9ADCCEBB82461E99C5A678CC31E799176D3860E6110C46523E"));
+    }
+
+    #[test]
+    fn hmac_sha512() {
+        test::<SHA512>(&[0x0b; 20], b"Hi There", base16!("87AA7CDEA5EF619D4FF0B4241A1D6CB02379F4E2CE4EC2787AD0B30545E17CDEDAA833B7D6B8A702038B274EAEA3F4E4BE9D914EEB61F1702E696C203A126854"));
+        test::<SHA512>(b"Jefe", b"what do ya want for nothing?", base16!("164B7A7BFCF819E2E395FBE73B56E0A387BD64222E831FD610270CD7EA2505549758BF75C05A994A6D034F65F8F0E6FDCAEAB1A34D4A6B4B636E070A38BCE737"));
+        test::<SHA512>(&[0xaa; 20], &[0xdd; 50], base16!("FA73B0089D56A284EFB0F0756C890BE9B1B5DBDD8EE81A3655F83E33B2279D39BF3E848279A722C806B485A47E67C807B946A337BEE8942674278859E13292FB"));
+        test::<SHA512>(base16!("0102030405060708090A0B0C0D0E0F10111213141516171819"), &[0xcd; 50], base16!("B0BA465637458C6990E5A8C5F61D4AF7E576D97FF94B872DE76F8050361EE3DBA91CA5C11AA25EB4D679275CC5788063A5F19741120C4F2DE2ADEBEB10A298DD"));
+        test::<SHA512>(&[0x0c; 20], b"Test With Truncation", base16!("415FAD6271580A531D4179BC891D87A6"));
+        test::<SHA512>(RFC4231_KEY_6, RFC4231_DATA_6, base16!("80B24263C7C1A3EBB71493C1DD7BE8B49B46D1F41B4AEEC1121B013783F8F3526B56D037E05F2598BD0FD2215D6A1E5295E64F73F63F0AEC8B915A985D786598"));
+        test::<SHA512>(RFC4231_KEY_6, RFC4231_DATA_7, base16!("E37B6A775DC87DBAA4DFA9F96E5E3FFDDEBD71F8867289865DF5A32D20CDC944B6022CAC3C4982B10D5EEB55C3E4DE15134676FB6DE0446065C97440FA8C6A58"));
+    }
+
+    #[test]
+    fn streaming() {
+        let mut state = Hmac::<SHA256>::new(RFC4231_KEY_6);
+        for part in RFC4231_DATA_7.chunks(7) {
+            state.update(part);
+        }
+        let expected = Hmac::<SHA256>::compute(RFC4231_KEY_6, RFC4231_DATA_7);
+        assert!(state.finalize().as_ref() == expected.as_ref());
+    }
+
+    #[test]
+    fn verify() {
+        let tag = Hmac::<SHA1>::compute(b"Jefe", b"what do ya want for nothing?");
+        let mut state = Hmac::<SHA1>::new(b"Jefe");
+        state.update(b"what do ya want for nothing?");
+        assert!(state.clone().verify(tag.as_ref()));
+        assert!(!state.clone().verify(&tag.as_ref()[..10]));
+        let mut bad_tag = tag.as_ref().to_vec();
+        bad_tag[19] ^= 1;
+        assert!(!state.verify(&bad_tag));
+    }
+
+    #[test]
+    fn debug_hides_state() {
+        assert_eq!(format!("{:?}", Hmac::<SHA256>::new(b"key")), "Hmac { .. }");
+    }
+}
@@ -0,0 +1,131 @@
+use std::io::{self, Read, Write};
+
+use super::DigestAlgorithm;
+
+// Passes data through from an inner reader, hashing everything that is read
+#[derive(Debug)]
+pub struct HashingReader<R, D> {
+    inner: R,
+    state: D
+}
+
+impl<R: Read, D: DigestAlgorithm> HashingReader<R, D> {
+    pub fn new(inner: R) -> Self {
+        HashingReader {
+            inner,
+            state: D::new()
+        }
+    }
+
+    pub fn get_ref(&self) -> &R {
+        &self.inner
+    }
+
+    pub fn get_mut(&mut self) -> &mut R {
+        &mut self.inner
+    }
+
+    // Returns the inner reader along with the digest of the data read so far
+    pub fn finalize(self) -> (R, D::Digest) {
+        (self.inner, self.state.digest())
+    }
+}
+
+impl<R: Read, D: DigestAlgorithm> Read for HashingReader<R, D> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.state.update(&buf[..n]);
+        Ok(n)
+    }
+}
+
+// Passes data through to an inner writer, hashing everything that is written
+#[derive(Debug)]
+pub struct HashingWriter<W, D> {
+    inner: W,
+    state: D
+}
+
+impl<W: Write, D: DigestAlgorithm> HashingWriter<W, D> {
+    pub fn new(inner: W) -> Self {
+        HashingWriter {
+            inner,
+            state: D::new()
+        }
+    }
+
+    pub fn get_ref(&self) -> &W {
+        &self.inner
+    }
+
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.inner
+    }
+
+    // Returns the inner writer along with the digest of the data written so far
+    pub fn finalize(self) -> (W, D::Digest) {
+        (self.inner, self.state.digest())
+    }
+}
+
+impl<W: Write, D: DigestAlgorithm> Write for HashingWriter<W, D> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        // Only hash what the inner writer actually accepted
+        let n = self.inner.write(buf)?;
+        self.state.update(&buf[..n]);
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{self, Read, Write};
+
+    use super::super::DigestAlgorithm;
+    use super::super::sha2::SHA256;
+    use super::*;
+
+    const INPUT: &[u8] = b"The quick brown fox jumps over the lazy dog";
+
+    fn check(actual: &[u8]) {
+        assert!(actual == base16!("D7A8FBB307D7809469CA9ABCB0082E4F8D5651E46D3CDB762D02D0BF37C9E592"));
+    }
+
+    #[test]
+    fn write_digest_state() {
+        let mut state = SHA256::new();
+        io::copy(&mut &INPUT[..], &mut state).unwrap();
+        check(state.digest().as_ref());
+    }
+
+    #[test]
+    fn hash_reader() {
+        let actual = SHA256::hash_reader(&mut &INPUT[..]).unwrap();
+        check(actual.as_ref());
+    }
+
+    #[test]
+    fn hashing_reader() {
+        let mut reader = HashingReader::<_, SHA256>::new(INPUT);
+        let mut output = Vec::new();
+        reader.read_to_end(&mut output).unwrap();
+        let (_, actual) = reader.finalize();
+        assert!(&output[..] == INPUT);
+        check(actual.as_ref());
+    }
+
+    #[test]
+    fn hashing_writer() {
+        let mut writer = HashingWriter::<_, SHA256>::new(Vec::new());
+        for part in INPUT.chunks(5) {
+            writer.write_all(part).unwrap();
+        }
+        let (output, actual) = writer.finalize();
+        assert!(&output[..] == INPUT);
+        check(actual.as_ref());
+    }
+}
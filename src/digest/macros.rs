@@ -1,46 +1,146 @@
 
 macro_rules! define_digest {
     ($digest:ident, $size:expr) => {
-        #[derive(Copy)]
+        #[derive(Copy, Clone)]
         pub struct $digest([u8; $size]);
 
-        impl AsRef<[u8]> for $digest {
+        define_digest!(@impl [] $digest, stringify!($digest), $size, $size*8, $size);
+    };
+    // A digest of `$bits` bits, where the length depends on a const parameter.
+    // The bytes are kept in an array of `$capacity` bytes, of which only the
+    // first `$bits` bits are used.
+    ($digest:ident<const $param:ident: usize>, $bits:expr, $capacity:expr) => {
+        #[derive(Copy, Clone)]
+        pub struct $digest<const $param: usize>([u8; $capacity]);
+
+        define_digest!(
+            @impl [const $param: usize] $digest<$param>,
+            format!("{}<{}>", stringify!($digest), $param), usize::div_ceil($bits, 8), $bits, $capacity
+        );
+    };
+    (@impl [$($generics:tt)*] $digest:ty, $name:expr, $len:expr, $bits:expr, $capacity:expr) => {
+        impl<$($generics)*> $digest {
+            // Compares digests without revealing the position of the first difference
+            pub fn ct_eq(&self, other: &Self) -> bool {
+                $crate::utils::constant_time::eq(self.as_ref(), other.as_ref())
+            }
+
+            pub fn to_hex(&self) -> String {
+                $crate::utils::hex::encode(self.as_ref())
+            }
+
+            pub fn to_base64(&self) -> String {
+                $crate::utils::base64::encode(self.as_ref())
+            }
+
+            pub fn from_base64(s: &str) -> Result<Self, $crate::digest::ParseDigestError> {
+                let bytes = $crate::utils::base64::decode(s).ok_or($crate::digest::ParseDigestError::InvalidEncoding)?;
+                <Self as ::std::convert::TryFrom<&[u8]>>::try_from(&bytes[..])
+            }
+        }
+
+        impl<$($generics)*> AsRef<[u8]> for $digest {
             fn as_ref(&self) -> &[u8] {
-                &self.0
+                &self.0[..$len]
+            }
+        }
+
+        impl<$($generics)*> From<$digest> for Box<[u8]> {
+            fn from(digest: $digest) -> Box<[u8]> {
+                digest.as_ref().into()
+            }
+        }
+
+        impl<'a, $($generics)*> ::std::convert::TryFrom<&'a [u8]> for $digest {
+            type Error = $crate::digest::ParseDigestError;
+
+            fn try_from(bytes: &'a [u8]) -> Result<Self, Self::Error> {
+                if bytes.len() != $len {
+                    return Err($crate::digest::ParseDigestError::InvalidLength);
+                }
+                // The unused low bits of a partial final byte must be clear
+                if $bits % 8 != 0 && bytes[$len - 1] & (0xff >> ($bits % 8)) != 0 {
+                    return Err($crate::digest::ParseDigestError::InvalidEncoding);
+                }
+                let mut result = [0; $capacity];
+                result[..$len].copy_from_slice(bytes);
+                Ok(Self(result))
+            }
+        }
+
+        impl<$($generics)*> ::std::str::FromStr for $digest {
+            type Err = $crate::digest::ParseDigestError;
+
+            fn from_str(s: &str) -> Result<Self, Self::Err> {
+                let bytes = $crate::utils::hex::decode(s).ok_or($crate::digest::ParseDigestError::InvalidEncoding)?;
+                <Self as ::std::convert::TryFrom<&[u8]>>::try_from(&bytes[..])
+            }
+        }
+
+        // Equality is constant-time, since digests are often compared against
+        // secret or attacker-controlled values
+        impl<$($generics)*> PartialEq for $digest {
+            fn eq(&self, other: &Self) -> bool {
+                self.ct_eq(other)
+            }
+        }
+
+        impl<$($generics)*> Eq for $digest {}
+
+        impl<$($generics)*> ::std::hash::Hash for $digest {
+            fn hash<H: ::std::hash::Hasher>(&self, state: &mut H) {
+                self.as_ref().hash(state)
             }
         }
 
-        impl Into<Box<[u8]>> for $digest {
-            fn into(self) -> Box<[u8]> {
-                Box::new(self.0)
+        impl<$($generics)*> ::std::fmt::Debug for $digest {
+            fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+                write!(f, "{}(\"{:x}\")", $name, self)
             }
         }
 
-        impl ::std::fmt::Debug for $digest {
+        impl<$($generics)*> ::std::fmt::Display for $digest {
             fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
-                write!(f, "{}(\"", stringify!($digest))?;
-                for byte in &self.0[..] {
+                write!(f, "{:x}", self)
+            }
+        }
+
+        impl<$($generics)*> ::std::fmt::LowerHex for $digest {
+            fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+                for byte in self.as_ref() {
                     write!(f, "{:02x}", byte)?;
                 }
-                write!(f, "\")")
+                Ok(())
             }
         }
 
-        impl Clone for $digest {
-            fn clone(&self) -> Self {
-                $digest(self.0)
+        impl<$($generics)*> ::std::fmt::UpperHex for $digest {
+            fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+                for byte in self.as_ref() {
+                    write!(f, "{:02X}", byte)?;
+                }
+                Ok(())
             }
         }
 
-        impl $crate::digest::Digest for $digest {}
+        impl<$($generics)*> $crate::digest::Digest for $digest {
+            const LEN: usize = $len;
+        }
     }
 }
 
 macro_rules! define_chunk {
     ($chunk:ident, $size:expr) => {
-        #[derive(Copy)]
+        // Not `Copy`, so that every copy of the data is wiped when dropped
+        #[derive(Clone)]
         pub struct $chunk([u8; $size]);
 
+        impl AsRef<[u8]> for $chunk {
+            fn as_ref(&self) -> &[u8] {
+                &self.0
+            }
+        }
+
         impl AsMut<[u8]> for $chunk {
             fn as_mut(&mut self) -> &mut [u8] {
                 &mut self.0
@@ -56,12 +156,6 @@ macro_rules! define_chunk {
             }
         }
 
-        impl Clone for $chunk {
-            fn clone(&self) -> Self {
-                $chunk(self.0)
-            }
-        }
-
         impl $crate::digest::chunked::Chunk for $chunk {
             fn new() -> Self {
                 $chunk([0; $size])
@@ -70,5 +164,17 @@ macro_rules! define_chunk {
                 $size
             }
         }
+
+        impl $crate::utils::zeroize::Zeroize for $chunk {
+            fn zeroize(&mut self) {
+                $crate::utils::zeroize::Zeroize::zeroize(&mut self.0);
+            }
+        }
+
+        impl Drop for $chunk {
+            fn drop(&mut self) {
+                $crate::utils::zeroize::Zeroize::zeroize(self);
+            }
+        }
     }
 }
@@ -0,0 +1,151 @@
+use std::io::Cursor;
+use super::chunked::{ChunkedDigestAlgorithm, ChunkedDigestWrapper, LengthPadding};
+use byteorder::{ReadBytesExt, WriteBytesExt, BigEndian, LittleEndian};
+use utils::zeroize::Zeroize;
+
+define_digest!(MD5Digest, 16);
+define_chunk!(MD5Chunk, 64);
+
+// Per-round shift amounts
+const S: [u32; 64] = [
+    7, 12, 17, 22, 7, 12, 17, 22, 7, 12, 17, 22, 7, 12, 17, 22,
+    5,  9, 14, 20, 5,  9, 14, 20, 5,  9, 14, 20, 5,  9, 14, 20,
+    4, 11, 16, 23, 4, 11, 16, 23, 4, 11, 16, 23, 4, 11, 16, 23,
+    6, 10, 15, 21, 6, 10, 15, 21, 6, 10, 15, 21, 6, 10, 15, 21
+];
+
+// Round constants, floor(abs(sin(i + 1)) * 2^32)
+const K: [u32; 64] = [
+    0xd76aa478, 0xe8c7b756, 0x242070db, 0xc1bdceee, 0xf57c0faf, 0x4787c62a, 0xa8304613, 0xfd469501,
+    0x698098d8, 0x8b44f7af, 0xffff5bb1, 0x895cd7be, 0x6b901122, 0xfd987193, 0xa679438e, 0x49b40821,
+    0xf61e2562, 0xc040b340, 0x265e5a51, 0xe9b6c7aa, 0xd62f105d, 0x02441453, 0xd8a1e681, 0xe7d3fbc8,
+    0x21e1cde6, 0xc33707d6, 0xf4d50d87, 0x455a14ed, 0xa9e3e905, 0xfcefa3f8, 0x676f02d9, 0x8d2a4c8a,
+    0xfffa3942, 0x8771f681, 0x6d9d6122, 0xfde5380c, 0xa4beea44, 0x4bdecfa9, 0xf6bb4b60, 0xbebfbc70,
+    0x289b7ec6, 0xeaa127fa, 0xd4ef3085, 0x04881d05, 0xd9d4d039, 0xe6db99e5, 0x1fa27cf8, 0xc4ac5665,
+    0xf4292244, 0x432aff97, 0xab9423a7, 0xfc93a039, 0x655b59c3, 0x8f0ccc92, 0xffeff47d, 0x85845dd1,
+    0x6fa87e4f, 0xfe2ce6e0, 0xa3014314, 0x4e0811a1, 0xf7537e82, 0xbd3af235, 0x2ad7d2bb, 0xeb86d391
+];
+
+#[derive(Debug, Clone)]
+pub struct MD5Chunked {
+    h: [u32; 4],
+}
+
+impl Zeroize for MD5Chunked {
+    fn zeroize(&mut self) {
+        self.h.zeroize();
+    }
+}
+
+impl Drop for MD5Chunked {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
+impl ChunkedDigestAlgorithm for MD5Chunked {
+    type Digest = MD5Digest;
+    type Chunk = MD5Chunk;
+
+    const PADDING: LengthPadding = LengthPadding::LITTLE_ENDIAN_64;
+    const NAME: &'static str = "md5";
+
+    fn new() -> Self {
+        MD5Chunked {
+            h: [0x67452301, 0xefcdab89, 0x98badcfe, 0x10325476]
+        }
+    }
+
+    fn update_chunk(&mut self, chunk: &[u8]) {
+        // Read 16 words
+        let mut m = [0; 16];
+        let mut reader = Cursor::new(chunk);
+        for word in &mut m {
+            *word = reader.read_u32::<LittleEndian>().unwrap();
+        }
+
+        // Copy hash state
+        let [mut a, mut b, mut c, mut d] = self.h;
+
+        for i in 0..64 {
+            let (f, g) = match i {
+                 0..=15 => ((b & c) | (!b & d), i),
+                16..=31 => ((d & b) | (!d & c), (5*i + 1) % 16),
+                32..=47 => (b ^ c ^ d, (3*i + 5) % 16),
+                48..=63 => (c ^ (b | !d), (7*i) % 16),
+                      _ => unreachable!()
+            };
+            let temp = f
+                .wrapping_add(a)
+                .wrapping_add(K[i])
+                .wrapping_add(m[g]);
+            a = d;
+            d = c;
+            c = b;
+            b = b.wrapping_add(temp.rotate_left(S[i]));
+        }
+
+        // Update hash state
+        for (h, v) in self.h.iter_mut().zip(&[a, b, c, d]) {
+            *h = h.wrapping_add(*v);
+        }
+    }
+
+    fn digest(self) -> Self::Digest {
+        let mut result = [0; 16];
+        {
+            let mut writer = Cursor::new(&mut result[..]);
+            for &h in &self.h {
+                writer.write_u32::<LittleEndian>(h).unwrap();
+            }
+        }
+        MD5Digest(result)
+    }
+
+    fn export_state(&self, out: &mut Vec<u8>) {
+        for &h in &self.h {
+            out.write_u32::<BigEndian>(h).unwrap();
+        }
+    }
+
+    fn import_state(state: &[u8]) -> Option<Self> {
+        if state.len() != 16 {
+            return None;
+        }
+        let mut h = [0; 4];
+        let mut reader = Cursor::new(state);
+        for word in &mut h {
+            *word = reader.read_u32::<BigEndian>().unwrap();
+        }
+        Some(MD5Chunked {
+            h
+        })
+    }
+}
+
+pub type MD5 = ChunkedDigestWrapper<MD5Chunked>;
+
+#[cfg(test)]
+mod tests {
+    use super::super::DigestAlgorithm;
+    use super::*;
+
+    fn test_md5(input: &[u8], expected: &[u8]) {
+        let actual = MD5::compute(input);
+        println!("A: {:?}", actual);
+        println!("B: {:?}", expected);
+        assert!(actual.as_ref() == expected)
+    }
+
+    // RFC 1321 appendix A.5
+    #[test]
+    fn md5() {
+        test_md5(b"", base16!("D41D8CD98F00B204E9800998ECF8427E"));
+        test_md5(b"a", base16!("0CC175B9C0F1B6A831C399E269772661"));
+        test_md5(b"abc", base16!("900150983CD24FB0D6963F7D28E17F72"));
+        test_md5(b"message digest", base16!("F96B697D7CB7938D525A2F31AAF161D0"));
+        test_md5(b"abcdefghijklmnopqrstuvwxyz", base16!("C3FCD3D76192E4007DFB496CCA67E13B"));
+        test_md5(b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789", base16!("D174AB98D277D9F5A5611C2C9F419D9F"));
+        test_md5(b"12345678901234567890123456789012345678901234567890123456789012345678901234567890", base16!("57EDF4A22BE3C955AC49DA2E2107B67A"));
+    }
+}
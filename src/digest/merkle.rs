@@ -0,0 +1,399 @@
+use super::DigestAlgorithm;
+
+// Domain separation prefixes from RFC 6962 section 2.1, so that a leaf can
+// never be passed off as an interior node
+const LEAF_PREFIX: u8 = 0x00;
+const NODE_PREFIX: u8 = 0x01;
+
+pub fn leaf_hash<D: DigestAlgorithm>(data: &[u8]) -> D::Digest {
+    let mut state = D::new();
+    state.update(&[LEAF_PREFIX]);
+    state.update(data);
+    state.digest()
+}
+
+pub fn node_hash<D: DigestAlgorithm>(left: &[u8], right: &[u8]) -> D::Digest {
+    let mut state = D::new();
+    state.update(&[NODE_PREFIX]);
+    state.update(left);
+    state.update(right);
+    state.digest()
+}
+
+// Largest power of two strictly less than `n`, for `n > 1`
+fn split(n: usize) -> usize {
+    1 << (usize::BITS - 1 - (n - 1).leading_zeros())
+}
+
+// Append-only Merkle tree as used by Certificate Transparency (RFC 6962 and
+// RFC 9162). Every complete subtree is retained, so the root for any earlier
+// size of the tree takes O(log n) hash operations. Each node of a proof is
+// also a subtree root, so proofs take O(log n) operations when the sizes are
+// powers of two and up to O(log^2 n) in total otherwise.
+#[derive(Debug, Clone)]
+pub struct MerkleTree<D: DigestAlgorithm> {
+    // `levels[k][i]` is the hash of the complete subtree covering leaves
+    // `i*2^k .. (i + 1)*2^k`
+    levels: Vec<Vec<D::Digest>>
+}
+
+impl<D: DigestAlgorithm> Default for MerkleTree<D> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<D: DigestAlgorithm> MerkleTree<D> {
+    pub fn new() -> Self {
+        MerkleTree {
+            levels: Vec::new()
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.levels.first().map_or(0, Vec::len)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    // Appends a leaf, returning its index
+    pub fn push(&mut self, data: &[u8]) -> usize {
+        let index = self.len();
+        let mut hash = leaf_hash::<D>(data);
+        let mut level = 0;
+        loop {
+            if self.levels.len() == level {
+                self.levels.push(Vec::new());
+            }
+            let nodes = &mut self.levels[level];
+            nodes.push(hash);
+            // Stop once there's no sibling to combine with
+            if !nodes.len().is_multiple_of(2) {
+                return index;
+            }
+            hash = node_hash::<D>(nodes[nodes.len() - 2].as_ref(), nodes[nodes.len() - 1].as_ref());
+            level += 1;
+        }
+    }
+
+    pub fn leaf(&self, index: usize) -> Option<&D::Digest> {
+        self.levels.first().and_then(|leaves| leaves.get(index))
+    }
+
+    pub fn root(&self) -> D::Digest {
+        self.root_at(self.len()).unwrap()
+    }
+
+    // Root of the tree as it was when it held `size` leaves
+    pub fn root_at(&self, size: usize) -> Option<D::Digest> {
+        if size > self.len() {
+            None
+        } else if size == 0 {
+            Some(D::compute(&[]))
+        } else {
+            Some(self.subtree(0, size))
+        }
+    }
+
+    // MTH(D[start:end]), for a non-empty range
+    fn subtree(&self, start: usize, end: usize) -> D::Digest {
+        let n = end - start;
+        if n.is_power_of_two() && start.is_multiple_of(n) {
+            self.levels[n.trailing_zeros() as usize][start/n].clone()
+        } else {
+            let k = split(n);
+            node_hash::<D>(self.subtree(start, start + k).as_ref(), self.subtree(start + k, end).as_ref())
+        }
+    }
+
+    // Audit path for leaf `index` in the tree of size `size` (RFC 6962 section 2.1.1)
+    pub fn inclusion_proof(&self, index: usize, size: usize) -> Option<Vec<D::Digest>> {
+        if index >= size || size > self.len() {
+            return None;
+        }
+        let mut proof = Vec::new();
+        self.path(index, 0, size, &mut proof);
+        Some(proof)
+    }
+
+    fn path(&self, m: usize, start: usize, end: usize, proof: &mut Vec<D::Digest>) {
+        let n = end - start;
+        if n > 1 {
+            let k = split(n);
+            if m < k {
+                self.path(m, start, start + k, proof);
+                proof.push(self.subtree(start + k, end));
+            } else {
+                self.path(m - k, start + k, end, proof);
+                proof.push(self.subtree(start, start + k));
+            }
+        }
+    }
+
+    // Proof that the tree of size `new_size` is an extension of the tree of
+    // size `old_size` (RFC 6962 section 2.1.2)
+    pub fn consistency_proof(&self, old_size: usize, new_size: usize) -> Option<Vec<D::Digest>> {
+        if old_size > new_size || new_size > self.len() {
+            return None;
+        }
+        let mut proof = Vec::new();
+        if old_size > 0 {
+            self.subproof(old_size, 0, new_size, true, &mut proof);
+        }
+        Some(proof)
+    }
+
+    fn subproof(&self, m: usize, start: usize, end: usize, complete: bool, proof: &mut Vec<D::Digest>) {
+        let n = end - start;
+        if m == n {
+            // The verifier already knows the old root
+            if !complete {
+                proof.push(self.subtree(start, end));
+            }
+        } else {
+            let k = split(n);
+            if m <= k {
+                self.subproof(m, start, start + k, complete, proof);
+                proof.push(self.subtree(start + k, end));
+            } else {
+                self.subproof(m - k, start + k, end, false, proof);
+                proof.push(self.subtree(start, start + k));
+            }
+        }
+    }
+}
+
+// Checks an audit path for the leaf with contents `data` (RFC 9162 section 2.1.3.2)
+pub fn verify_inclusion<D: DigestAlgorithm>(data: &[u8], index: usize, size: usize, proof: &[D::Digest], root: &D::Digest) -> bool {
+    if index >= size {
+        return false;
+    }
+    let mut f = index;
+    let mut s = size - 1;
+    let mut r = leaf_hash::<D>(data);
+    for p in proof {
+        if s == 0 {
+            return false;
+        }
+        if !f.is_multiple_of(2) || f == s {
+            r = node_hash::<D>(p.as_ref(), r.as_ref());
+            while f.is_multiple_of(2) && f != 0 {
+                f >>= 1;
+                s >>= 1;
+            }
+        } else {
+            r = node_hash::<D>(r.as_ref(), p.as_ref());
+        }
+        f >>= 1;
+        s >>= 1;
+    }
+    s == 0 && r == *root
+}
+
+// Checks a consistency proof between two tree sizes (RFC 9162 section 2.1.4.2)
+pub fn verify_consistency<D: DigestAlgorithm>(old_size: usize, new_size: usize, old_root: &D::Digest, new_root: &D::Digest, proof: &[D::Digest]) -> bool {
+    if old_size > new_size {
+        return false;
+    }
+    if old_size == new_size {
+        return proof.is_empty() && old_root == new_root;
+    }
+    // Every tree is an extension of the empty tree
+    if old_size == 0 {
+        return proof.is_empty();
+    }
+
+    // When the old tree is a complete subtree its root is omitted from the proof
+    let mut path = proof.iter();
+    let first = if old_size.is_power_of_two() {
+        old_root
+    } else {
+        match path.next() {
+            Some(first) => first,
+            None => return false
+        }
+    };
+
+    let mut f = old_size - 1;
+    let mut s = new_size - 1;
+    while !f.is_multiple_of(2) {
+        f >>= 1;
+        s >>= 1;
+    }
+    let mut fr = first.clone();
+    let mut sr = first.clone();
+    for c in path {
+        if s == 0 {
+            return false;
+        }
+        if !f.is_multiple_of(2) || f == s {
+            fr = node_hash::<D>(c.as_ref(), fr.as_ref());
+            sr = node_hash::<D>(c.as_ref(), sr.as_ref());
+            while f.is_multiple_of(2) && f != 0 {
+                f >>= 1;
+                s >>= 1;
+            }
+        } else {
+            sr = node_hash::<D>(sr.as_ref(), c.as_ref());
+        }
+        f >>= 1;
+        s >>= 1;
+    }
+    s == 0 && fr == *old_root && sr == *new_root
+}
+
+#[cfg(test)]
+mod tests {
+    use utils::hex;
+    use super::super::sha2::{SHA256, SHA256Digest};
+    use super::*;
+
+    // Reference vectors from the Certificate Transparency merkle_tree_test.cc
+    const LEAVES: &[&str] = &[
+        "",
+        "00",
+        "10",
+        "2021",
+        "3031",
+        "40414243",
+        "5051525354555657",
+        "606162636465666768696a6b6c6d6e6f"
+    ];
+
+    const ROOTS: &[&str] = &[
+        "6e340b9cffb37a989ca544e6bb780a2c78901d3fb33738768511a30617afa01d",
+        "fac54203e7cc696cf0dfcb42c92a1d9dbaf70ad9e621f4bd8d98662f00e3c125",
+        "aeb6bcfe274b70a14fb067a5e5578264db0fa9b51af5e0ba159158f329e06e77",
+        "d37ee418976dd95753c1c73862b9398fa2a2cf9b4ff0fdfe8b30cd95209614b7",
+        "4e3bbb1f7b478dcfe71fb631631519a3bca12c9aefca1612bfce4c13a86264d4",
+        "76e67dadbcdf1e10e1b74ddc608abd2f98dfb16fbce75277b5232a127f2087ef",
+        "ddb89be403809e325750d3d263cd78929c2942b7942a34b77e122c9594a74c8c",
+        "5dc9da79a70659a9ad559cb701ded9a2ab9d823aad2f4960cfe370eff4604328"
+    ];
+
+    // (leaf index, tree size, audit path)
+    const INCLUSION_PROOFS: &[(usize, usize, &[&str])] = &[
+        (0, 1, &[]),
+        (0, 8, &[
+            "96a296d224f285c67bee93c30f8a309157f0daa35dc5b87e410b78630a09cfc7",
+            "5f083f0a1a33ca076a95279832580db3e0ef4584bdff1f54c8a360f50de3031e",
+            "6b47aaf29ee3c2af9af889bc1fb9254dabd31177f16232dd6aab035ca39bf6e4"
+        ]),
+        (5, 8, &[
+            "bc1a0643b12e4d2d7c77918f44e0f4f79a838b6cf9ec5b5c283e1f4d88599e6b",
+            "ca854ea128ed050b41b35ffc1b87b8eb2bde461e9e3b5596ece6b9d5975a0ae0",
+            "d37ee418976dd95753c1c73862b9398fa2a2cf9b4ff0fdfe8b30cd95209614b7"
+        ]),
+        (2, 3, &[
+            "fac54203e7cc696cf0dfcb42c92a1d9dbaf70ad9e621f4bd8d98662f00e3c125"
+        ]),
+        (1, 5, &[
+            "6e340b9cffb37a989ca544e6bb780a2c78901d3fb33738768511a30617afa01d",
+            "5f083f0a1a33ca076a95279832580db3e0ef4584bdff1f54c8a360f50de3031e",
+            "bc1a0643b12e4d2d7c77918f44e0f4f79a838b6cf9ec5b5c283e1f4d88599e6b"
+        ])
+    ];
+
+    // (old size, new size, consistency proof)
+    const CONSISTENCY_PROOFS: &[(usize, usize, &[&str])] = &[
+        (1, 1, &[]),
+        (1, 8, &[
+            "96a296d224f285c67bee93c30f8a309157f0daa35dc5b87e410b78630a09cfc7",
+            "5f083f0a1a33ca076a95279832580db3e0ef4584bdff1f54c8a360f50de3031e",
+            "6b47aaf29ee3c2af9af889bc1fb9254dabd31177f16232dd6aab035ca39bf6e4"
+        ]),
+        (6, 8, &[
+            "0ebc5d3437fbe2db158b9f126a1d118e308181031d0a949f8dededebc558ef6a",
+            "ca854ea128ed050b41b35ffc1b87b8eb2bde461e9e3b5596ece6b9d5975a0ae0",
+            "d37ee418976dd95753c1c73862b9398fa2a2cf9b4ff0fdfe8b30cd95209614b7"
+        ]),
+        (2, 5, &[
+            "5f083f0a1a33ca076a95279832580db3e0ef4584bdff1f54c8a360f50de3031e",
+            "bc1a0643b12e4d2d7c77918f44e0f4f79a838b6cf9ec5b5c283e1f4d88599e6b"
+        ])
+    ];
+
+    fn leaves() -> Vec<Vec<u8>> {
+        LEAVES.iter().map(|leaf| hex::decode(leaf).unwrap()).collect()
+    }
+
+    fn digests(hashes: &[&str]) -> Vec<SHA256Digest> {
+        hashes.iter().map(|hash| hash.parse().unwrap()).collect()
+    }
+
+    fn tree() -> MerkleTree<SHA256> {
+        let mut tree = MerkleTree::new();
+        for leaf in leaves() {
+            tree.push(&leaf);
+        }
+        tree
+    }
+
+    #[test]
+    fn roots() {
+        let mut tree = MerkleTree::<SHA256>::new();
+        assert_eq!(tree.root().to_string(), "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855");
+        for (i, leaf) in leaves().iter().enumerate() {
+            assert_eq!(tree.push(leaf), i);
+            assert_eq!(tree.root().to_string(), ROOTS[i]);
+        }
+        for (size, root) in ROOTS.iter().enumerate() {
+            assert_eq!(tree.root_at(size + 1).unwrap().to_string(), *root);
+        }
+        assert!(tree.root_at(9).is_none());
+    }
+
+    #[test]
+    fn inclusion_proofs() {
+        let tree = tree();
+        let leaves = leaves();
+        for &(index, size, expected) in INCLUSION_PROOFS {
+            let proof = tree.inclusion_proof(index, size).unwrap();
+            assert_eq!(proof, digests(expected));
+        }
+
+        // Every proof verifies, and fails against the wrong leaf or root
+        for size in 1..9 {
+            let root = tree.root_at(size).unwrap();
+            for (index, leaf) in leaves.iter().enumerate().take(size) {
+                let proof = tree.inclusion_proof(index, size).unwrap();
+                assert!(verify_inclusion::<SHA256>(leaf, index, size, &proof, &root));
+                assert!(!verify_inclusion::<SHA256>(b"bogus", index, size, &proof, &root));
+                assert!(!verify_inclusion::<SHA256>(leaf, index ^ 1, size, &proof, &root));
+                if size > 1 {
+                    assert!(!verify_inclusion::<SHA256>(leaf, index, size, &proof[1..], &root));
+                }
+            }
+        }
+        assert!(tree.inclusion_proof(8, 8).is_none());
+        assert!(tree.inclusion_proof(0, 9).is_none());
+    }
+
+    #[test]
+    fn consistency_proofs() {
+        let tree = tree();
+        for &(old_size, new_size, expected) in CONSISTENCY_PROOFS {
+            let proof = tree.consistency_proof(old_size, new_size).unwrap();
+            assert_eq!(proof, digests(expected));
+        }
+
+        for new_size in 0..9 {
+            let new_root = tree.root_at(new_size).unwrap();
+            for old_size in 0..new_size + 1 {
+                let old_root = tree.root_at(old_size).unwrap();
+                let proof = tree.consistency_proof(old_size, new_size).unwrap();
+                assert!(verify_consistency::<SHA256>(old_size, new_size, &old_root, &new_root, &proof));
+                if old_size > 0 && old_size < new_size {
+                    let bogus = SHA256::compute(b"bogus");
+                    assert!(!verify_consistency::<SHA256>(old_size, new_size, &bogus, &new_root, &proof));
+                    assert!(!verify_consistency::<SHA256>(old_size, new_size, &old_root, &bogus, &proof));
+                    assert!(!verify_consistency::<SHA256>(old_size, new_size, &old_root, &new_root, &proof[1..]));
+                }
+            }
+        }
+        assert!(tree.consistency_proof(2, 1).is_none());
+        assert!(tree.consistency_proof(1, 9).is_none());
+    }
+}
@@ -1,17 +1,59 @@
-use std::fmt::Debug;
+use std::error::Error;
+use std::fmt::{self, Debug, Display};
+use std::hash::Hash;
+use std::io::{ErrorKind, Read, Result};
 
 #[macro_use]
 pub mod macros;
+pub mod adler32;
+pub mod backend;
+pub mod blake2;
+pub mod blake3;
+pub mod cavp;
 pub mod chunked;
+pub mod crc32;
+pub mod dynamic;
+pub mod hmac;
+pub mod io;
+pub mod md5;
+pub mod merkle;
+pub mod ripemd160;
 pub mod sha1;
+pub mod sha1dc;
 pub mod sha2;
+pub mod sha3;
+pub mod siphash;
+pub mod sm3;
+#[cfg(target_arch = "x86_64")]
+mod x86;
 
-pub trait Digest: Clone + AsRef<[u8]> + Into<Box<[u8]>> + Debug {}
+pub trait Digest: Clone + AsRef<[u8]> + Into<Box<[u8]>> + Debug + Display + Eq + Hash {
+    // Length of the digest in bytes
+    const LEN: usize;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParseDigestError {
+    InvalidEncoding,
+    InvalidLength
+}
+
+impl fmt::Display for ParseDigestError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ParseDigestError::InvalidEncoding => write!(f, "invalid digest encoding"),
+            ParseDigestError::InvalidLength => write!(f, "invalid digest length")
+        }
+    }
+}
+
+impl Error for ParseDigestError {}
 
 pub trait DigestAlgorithm {
     type Digest: Digest;
 
     fn new() -> Self;
+    fn block_len() -> usize;
     fn update(&mut self, input: &[u8]);
     fn digest(self) -> Self::Digest;
 
@@ -20,4 +62,42 @@ pub trait DigestAlgorithm {
         state.update(input);
         state.digest()
     }
+
+    // Hashes everything remaining in `reader`
+    fn hash_reader<R: Read + ?Sized>(reader: &mut R) -> Result<Self::Digest> where Self: Sized {
+        let mut state = Self::new();
+        let mut buffer = [0; 8192];
+        loop {
+            match reader.read(&mut buffer) {
+                Ok(0) => return Ok(state.digest()),
+                Ok(n) => state.update(&buffer[..n]),
+                Err(ref e) if e.kind() == ErrorKind::Interrupted => {},
+                Err(e) => return Err(e)
+            }
+        }
+    }
+}
+
+// Extendable-output function, which can produce output of any length
+pub trait XofAlgorithm {
+    type Reader: XofReader;
+
+    fn new() -> Self;
+    fn block_len() -> usize;
+    fn update(&mut self, input: &[u8]);
+    fn finalize(self) -> Self::Reader;
+
+    fn compute(input: &[u8], output_len: usize) -> Vec<u8> where Self: Sized {
+        let mut state = Self::new();
+        state.update(input);
+        let mut result = vec![0; output_len];
+        state.finalize().squeeze(&mut result);
+        result
+    }
+}
+
+pub trait XofReader {
+    // Fills `output` with the next bytes of the output stream. Successive calls
+    // continue where the previous one left off.
+    fn squeeze(&mut self, output: &mut [u8]);
 }
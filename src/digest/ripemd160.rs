@@ -0,0 +1,189 @@
+use std::io::Cursor;
+use super::chunked::{ChunkedDigestAlgorithm, ChunkedDigestWrapper, LengthPadding};
+use byteorder::{ReadBytesExt, WriteBytesExt, BigEndian, LittleEndian};
+use utils::zeroize::Zeroize;
+
+define_digest!(RIPEMD160Digest, 20);
+define_chunk!(RIPEMD160Chunk, 64);
+
+// Message word selection for the left and right lines
+const R_LEFT: [usize; 80] = [
+     0,  1,  2,  3,  4,  5,  6,  7,  8,  9, 10, 11, 12, 13, 14, 15,
+     7,  4, 13,  1, 10,  6, 15,  3, 12,  0,  9,  5,  2, 14, 11,  8,
+     3, 10, 14,  4,  9, 15,  8,  1,  2,  7,  0,  6, 13, 11,  5, 12,
+     1,  9, 11, 10,  0,  8, 12,  4, 13,  3,  7, 15, 14,  5,  6,  2,
+     4,  0,  5,  9,  7, 12,  2, 10, 14,  1,  3,  8, 11,  6, 15, 13
+];
+const R_RIGHT: [usize; 80] = [
+     5, 14,  7,  0,  9,  2, 11,  4, 13,  6, 15,  8,  1, 10,  3, 12,
+     6, 11,  3,  7,  0, 13,  5, 10, 14, 15,  8, 12,  4,  9,  1,  2,
+    15,  5,  1,  3,  7, 14,  6,  9, 11,  8, 12,  2, 10,  0,  4, 13,
+     8,  6,  4,  1,  3, 11, 15,  0,  5, 12,  2, 13,  9,  7, 10, 14,
+    12, 15, 10,  4,  1,  5,  8,  7,  6,  2, 13, 14,  0,  3,  9, 11
+];
+
+// Rotation amounts for the left and right lines
+const S_LEFT: [u32; 80] = [
+    11, 14, 15, 12,  5,  8,  7,  9, 11, 13, 14, 15,  6,  7,  9,  8,
+     7,  6,  8, 13, 11,  9,  7, 15,  7, 12, 15,  9, 11,  7, 13, 12,
+    11, 13,  6,  7, 14,  9, 13, 15, 14,  8, 13,  6,  5, 12,  7,  5,
+    11, 12, 14, 15, 14, 15,  9,  8,  9, 14,  5,  6,  8,  6,  5, 12,
+     9, 15,  5, 11,  6,  8, 13, 12,  5, 12, 13, 14, 11,  8,  5,  6
+];
+const S_RIGHT: [u32; 80] = [
+     8,  9,  9, 11, 13, 15, 15,  5,  7,  7,  8, 11, 14, 14, 12,  6,
+     9, 13, 15,  7, 12,  8,  9, 11,  7,  7, 12,  7,  6, 15, 13, 11,
+     9,  7, 15, 11,  8,  6,  6, 14, 12, 13,  5, 14, 13, 13,  7,  5,
+    15,  5,  8, 11, 14, 14,  6, 14,  6,  9, 12,  9, 12,  5, 15,  8,
+     8,  5, 12,  9, 12,  5, 14,  6,  8, 13,  6,  5, 15, 13, 11, 11
+];
+
+// Round constants for each group of 16 steps
+const K_LEFT: [u32; 5] = [0x00000000, 0x5a827999, 0x6ed9eba1, 0x8f1bbcdc, 0xa953fd4e];
+const K_RIGHT: [u32; 5] = [0x50a28be6, 0x5c4dd124, 0x6d703ef3, 0x7a6d76e9, 0x00000000];
+
+// Bitwise function for each group of 16 steps
+fn f(round: usize, x: u32, y: u32, z: u32) -> u32 {
+    match round {
+        0 => x ^ y ^ z,
+        1 => (x & y) | (!x & z),
+        2 => (x | !y) ^ z,
+        3 => (x & z) | (y & !z),
+        4 => x ^ (y | !z),
+        _ => unreachable!()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct RIPEMD160Chunked {
+    h: [u32; 5],
+}
+
+impl Zeroize for RIPEMD160Chunked {
+    fn zeroize(&mut self) {
+        self.h.zeroize();
+    }
+}
+
+impl Drop for RIPEMD160Chunked {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
+impl ChunkedDigestAlgorithm for RIPEMD160Chunked {
+    type Digest = RIPEMD160Digest;
+    type Chunk = RIPEMD160Chunk;
+
+    const PADDING: LengthPadding = LengthPadding::LITTLE_ENDIAN_64;
+    const NAME: &'static str = "ripemd160";
+
+    fn new() -> Self {
+        RIPEMD160Chunked {
+            h: [0x67452301, 0xefcdab89, 0x98badcfe, 0x10325476, 0xc3d2e1f0]
+        }
+    }
+
+    fn update_chunk(&mut self, chunk: &[u8]) {
+        // Read 16 words
+        let mut x = [0; 16];
+        let mut reader = Cursor::new(chunk);
+        for word in &mut x {
+            *word = reader.read_u32::<LittleEndian>().unwrap();
+        }
+
+        // Both lines start from the current hash state
+        let mut l = self.h;
+        let mut r = self.h;
+
+        for j in 0..80 {
+            let round = j/16;
+
+            let temp = l[0]
+                .wrapping_add(f(round, l[1], l[2], l[3]))
+                .wrapping_add(x[R_LEFT[j]])
+                .wrapping_add(K_LEFT[round])
+                .rotate_left(S_LEFT[j])
+                .wrapping_add(l[4]);
+            l = [l[4], temp, l[1], l[2].rotate_left(10), l[3]];
+
+            // The right line applies the bitwise functions in reverse order
+            let temp = r[0]
+                .wrapping_add(f(4 - round, r[1], r[2], r[3]))
+                .wrapping_add(x[R_RIGHT[j]])
+                .wrapping_add(K_RIGHT[round])
+                .rotate_left(S_RIGHT[j])
+                .wrapping_add(r[4]);
+            r = [r[4], temp, r[1], r[2].rotate_left(10), r[3]];
+        }
+
+        // Combine both lines into the hash state
+        let h = self.h;
+        self.h = [
+            h[1].wrapping_add(l[2]).wrapping_add(r[3]),
+            h[2].wrapping_add(l[3]).wrapping_add(r[4]),
+            h[3].wrapping_add(l[4]).wrapping_add(r[0]),
+            h[4].wrapping_add(l[0]).wrapping_add(r[1]),
+            h[0].wrapping_add(l[1]).wrapping_add(r[2])
+        ];
+    }
+
+    fn digest(self) -> Self::Digest {
+        let mut result = [0; 20];
+        {
+            let mut writer = Cursor::new(&mut result[..]);
+            for &h in &self.h {
+                writer.write_u32::<LittleEndian>(h).unwrap();
+            }
+        }
+        RIPEMD160Digest(result)
+    }
+
+    fn export_state(&self, out: &mut Vec<u8>) {
+        for &h in &self.h {
+            out.write_u32::<BigEndian>(h).unwrap();
+        }
+    }
+
+    fn import_state(state: &[u8]) -> Option<Self> {
+        if state.len() != 20 {
+            return None;
+        }
+        let mut h = [0; 5];
+        let mut reader = Cursor::new(state);
+        for word in &mut h {
+            *word = reader.read_u32::<BigEndian>().unwrap();
+        }
+        Some(RIPEMD160Chunked {
+            h
+        })
+    }
+}
+
+pub type RIPEMD160 = ChunkedDigestWrapper<RIPEMD160Chunked>;
+
+#[cfg(test)]
+mod tests {
+    use super::super::DigestAlgorithm;
+    use super::*;
+
+    fn test_ripemd160(input: &[u8], expected: &[u8]) {
+        let actual = RIPEMD160::compute(input);
+        println!("A: {:?}", actual);
+        println!("B: {:?}", expected);
+        assert!(actual.as_ref() == expected)
+    }
+
+    // Test vectors from the RIPEMD-160 reference
+    #[test]
+    fn ripemd160() {
+        test_ripemd160(b"", base16!("9C1185A5C5E9FC54612808977EE8F548B2258D31"));
+        test_ripemd160(b"a", base16!("0BDC9D2D256B3EE9DAAE347BE6F4DC835A467FFE"));
+        test_ripemd160(b"abc", base16!("8EB208F7E05D987A9B044A8E98C6B087F15A0BFC"));
+        test_ripemd160(b"message digest", base16!("5D0689EF49D2FAE572B881B123A85FFA21595F36"));
+        test_ripemd160(b"abcdefghijklmnopqrstuvwxyz", base16!("F71C27109C692C1B56BBDCEB5B9D2865B3708DBC"));
+        test_ripemd160(b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq", base16!("12A053384A9C0C88E405A06C27DCF49ADA62EB2B"));
+        test_ripemd160(b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789", base16!("B0E20B6E3116640286ED3A87A5713079B21F5189"));
+        test_ripemd160(b"12345678901234567890123456789012345678901234567890123456789012345678901234567890", base16!("9B752E45573D4B39F4DBD3323CAB82BF63326BFB"));
+    }
+}
@@ -1,19 +1,139 @@
 use std::io::Cursor;
-use super::chunked::{ChunkedDigestAlgorithm, ChunkedDigestWrapper};
+use super::chunked::{ChunkedDigestAlgorithm, ChunkedDigestWrapper, LengthPadding};
 use byteorder::{ReadBytesExt, WriteBytesExt, BigEndian};
+use utils::zeroize::Zeroize;
+use super::backend::Backend;
+#[cfg(target_arch = "x86_64")]
+use super::x86;
 
 define_digest!(SHA1Digest, 20);
 define_chunk!(SHA1Chunk, 64);
 
+fn sha1_update_chunk(self_h: &mut [u32; 5], chunk: &[u8]) {
+    sha1_update_chunk_with(Backend::detect(), self_h, chunk);
+}
+
+fn sha1_update_chunk_with(backend: Backend, self_h: &mut [u32; 5], chunk: &[u8]) {
+    debug_assert!(backend.is_available());
+    match backend {
+        #[cfg(target_arch = "x86_64")]
+        Backend::ShaNi => unsafe { x86::sha1_update_chunk_sha_ni(self_h, chunk) },
+        #[cfg(target_arch = "x86_64")]
+        Backend::Ssse3 | Backend::Avx2 => sha1_rounds(self_h, &unsafe { x86::sha1_schedule_ssse3(chunk) }),
+        _ => sha1_rounds(self_h, &sha1_schedule(chunk))
+    }
+}
+
+fn sha1_update_chunks(self_h: &mut [u32; 5], chunks: &[u8]) {
+    sha1_update_chunks_with(Backend::detect(), self_h, chunks);
+}
+
+// The AVX2 backend schedules chunks in pairs, so it needs to see them together
+fn sha1_update_chunks_with(backend: Backend, self_h: &mut [u32; 5], chunks: &[u8]) {
+    let rest = match backend {
+        #[cfg(target_arch = "x86_64")]
+        Backend::Avx2 => {
+            debug_assert!(backend.is_available());
+            let pairs = chunks.chunks_exact(128);
+            let rest = pairs.remainder();
+            for pair in pairs {
+                let [w0, w1] = unsafe { x86::sha1_schedule_avx2(pair) };
+                sha1_rounds(self_h, &w0);
+                sha1_rounds(self_h, &w1);
+            }
+            rest
+        }
+        _ => chunks
+    };
+    for chunk in rest.chunks_exact(64) {
+        sha1_update_chunk_with(backend, self_h, chunk);
+    }
+}
+
+pub(super) fn sha1_schedule(chunk: &[u8]) -> [u32; 80] {
+    // Compute 80 words
+    let mut w = [0; 80];
+    let mut reader = Cursor::new(chunk);
+    for i in 0..16 {
+        w[i] = reader.read_u32::<BigEndian>().unwrap();
+    }
+    for i in 16..80 {
+        w[i] = (w[i-3] ^ w[i-8] ^ w[i-14] ^ w[i-16]).rotate_left(1);
+    }
+    w
+}
+
+fn sha1_rounds(self_h: &mut [u32; 5], w: &[u32; 80]) {
+    // Copy hash state
+    let mut h = *self_h;
+
+    // Bitwise functions
+    let choose   = |x: u32, y: u32, z: u32| (x & y) | (!x & z);
+    let parity   = |x: u32, y: u32, z: u32| x ^ y ^ z;
+    let majority = |x: u32, y: u32, z: u32| (x & y) | (y & z) | (x & z);
+
+    for i in 0..80 {
+        let (f, k) = match i {
+             0...19 => (  choose(h[1], h[2], h[3]), 0x5A827999),
+            20...39 => (  parity(h[1], h[2], h[3]), 0x6ED9EBA1),
+            40...59 => (majority(h[1], h[2], h[3]), 0x8F1BBCDC),
+            60...79 => (  parity(h[1], h[2], h[3]), 0xCA62C1D6),
+                  _ => unreachable!()
+        };
+        let temp = h[0].rotate_left(5)
+            .wrapping_add(f)
+            .wrapping_add(h[4])
+            .wrapping_add(k)
+            .wrapping_add(w[i]);
+        
+        for i in (0..4).rev() {
+            h[i+1] = h[i];
+        }
+        h[2] = h[2].rotate_left(30);
+        h[0] = temp;
+    }
+
+    // Update hash state
+    for i in 0..5 {
+        self_h[i] = self_h[i].wrapping_add(h[i]);
+    }
+}
+
+pub(super) fn sha1_digest(h: &[u32; 5]) -> SHA1Digest {
+    let mut result = [0; 20];
+    {
+        let mut writer = Cursor::new(&mut result[..]);
+        for &h in h {
+            writer.write_u32::<BigEndian>(h).unwrap();
+        }
+    }
+    SHA1Digest(result)
+}
+
 #[derive(Debug, Clone)]
 pub struct SHA1Chunked {
     h: [u32; 5],
 }
 
+impl Zeroize for SHA1Chunked {
+    fn zeroize(&mut self) {
+        self.h.zeroize();
+    }
+}
+
+impl Drop for SHA1Chunked {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
 impl ChunkedDigestAlgorithm for SHA1Chunked {
     type Digest = SHA1Digest;
     type Chunk = SHA1Chunk;
 
+    const PADDING: LengthPadding = LengthPadding::BIG_ENDIAN_64;
+    const NAME: &'static str = "sha1";
+
     fn new() -> Self {
         SHA1Chunked {
             h: [
@@ -27,60 +147,35 @@ impl ChunkedDigestAlgorithm for SHA1Chunked {
     }
 
     fn update_chunk(&mut self, chunk: &[u8]) {
-        // Compute 80 words
-        let mut w = [0; 80];
-        let mut reader = Cursor::new(chunk);
-        for i in 0..16 {
-            w[i] = reader.read_u32::<BigEndian>().unwrap();
-        }
-        for i in 16..80 {
-            w[i] = (w[i-3] ^ w[i-8] ^ w[i-14] ^ w[i-16]).rotate_left(1);
-        }
+        sha1_update_chunk(&mut self.h, chunk);
+    }
 
-        // Copy hash state
-        let mut h = self.h;
-
-        // Bitwise functions
-        let choose   = |x: u32, y: u32, z: u32| (x & y) | (!x & z);
-        let parity   = |x: u32, y: u32, z: u32| x ^ y ^ z;
-        let majority = |x: u32, y: u32, z: u32| (x & y) | (y & z) | (x & z);
-
-        for i in 0..80 {
-            let (f, k) = match i {
-                 0...19 => (  choose(h[1], h[2], h[3]), 0x5A827999),
-                20...39 => (  parity(h[1], h[2], h[3]), 0x6ED9EBA1),
-                40...59 => (majority(h[1], h[2], h[3]), 0x8F1BBCDC),
-                60...79 => (  parity(h[1], h[2], h[3]), 0xCA62C1D6),
-                      _ => unreachable!()
-            };
-            let temp = h[0].rotate_left(5)
-                .wrapping_add(f)
-                .wrapping_add(h[4])
-                .wrapping_add(k)
-                .wrapping_add(w[i]);
-            
-            for i in (0..4).rev() {
-                h[i+1] = h[i];
-            }
-            h[2] = h[2].rotate_left(30);
-            h[0] = temp;
-        }
+    fn update_chunks(&mut self, chunks: &[u8]) {
+        sha1_update_chunks(&mut self.h, chunks);
+    }
+
+    fn digest(self) -> Self::Digest {
+        sha1_digest(&self.h)
+    }
 
-        // Update hash state
-        for i in 0..5 {
-            self.h[i] = self.h[i].wrapping_add(h[i]);
+    fn export_state(&self, out: &mut Vec<u8>) {
+        for &h in &self.h {
+            out.write_u32::<BigEndian>(h).unwrap();
         }
     }
 
-    fn digest(self) -> Self::Digest {
-        let mut result = [0; 20];
-        {
-            let mut writer = Cursor::new(&mut result[..]);
-            for &h in &self.h {
-                writer.write_u32::<BigEndian>(h).unwrap();
-            }
+    fn import_state(state: &[u8]) -> Option<Self> {
+        if state.len() != 20 {
+            return None;
         }
-        SHA1Digest(result)
+        let mut h = [0; 5];
+        let mut reader = Cursor::new(state);
+        for word in &mut h {
+            *word = reader.read_u32::<BigEndian>().unwrap();
+        }
+        Some(SHA1Chunked {
+            h
+        })
     }
 }
 
@@ -106,4 +201,55 @@ mod tests {
         test_sha1(b"@", base16!("9A78211436F6D425EC38F5C4E02270801F3524F8"));
         test_sha1(b"The quick brown fox jumps over the lazy dog", base16!("2FD4E1C67A2D28FCED849EE1BB76E7391B93EB12"));
     }
+
+    #[test]
+    fn sha1_backends() {
+        use super::super::backend::Backend;
+
+        // Xorshift, so the inputs are reproducible
+        let mut seed = 0x2545f4914f6cdd1du64;
+        let mut next = || {
+            seed ^= seed << 13;
+            seed ^= seed >> 7;
+            seed ^= seed << 17;
+            seed as u32
+        };
+
+        for _ in 0..256 {
+            let mut h = [0; 5];
+            // Five chunks, so paired and single chunks are both covered
+            let mut chunks = [0; 320];
+            for word in &mut h {
+                *word = next();
+            }
+            for byte in &mut chunks[..] {
+                *byte = next() as u8;
+            }
+
+            let mut expected = h;
+            for chunk in chunks.chunks(64) {
+                sha1_update_chunk_with(Backend::Scalar, &mut expected, chunk);
+            }
+            for &backend in Backend::ALL {
+                if backend.is_available() {
+                    let mut actual = h;
+                    sha1_update_chunks_with(backend, &mut actual, &chunks);
+                    assert_eq!(actual, expected, "{:?}", backend);
+                }
+            }
+        }
+    }
+
+    fn test_sha1_bits(input: &[u8], bit_len: usize, expected: &[u8]) {
+        let mut state = SHA1::new();
+        state.update_bits(input, bit_len);
+        assert!(state.digest().as_ref() == expected);
+    }
+
+    // Bit-oriented vectors from the SHAVS response files
+    #[test]
+    fn sha1_bits() {
+        test_sha1_bits(&[0x98], 5, base16!("29826B003B906E660EFF4027CE98AF3531AC75BA"));
+        test_sha1_bits(base16!("49B2AEC2594BBE3A3B117542D94AC880"), 123, base16!("6239781E03729919C01955B3FFA8ACB60B988340"));
+    }
 }
@@ -0,0 +1,538 @@
+// SHA-1 with collision detection, following Marc Stevens' counter-cryptanalysis
+// ("Counter-cryptanalysis", CRYPTO 2013) as implemented by the
+// sha1collisiondetection project used by Git.
+//
+// Every known practical collision attack on SHA-1 builds near-collision
+// blocks from one of a small set of disturbance vectors. For each chunk, the
+// message block that would pair with it under each vector is recompressed
+// from the middle of the computation; if that block leads to the same output
+// the chunk is part of a collision attack.
+
+use std::error::Error;
+use std::fmt;
+use std::io::Cursor;
+use super::chunked::{ChunkedDigestAlgorithm, ChunkedDigestWrapper, LengthPadding};
+use super::sha1::{sha1_digest, sha1_schedule, SHA1Chunk, SHA1Digest};
+use byteorder::{ReadBytesExt, WriteBytesExt, BigEndian};
+use utils::zeroize::Zeroize;
+
+// Disturbance vectors checked, as `(type, k, b, step)`: vector I(k, b) or
+// II(k, b) from the paper, and the step to recompress from. Vector `n`
+// corresponds to bit `n` of the masks in `UBC`.
+const DVS: [(u8, usize, u32, usize); 32] = [
+    (1, 43, 0, 58),
+    (1, 44, 0, 58),
+    (1, 45, 0, 58),
+    (1, 46, 0, 58),
+    (1, 46, 2, 58),
+    (1, 47, 0, 58),
+    (1, 47, 2, 58),
+    (1, 48, 0, 58),
+    (1, 48, 2, 58),
+    (1, 49, 0, 58),
+    (1, 49, 2, 58),
+    (1, 50, 0, 65),
+    (1, 50, 2, 65),
+    (1, 51, 0, 65),
+    (1, 51, 2, 65),
+    (1, 52, 0, 65),
+    (2, 45, 0, 58),
+    (2, 46, 0, 58),
+    (2, 46, 2, 58),
+    (2, 47, 0, 58),
+    (2, 48, 0, 58),
+    (2, 49, 0, 58),
+    (2, 49, 2, 58),
+    (2, 50, 0, 65),
+    (2, 50, 2, 65),
+    (2, 51, 0, 65),
+    (2, 51, 2, 65),
+    (2, 52, 0, 65),
+    (2, 53, 0, 65),
+    (2, 54, 0, 65),
+    (2, 55, 0, 65),
+    (2, 56, 0, 65)
+];
+
+// First 16 message differences of I(52, 0) and II(56, 0). Vectors with a
+// smaller `k` are the same sequence starting later, and `b` rotates each word.
+const DV_I_52: [u32; 16] = [
+    0x04000010, 0xe8000000, 0x0800000c, 0x18000000, 0xb800000a, 0xc8000010, 0x2c000010, 0xf4000014,
+    0xb4000008, 0x08000000, 0x9800000c, 0xd8000010, 0x08000010, 0xb8000010, 0x98000000, 0x60000000
+];
+const DV_II_56: [u32; 16] = [
+    0x2600001a, 0x00000010, 0x0400001c, 0xcc000014, 0x0c000002, 0xc0000010, 0xb400001c, 0x3c000004,
+    0xbc00001a, 0x20000010, 0x2400001c, 0xec000014, 0x0c000002, 0xc0000010, 0xb400001c, 0x2c000004
+];
+
+// Unavoidable bit conditions: each `(mask, i, a, j, b, differ)` requires bit
+// `a` of W[i] and bit `b` of W[j] to differ (or to be equal, if `differ` is
+// false) for any vector in `mask` to apply. Most chunks rule out every vector
+// this way, skipping the recompressions entirely.
+const UBC: [(u32, usize, u32, usize, u32, bool); 156] = [
+    (0x0283a080, 44, 29, 45, 29, false),
+    (0xc2810008, 49, 29, 50, 29, false),
+    (0x60a08004, 48, 29, 49, 29, false),
+    (0x82012220, 47, 4, 50, 29, false),
+    (0x30302002, 47, 29, 48, 29, false),
+    (0x40808888, 46, 4, 49, 29, false),
+    (0x18180801, 46, 29, 47, 29, false),
+    (0x20202224, 45, 4, 48, 29, false),
+    (0x0a0a8200, 45, 29, 46, 29, false),
+    (0x1010088a, 44, 4, 47, 29, false),
+    (0x08080225, 43, 4, 46, 29, false),
+    (0x00a12820, 43, 29, 44, 29, false),
+    (0x0202808a, 42, 4, 45, 29, false),
+    (0x00812025, 41, 4, 44, 29, false),
+    (0x800a00a2, 40, 29, 41, 29, false),
+    (0xc0882000, 54, 29, 55, 29, false),
+    (0x60220800, 53, 29, 54, 29, false),
+    (0x30110200, 52, 29, 53, 29, false),
+    (0x20128800, 50, 4, 53, 29, false),
+    (0x8a020020, 50, 29, 51, 29, false),
+    (0x10092200, 49, 4, 52, 29, false),
+    (0x08028880, 48, 4, 51, 29, false),
+    (0x00300a08, 42, 29, 43, 29, false),
+    (0x00180284, 41, 29, 42, 29, false),
+    (0x8020080a, 40, 4, 43, 29, false),
+    (0x40100205, 39, 4, 42, 29, false),
+    (0xa0080082, 38, 4, 41, 29, false),
+    (0x50020021, 37, 4, 40, 29, false),
+    (0x82108000, 55, 29, 56, 29, false),
+    (0x80908000, 52, 4, 55, 29, false),
+    (0x40282000, 51, 4, 54, 29, false),
+    (0x18080080, 51, 29, 52, 29, false),
+    (0x00110208, 36, 4, 40, 29, false),
+    (0x00308000, 53, 29, 56, 29, true),
+    (0x000a0800, 51, 29, 54, 29, true),
+    (0x00012200, 50, 29, 52, 29, true),
+    (0x00008880, 49, 29, 51, 29, true),
+    (0x00002220, 48, 29, 50, 29, true),
+    (0x00000888, 47, 29, 49, 29, true),
+    (0x00000224, 46, 29, 48, 29, true),
+    (0x00004440, 45, 6, 47, 6, false),
+    (0x0000008a, 45, 29, 47, 29, true),
+    (0x00001110, 44, 6, 46, 6, false),
+    (0x00000025, 44, 29, 46, 29, true),
+    (0x04040100, 41, 1, 42, 6, true),
+    (0x01004040, 40, 1, 41, 6, true),
+    (0x8000000a, 40, 4, 42, 4, true),
+    (0x00401010, 39, 1, 40, 6, true),
+    (0x40000005, 39, 4, 41, 4, true),
+    (0xa0000002, 38, 4, 40, 4, true),
+    (0x50000001, 37, 4, 39, 4, true),
+    (0x00041040, 36, 1, 37, 6, true),
+    (0x00080084, 35, 4, 39, 29, false),
+    (0x00100080, 63, 0, 64, 5, true),
+    (0x00010004, 63, 1, 64, 6, true),
+    (0x00080020, 62, 0, 63, 5, true),
+    (0x00020008, 61, 0, 62, 5, true),
+    (0x00040010, 61, 2, 62, 7, true),
+    (0x00010004, 60, 0, 61, 5, true),
+    (0x22000000, 58, 29, 59, 29, false),
+    (0x10800000, 57, 29, 58, 29, false),
+    (0x28000000, 56, 4, 59, 29, false),
+    (0x0a000000, 56, 29, 59, 29, true),
+    (0x08200000, 56, 29, 57, 29, false),
+    (0x12000000, 55, 4, 58, 29, false),
+    (0x08800000, 54, 4, 57, 29, false),
+    (0x02200000, 53, 4, 56, 29, false),
+    (0x00041000, 51, 1, 50, 6, false),
+    (0x00041000, 48, 6, 50, 6, false),
+    (0x0000a000, 48, 29, 55, 29, true),
+    (0x00004400, 47, 6, 49, 6, false),
+    (0x04000040, 48, 1, 47, 6, false),
+    (0x00001100, 46, 6, 48, 6, false),
+    (0x01000010, 47, 1, 46, 6, false),
+    (0x00404000, 44, 1, 45, 6, true),
+    (0x00000440, 43, 6, 45, 6, false),
+    (0x00000110, 42, 6, 44, 6, false),
+    (0x04040000, 43, 1, 42, 6, false),
+    (0x01004000, 42, 1, 41, 6, false),
+    (0x00401000, 41, 1, 40, 6, false),
+    (0x02008000, 39, 4, 43, 29, false),
+    (0x00802000, 38, 4, 42, 29, false),
+    (0x00004100, 37, 1, 38, 6, true),
+    (0x00200800, 37, 4, 41, 29, false),
+    (0x28000000, 36, 4, 38, 4, true),
+    (0x00000410, 35, 1, 36, 6, true),
+    (0x00082000, 35, 3, 39, 28, false),
+    (0x00000001, 61, 1, 62, 6, true),
+    (0x00000001, 59, 5, 63, 30, false),
+    (0x00000001, 58, 0, 63, 30, true),
+    (0x00000002, 62, 1, 63, 6, true),
+    (0x00000002, 60, 5, 64, 30, false),
+    (0x00000002, 59, 0, 64, 30, true),
+    (0x00000010, 40, 6, 42, 6, false),
+    (0x00000040, 62, 2, 63, 7, true),
+    (0x00000040, 41, 6, 43, 6, false),
+    (0x00000100, 63, 2, 64, 7, true),
+    (0x00000100, 48, 6, 49, 1, false),
+    (0x00000400, 49, 6, 50, 1, false),
+    (0x00000400, 42, 1, 50, 1, true),
+    (0x00000400, 39, 6, 40, 1, false),
+    (0x00000400, 38, 1, 40, 1, true),
+    (0x00000800, 36, 4, 37, 4, true),
+    (0x00001000, 43, 1, 51, 1, true),
+    (0x00002000, 37, 4, 38, 4, true),
+    (0x00004000, 51, 6, 52, 1, false),
+    (0x00004000, 49, 6, 51, 6, false),
+    (0x00004000, 37, 1, 37, 6, false),
+    (0x00004000, 35, 5, 39, 30, false),
+    (0x00008000, 38, 4, 39, 4, true),
+    (0x00040000, 47, 1, 51, 1, true),
+    (0x00100000, 36, 3, 40, 28, false),
+    (0x00100000, 35, 30, 40, 28, true),
+    (0x00200000, 37, 3, 41, 28, false),
+    (0x00200000, 36, 30, 41, 28, true),
+    (0x00400000, 53, 6, 54, 1, false),
+    (0x00400000, 51, 6, 53, 6, false),
+    (0x00400000, 50, 1, 54, 1, true),
+    (0x00400000, 45, 6, 46, 1, false),
+    (0x00400000, 37, 5, 41, 30, false),
+    (0x00400000, 36, 0, 41, 30, true),
+    (0x00800000, 55, 29, 58, 29, true),
+    (0x00800000, 38, 3, 42, 28, false),
+    (0x00800000, 37, 30, 42, 28, true),
+    (0x01000000, 54, 6, 55, 1, false),
+    (0x01000000, 52, 6, 54, 6, false),
+    (0x01000000, 51, 1, 55, 1, true),
+    (0x01000000, 45, 1, 47, 1, true),
+    (0x01000000, 38, 5, 42, 30, false),
+    (0x01000000, 37, 0, 42, 30, true),
+    (0x02000000, 39, 3, 43, 28, false),
+    (0x02000000, 38, 30, 43, 28, true),
+    (0x04000000, 55, 6, 56, 1, false),
+    (0x04000000, 53, 6, 55, 6, false),
+    (0x04000000, 52, 1, 56, 1, true),
+    (0x04000000, 46, 1, 48, 1, true),
+    (0x04000000, 39, 5, 43, 30, false),
+    (0x04000000, 38, 0, 43, 30, true),
+    (0x08000000, 59, 29, 60, 29, false),
+    (0x08000000, 40, 3, 44, 28, false),
+    (0x08000000, 40, 4, 44, 29, false),
+    (0x08000000, 39, 30, 44, 28, true),
+    (0x10000000, 58, 29, 61, 29, true),
+    (0x10000000, 57, 4, 61, 29, false),
+    (0x10000000, 41, 3, 45, 28, false),
+    (0x10000000, 41, 4, 45, 29, false),
+    (0x20000000, 58, 4, 62, 29, false),
+    (0x20000000, 42, 3, 46, 28, false),
+    (0x20000000, 42, 4, 46, 29, false),
+    (0x40000000, 59, 4, 63, 29, false),
+    (0x40000000, 57, 4, 59, 29, false),
+    (0x40000000, 43, 3, 47, 28, false),
+    (0x40000000, 43, 4, 47, 29, false),
+    (0x80000000, 60, 4, 64, 29, false),
+    (0x80000000, 44, 3, 48, 28, false),
+    (0x80000000, 44, 4, 48, 29, false)
+];
+
+// Boolean function and constant for step `i`
+fn round_function(i: usize, b: u32, c: u32, d: u32) -> (u32, u32) {
+    match i {
+         0..=19 => ((b & c) | (!b & d), 0x5A827999),
+        20..=39 => (b ^ c ^ d, 0x6ED9EBA1),
+        40..=59 => ((b & c) | (c & d) | (b & d), 0x8F1BBCDC),
+        60..=79 => (b ^ c ^ d, 0xCA62C1D6),
+              _ => unreachable!()
+    }
+}
+
+fn step(s: &mut [u32; 5], i: usize, w: u32) {
+    let (f, k) = round_function(i, s[1], s[2], s[3]);
+    let temp = s[0].rotate_left(5)
+        .wrapping_add(f)
+        .wrapping_add(s[4])
+        .wrapping_add(k)
+        .wrapping_add(w);
+    *s = [temp, s[0], s[1].rotate_left(30), s[2], s[3]];
+}
+
+// Inverse of `step`
+fn unstep(s: &mut [u32; 5], i: usize, w: u32) {
+    let (a, b, c, d) = (s[1], s[2].rotate_right(30), s[3], s[4]);
+    let (f, k) = round_function(i, b, c, d);
+    let e = s[0]
+        .wrapping_sub(a.rotate_left(5))
+        .wrapping_sub(f)
+        .wrapping_sub(k)
+        .wrapping_sub(w);
+    *s = [a, b, c, d, e];
+}
+
+// Compresses a block into `h`, recording the state before each step
+fn compress(h: &mut [u32; 5], w: &[u32; 80], states: &mut [[u32; 5]; 80]) {
+    let mut s = *h;
+    for i in 0..80 {
+        states[i] = s;
+        step(&mut s, i, w[i]);
+    }
+    for (h, &s) in h.iter_mut().zip(&s) {
+        *h = h.wrapping_add(s);
+    }
+}
+
+// Bitmask of the disturbance vectors whose conditions `w` satisfies
+fn ubc_check(w: &[u32; 80]) -> u32 {
+    let mut mask = !0;
+    for &(dvs, i, a, j, b, differ) in UBC.iter() {
+        if mask == 0 {
+            break;
+        }
+        if ((w[i] >> a ^ w[j] >> b) & 1 == 1) != differ {
+            mask &= !dvs;
+        }
+    }
+    mask
+}
+
+// Expanded message difference of a disturbance vector
+fn message_difference(dv_type: u8, k: usize, b: u32) -> [u32; 80] {
+    let (base, offset) = match dv_type {
+        1 => (&DV_I_52, 52 - k),
+        _ => (&DV_II_56, 56 - k)
+    };
+    let mut expanded = [0; 96];
+    expanded[..16].copy_from_slice(base);
+    for i in 16..96 {
+        expanded[i] = (expanded[i-3] ^ expanded[i-8] ^ expanded[i-14] ^ expanded[i-16]).rotate_left(1);
+    }
+    let mut result = [0; 80];
+    for (dm, &x) in result.iter_mut().zip(&expanded[offset..]) {
+        *dm = x.rotate_left(b);
+    }
+    result
+}
+
+// Whether the block pairs with another one, differing by one of the
+// disturbance vectors, that gives the same output from some chaining value
+fn detect_collision(h: &[u32; 5], w: &[u32; 80], states: &[[u32; 5]; 80]) -> bool {
+    let mask = ubc_check(w);
+    for (n, &(dv_type, k, b, t)) in DVS.iter().enumerate() {
+        if mask & (1 << n) == 0 {
+            continue;
+        }
+        let mut w2 = message_difference(dv_type, k, b);
+        for (w2, &w) in w2.iter_mut().zip(w) {
+            *w2 ^= w;
+        }
+
+        // Work back from the shared state to the chaining value the other
+        // block would need, then forward to its output
+        let mut ihv = states[t];
+        for i in (0..t).rev() {
+            unstep(&mut ihv, i, w2[i]);
+        }
+        let mut s = states[t];
+        for (i, &w) in w2.iter().enumerate().skip(t) {
+            step(&mut s, i, w);
+        }
+        if (0..5).all(|i| ihv[i].wrapping_add(s[i]) == h[i]) {
+            return true;
+        }
+    }
+    false
+}
+
+// The message contains a block from a SHA-1 collision attack
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CollisionError {
+    // Digest of the message, which differs from its SHA-1 digest if the safe
+    // hash is enabled
+    pub digest: SHA1Digest
+}
+
+impl fmt::Display for CollisionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "SHA-1 collision attack detected")
+    }
+}
+
+impl Error for CollisionError {}
+
+// With `SAFE_HASH`, blocks that are part of a collision attack are compressed
+// two more times, so that colliding messages get different digests. Other
+// messages hash exactly as with plain SHA-1.
+#[derive(Debug, Clone)]
+pub struct SHA1DCChunked<const SAFE_HASH: bool> {
+    h: [u32; 5],
+    collision: bool
+}
+
+impl<const SAFE_HASH: bool> SHA1DCChunked<SAFE_HASH> {
+    pub fn collision_detected(&self) -> bool {
+        self.collision
+    }
+}
+
+impl<const SAFE_HASH: bool> Zeroize for SHA1DCChunked<SAFE_HASH> {
+    fn zeroize(&mut self) {
+        self.h.zeroize();
+        self.collision = false;
+    }
+}
+
+impl<const SAFE_HASH: bool> Drop for SHA1DCChunked<SAFE_HASH> {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
+impl<const SAFE_HASH: bool> ChunkedDigestAlgorithm for SHA1DCChunked<SAFE_HASH> {
+    type Digest = SHA1Digest;
+    type Chunk = SHA1Chunk;
+
+    const PADDING: LengthPadding = LengthPadding::BIG_ENDIAN_64;
+    const NAME: &'static str = "sha1dc";
+
+    fn new() -> Self {
+        SHA1DCChunked {
+            h: [
+                0x67452301,
+                0xEFCDAB89,
+                0x98BADCFE,
+                0x10325476,
+                0xC3D2E1F0
+            ],
+            collision: false
+        }
+    }
+
+    fn update_chunk(&mut self, chunk: &[u8]) {
+        let w = sha1_schedule(chunk);
+        let mut states = [[0; 5]; 80];
+        compress(&mut self.h, &w, &mut states);
+
+        if detect_collision(&self.h, &w, &states) {
+            self.collision = true;
+            if SAFE_HASH {
+                compress(&mut self.h, &w, &mut states);
+                compress(&mut self.h, &w, &mut states);
+            }
+        }
+    }
+
+    fn digest(self) -> Self::Digest {
+        sha1_digest(&self.h)
+    }
+
+    // The state is followed by flags for the safe hash and any detected
+    // collision
+    fn export_state(&self, out: &mut Vec<u8>) {
+        for &h in &self.h {
+            out.write_u32::<BigEndian>(h).unwrap();
+        }
+        out.push(SAFE_HASH as u8 | (self.collision as u8) << 1);
+    }
+
+    fn import_state(state: &[u8]) -> Option<Self> {
+        if state.len() != 21 || state[20] & !2 != SAFE_HASH as u8 {
+            return None;
+        }
+        let mut h = [0; 5];
+        let mut reader = Cursor::new(state);
+        for word in &mut h {
+            *word = reader.read_u32::<BigEndian>().unwrap();
+        }
+        Some(SHA1DCChunked {
+            h,
+            collision: state[20] & 2 != 0
+        })
+    }
+}
+
+pub type SHA1DC = ChunkedDigestWrapper<SHA1DCChunked<true>>;
+// Detects collisions without changing the digest
+pub type SHA1DCDetectOnly = ChunkedDigestWrapper<SHA1DCChunked<false>>;
+
+impl<const SAFE_HASH: bool> ChunkedDigestWrapper<SHA1DCChunked<SAFE_HASH>> {
+    // Like `digest`, but fails if any chunk was part of a collision attack
+    pub fn checked_digest(self) -> Result<SHA1Digest, CollisionError> {
+        let inner = self.finish();
+        let collision = inner.collision_detected();
+        let digest = inner.digest();
+        if collision {
+            Err(CollisionError { digest })
+        } else {
+            Ok(digest)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::DigestAlgorithm;
+    use super::super::sha1::SHA1;
+    use super::*;
+
+    // The first 320 bytes of the SHAttered PDFs (shattered.io): a shared
+    // 192 byte header followed by each file's pair of near-collision blocks
+    fn shattered_prefix(blocks: &[u8]) -> Vec<u8> {
+        let mut result = base16!("255044462D312E330A25E2E3CFD30A0A0A312030206F626A0A3C3C2F57696474682032203020522F4865696768742033203020522F547970652034203020522F537562747970652035203020522F46696C7465722036203020522F436F6C6F7253706163652037203020522F4C656E6774682038203020522F42697473506572436F6D706F6E656E7420383E3E0A73747265616D0AFFD8FFFE00245348412D3120697320646561642121212121852FEC092339759C39B1A1C63C4C97E1FFFE01").to_vec();
+        result.extend_from_slice(blocks);
+        result
+    }
+
+    fn shattered() -> (Vec<u8>, Vec<u8>) {
+        (
+            shattered_prefix(base16!("7346DC9166B67E118F029AB621B2560FF9CA67CCA8C7F85BA84C79030C2B3DE218F86DB3A90901D5DF45C14F26FEDFB3DC38E96AC22FE7BD728F0E45BCE046D23C570FEB141398BB552EF5A0A82BE331FEA48037B8B5D71F0E332EDF93AC3500EB4DDC0DECC1A864790C782C76215660DD309791D06BD0AF3F98CDA4BC4629B1")),
+            shattered_prefix(base16!("7F46DC93A6B67E013B029AAA1DB2560B45CA67D688C7F84B8C4C791FE02B3DF614F86DB1690901C56B45C1530AFEDFB76038E972722FE7AD728F0E4904E046C230570FE9D41398ABE12EF5BC942BE33542A4802D98B5D70F2A332EC37FAC3514E74DDC0F2CC1A874CD0C78305A21566461309789606BD0BF3F98CDA8044629A1"))
+        )
+    }
+
+    #[test]
+    fn no_collision() {
+        for input in &[&b""[..], b"The quick brown fox jumps over the lazy dog", &[b'a'; 1000]] {
+            let mut state = SHA1DC::new();
+            state.update(input);
+            assert!(state.checked_digest() == Ok(SHA1::compute(input)));
+            assert!(SHA1DC::compute(input) == SHA1::compute(input));
+        }
+    }
+
+    #[test]
+    fn shattered_detected() {
+        let (first, second) = shattered();
+        assert!(first != second);
+        assert!(SHA1::compute(&first) == SHA1::compute(&second));
+
+        // Without the safe hash both still hash to their SHA-1 digest
+        for input in &[&first, &second] {
+            let mut state = SHA1DCDetectOnly::new();
+            state.update(input);
+            assert!(state.checked_digest().unwrap_err().digest.as_ref() == base16!("F92D74E3874587AAF443D1DB961D4E26DDE13E9C"));
+        }
+
+        // The safe hash separates them
+        let mut state = SHA1DC::new();
+        state.update(&first);
+        assert!(state.checked_digest().unwrap_err().digest.as_ref() == base16!("7117B3CB9225AAF0D8EF1A40E493957B0BF8693D"));
+        assert!(SHA1DC::compute(&second).as_ref() == base16!("29F38AE9FD98E2931120FA0BF213E024250D3F6A"));
+
+        // Later input doesn't clear the detection
+        let mut state = SHA1DC::new();
+        state.update(&second);
+        state.update(&[0; 1000]);
+        assert!(state.checked_digest().is_err());
+    }
+
+    #[test]
+    fn export_import() {
+        let (first, _) = shattered();
+        let mut state = SHA1DC::new();
+        state.update(&first);
+        let exported = state.export_state();
+        assert!(SHA1DC::import_state(&exported).unwrap().checked_digest() == state.checked_digest());
+        assert!(SHA1DCDetectOnly::import_state(&exported).is_err());
+    }
+
+    #[test]
+    fn message_differences() {
+        // Spot checks against the expanded vectors in sha1collisiondetection
+        let dm = message_difference(1, 43, 0);
+        assert_eq!((dm[0], dm[1], dm[79]), (0x08000000, 0x9800000c, 0x80000599));
+        let dm = message_difference(2, 46, 2);
+        assert_eq!((dm[0], dm[15], dm[79]), (0x90000070, 0xa0000003, 0x00000152));
+    }
+}
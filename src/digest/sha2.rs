@@ -1,6 +1,11 @@
 use std::io::Cursor;
-use super::chunked::{ChunkedDigestAlgorithm, ChunkedDigestWrapper};
+use super::chunked::{ChunkedDigestAlgorithm, ChunkedDigestWrapper, Endianness, LengthPadding};
 use byteorder::{ReadBytesExt, WriteBytesExt, BigEndian};
+use utils::slice_ext::SliceExt;
+use utils::zeroize::Zeroize;
+use super::backend::Backend;
+#[cfg(target_arch = "x86_64")]
+use super::x86;
 
 
 define_digest!(SHA224Digest, 28);
@@ -15,114 +20,382 @@ define_chunk!(SHA256Chunk, 64);
 define_chunk!(SHA512Chunk, 128);
 
 
+// Round constants for SHA-256
+pub(super) const SHA256_K: [u32; 64] = [
+    0x428a2f98, 0x71374491, 0xb5c0fbcf, 0xe9b5dba5, 0x3956c25b, 0x59f111f1, 0x923f82a4, 0xab1c5ed5,
+    0xd807aa98, 0x12835b01, 0x243185be, 0x550c7dc3, 0x72be5d74, 0x80deb1fe, 0x9bdc06a7, 0xc19bf174,
+    0xe49b69c1, 0xefbe4786, 0x0fc19dc6, 0x240ca1cc, 0x2de92c6f, 0x4a7484aa, 0x5cb0a9dc, 0x76f988da,
+    0x983e5152, 0xa831c66d, 0xb00327c8, 0xbf597fc7, 0xc6e00bf3, 0xd5a79147, 0x06ca6351, 0x14292967,
+    0x27b70a85, 0x2e1b2138, 0x4d2c6dfc, 0x53380d13, 0x650a7354, 0x766a0abb, 0x81c2c92e, 0x92722c85,
+    0xa2bfe8a1, 0xa81a664b, 0xc24b8b70, 0xc76c51a3, 0xd192e819, 0xd6990624, 0xf40e3585, 0x106aa070,
+    0x19a4c116, 0x1e376c08, 0x2748774c, 0x34b0bcb5, 0x391c0cb3, 0x4ed8aa4a, 0x5b9cca4f, 0x682e6ff3,
+    0x748f82ee, 0x78a5636f, 0x84c87814, 0x8cc70208, 0x90befffa, 0xa4506ceb, 0xbef9a3f7, 0xc67178f2
+];
+
+// Round constants for SHA-512
+const SHA512_K: [u64; 80] = [
+    0x428a2f98d728ae22, 0x7137449123ef65cd, 0xb5c0fbcfec4d3b2f, 0xe9b5dba58189dbbc, 0x3956c25bf348b538, 
+    0x59f111f1b605d019, 0x923f82a4af194f9b, 0xab1c5ed5da6d8118, 0xd807aa98a3030242, 0x12835b0145706fbe, 
+    0x243185be4ee4b28c, 0x550c7dc3d5ffb4e2, 0x72be5d74f27b896f, 0x80deb1fe3b1696b1, 0x9bdc06a725c71235, 
+    0xc19bf174cf692694, 0xe49b69c19ef14ad2, 0xefbe4786384f25e3, 0x0fc19dc68b8cd5b5, 0x240ca1cc77ac9c65, 
+    0x2de92c6f592b0275, 0x4a7484aa6ea6e483, 0x5cb0a9dcbd41fbd4, 0x76f988da831153b5, 0x983e5152ee66dfab, 
+    0xa831c66d2db43210, 0xb00327c898fb213f, 0xbf597fc7beef0ee4, 0xc6e00bf33da88fc2, 0xd5a79147930aa725, 
+    0x06ca6351e003826f, 0x142929670a0e6e70, 0x27b70a8546d22ffc, 0x2e1b21385c26c926, 0x4d2c6dfc5ac42aed, 
+    0x53380d139d95b3df, 0x650a73548baf63de, 0x766a0abb3c77b2a8, 0x81c2c92e47edaee6, 0x92722c851482353b, 
+    0xa2bfe8a14cf10364, 0xa81a664bbc423001, 0xc24b8b70d0f89791, 0xc76c51a30654be30, 0xd192e819d6ef5218, 
+    0xd69906245565a910, 0xf40e35855771202a, 0x106aa07032bbd1b8, 0x19a4c116b8d2d0c8, 0x1e376c085141ab53, 
+    0x2748774cdf8eeb99, 0x34b0bcb5e19b48a8, 0x391c0cb3c5c95a63, 0x4ed8aa4ae3418acb, 0x5b9cca4f7763e373, 
+    0x682e6ff3d6b2b8a3, 0x748f82ee5defb2fc, 0x78a5636f43172f60, 0x84c87814a1f0ab72, 0x8cc702081a6439ec, 
+    0x90befffa23631e28, 0xa4506cebde82bde9, 0xbef9a3f7b2c67915, 0xc67178f2e372532b, 0xca273eceea26619c, 
+    0xd186b8c721c0c207, 0xeada7dd6cde0eb1e, 0xf57d4f7fee6ed178, 0x06f067aa72176fba, 0x0a637dc5a2c898a6, 
+    0x113f9804bef90dae, 0x1b710b35131c471b, 0x28db77f523047d84, 0x32caab7b40c72493, 0x3c9ebe0a15c9bebc, 
+    0x431d67c49c100d4c, 0x4cc5d4becb3e42b6, 0x597f299cfc657e2a, 0x5fcb6fab3ad6faec, 0x6c44198c4a475817
+];
+
+// Initial hash values
+const SHA256_IV: [u32; 8] = [0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a, 0x510e527f, 0x9b05688c, 0x1f83d9ab, 0x5be0cd19];
+const SHA512_IV: [u64; 8] = [
+    0x6a09e667f3bcc908, 0xbb67ae8584caa73b, 0x3c6ef372fe94f82b, 0xa54ff53a5f1d36f1,
+    0x510e527fade682d1, 0x9b05688c2b3e6c1f, 0x1f83d9abfb41bd6b, 0x5be0cd19137e2179
+];
+
 fn sha256_update_chunk(self_h: &mut [u32; 8], chunk: &[u8]) {
+    sha256_update_chunk_with(Backend::detect(), self_h, chunk);
+}
+
+fn sha256_update_chunk_with(backend: Backend, self_h: &mut [u32; 8], chunk: &[u8]) {
+    debug_assert!(backend.is_available());
+    match backend {
+        #[cfg(target_arch = "x86_64")]
+        Backend::ShaNi => unsafe { x86::sha256_update_chunk_sha_ni(self_h, chunk) },
+        #[cfg(target_arch = "x86_64")]
+        Backend::Ssse3 | Backend::Avx2 => sha256_rounds(self_h, &unsafe { x86::sha256_schedule_ssse3(chunk) }),
+        _ => sha256_rounds(self_h, &sha256_schedule(chunk))
+    }
+}
+
+fn sha256_update_chunks(self_h: &mut [u32; 8], chunks: &[u8]) {
+    sha256_update_chunks_with(Backend::detect(), self_h, chunks);
+}
+
+// The AVX2 backend schedules chunks in pairs, so it needs to see them together
+fn sha256_update_chunks_with(backend: Backend, self_h: &mut [u32; 8], chunks: &[u8]) {
+    let rest = match backend {
+        #[cfg(target_arch = "x86_64")]
+        Backend::Avx2 => {
+            debug_assert!(backend.is_available());
+            let pairs = chunks.chunks_exact(128);
+            let rest = pairs.remainder();
+            for pair in pairs {
+                let [w0, w1] = unsafe { x86::sha256_schedule_avx2(pair) };
+                sha256_rounds(self_h, &w0);
+                sha256_rounds(self_h, &w1);
+            }
+            rest
+        }
+        _ => chunks
+    };
+    for chunk in rest.chunks_exact(64) {
+        sha256_update_chunk_with(backend, self_h, chunk);
+    }
+}
+
+// The scalar schedule and rounds are const fns, shared with `const_compute`,
+// hence the `while` loops
+const fn sha256_schedule(chunk: &[u8]) -> [u32; 64] {
     // Compute 64 words
     let mut w = [0; 64];
-    let mut reader = Cursor::new(chunk);
-    for i in 0..16 {
-        w[i] = reader.read_u32::<BigEndian>().unwrap();
+    let mut i = 0;
+    while i < 16 {
+        w[i] = u32::from_be_bytes([chunk[4*i], chunk[4*i + 1], chunk[4*i + 2], chunk[4*i + 3]]);
+        i += 1;
     }
-    for i in 16..64 {
+    while i < 64 {
         let s0 = w[i-15].rotate_right(7) ^ w[i-15].rotate_right(18) ^ (w[i-15] >> 3);
         let s1 = w[i-2].rotate_right(17) ^ w[i-2].rotate_right(19) ^ (w[i-2] >> 10);
         w[i] = w[i-16].wrapping_add(s0).wrapping_add(w[i-7]).wrapping_add(s1);
+        i += 1;
     }
+    w
+}
 
-    // Round constants
-    const K: [u32; 64] = [
-        0x428a2f98, 0x71374491, 0xb5c0fbcf, 0xe9b5dba5, 0x3956c25b, 0x59f111f1, 0x923f82a4, 0xab1c5ed5,
-        0xd807aa98, 0x12835b01, 0x243185be, 0x550c7dc3, 0x72be5d74, 0x80deb1fe, 0x9bdc06a7, 0xc19bf174,
-        0xe49b69c1, 0xefbe4786, 0x0fc19dc6, 0x240ca1cc, 0x2de92c6f, 0x4a7484aa, 0x5cb0a9dc, 0x76f988da,
-        0x983e5152, 0xa831c66d, 0xb00327c8, 0xbf597fc7, 0xc6e00bf3, 0xd5a79147, 0x06ca6351, 0x14292967,
-        0x27b70a85, 0x2e1b2138, 0x4d2c6dfc, 0x53380d13, 0x650a7354, 0x766a0abb, 0x81c2c92e, 0x92722c85,
-        0xa2bfe8a1, 0xa81a664b, 0xc24b8b70, 0xc76c51a3, 0xd192e819, 0xd6990624, 0xf40e3585, 0x106aa070,
-        0x19a4c116, 0x1e376c08, 0x2748774c, 0x34b0bcb5, 0x391c0cb3, 0x4ed8aa4a, 0x5b9cca4f, 0x682e6ff3,
-        0x748f82ee, 0x78a5636f, 0x84c87814, 0x8cc70208, 0x90befffa, 0xa4506ceb, 0xbef9a3f7, 0xc67178f2
-    ];
-
+const fn sha256_rounds(self_h: &mut [u32; 8], w: &[u32; 64]) {
     // Copy hash state
     let mut h = *self_h;
 
-    // Bitwise functions
-    let choose   = |x: u32, y: u32, z: u32| (x & y) | (!x & z);
-    let majority = |x: u32, y: u32, z: u32| (x & y) | (y & z) | (x & z);
-
-    for i in 0..64 {
+    let mut i = 0;
+    while i < 64 {
         let s1 = h[4].rotate_right(6) ^ h[4].rotate_right(11) ^ h[4].rotate_right(25);
-        let temp1 = h[7].wrapping_add(s1).wrapping_add(choose(h[4], h[5], h[6])).wrapping_add(K[i]).wrapping_add(w[i]);
+        let choose = (h[4] & h[5]) | (!h[4] & h[6]);
+        let temp1 = h[7].wrapping_add(s1).wrapping_add(choose).wrapping_add(SHA256_K[i]).wrapping_add(w[i]);
         let s0 = h[0].rotate_right(2) ^ h[0].rotate_right(13) ^ h[0].rotate_right(22);
-        let temp2 = s0.wrapping_add(majority(h[0], h[1], h[2]));
+        let majority = (h[0] & h[1]) | (h[1] & h[2]) | (h[0] & h[2]);
+        let temp2 = s0.wrapping_add(majority);
 
-        for i in (0..7).rev() {
-            h[i+1] = h[i];
-        }
-        h[4] = h[4].wrapping_add(temp1);
-        h[0] = temp1.wrapping_add(temp2);
+        h = [temp1.wrapping_add(temp2), h[0], h[1], h[2], h[3].wrapping_add(temp1), h[4], h[5], h[6]];
+        i += 1;
     }
 
     // Update hash state
-    for i in 0..8 {
+    let mut i = 0;
+    while i < 8 {
         self_h[i] = self_h[i].wrapping_add(h[i]);
+        i += 1;
     }
 }
 
-fn sha512_update_chunk(self_h: &mut [u64; 8], chunk: &[u8]) {
+const fn sha512_update_chunk(self_h: &mut [u64; 8], chunk: &[u8]) {
     // Compute 80 words
     let mut w = [0; 80];
-    let mut reader = Cursor::new(chunk);
-    for i in 0..16 {
-        w[i] = reader.read_u64::<BigEndian>().unwrap();
+    let mut i = 0;
+    while i < 16 {
+        let mut bytes = [0; 8];
+        let mut j = 0;
+        while j < 8 {
+            bytes[j] = chunk[8*i + j];
+            j += 1;
+        }
+        w[i] = u64::from_be_bytes(bytes);
+        i += 1;
     }
-    for i in 16..80 {
+    while i < 80 {
         let s0 = w[i-15].rotate_right(1) ^ w[i-15].rotate_right(8) ^ (w[i-15] >> 7);
         let s1 = w[i-2].rotate_right(19) ^ w[i-2].rotate_right(61) ^ (w[i-2] >> 6);
         w[i] = w[i-16].wrapping_add(s0).wrapping_add(w[i-7]).wrapping_add(s1);
+        i += 1;
     }
 
-    // Round constants
-    const K: [u64; 80] = [
-        0x428a2f98d728ae22, 0x7137449123ef65cd, 0xb5c0fbcfec4d3b2f, 0xe9b5dba58189dbbc, 0x3956c25bf348b538, 
-        0x59f111f1b605d019, 0x923f82a4af194f9b, 0xab1c5ed5da6d8118, 0xd807aa98a3030242, 0x12835b0145706fbe, 
-        0x243185be4ee4b28c, 0x550c7dc3d5ffb4e2, 0x72be5d74f27b896f, 0x80deb1fe3b1696b1, 0x9bdc06a725c71235, 
-        0xc19bf174cf692694, 0xe49b69c19ef14ad2, 0xefbe4786384f25e3, 0x0fc19dc68b8cd5b5, 0x240ca1cc77ac9c65, 
-        0x2de92c6f592b0275, 0x4a7484aa6ea6e483, 0x5cb0a9dcbd41fbd4, 0x76f988da831153b5, 0x983e5152ee66dfab, 
-        0xa831c66d2db43210, 0xb00327c898fb213f, 0xbf597fc7beef0ee4, 0xc6e00bf33da88fc2, 0xd5a79147930aa725, 
-        0x06ca6351e003826f, 0x142929670a0e6e70, 0x27b70a8546d22ffc, 0x2e1b21385c26c926, 0x4d2c6dfc5ac42aed, 
-        0x53380d139d95b3df, 0x650a73548baf63de, 0x766a0abb3c77b2a8, 0x81c2c92e47edaee6, 0x92722c851482353b, 
-        0xa2bfe8a14cf10364, 0xa81a664bbc423001, 0xc24b8b70d0f89791, 0xc76c51a30654be30, 0xd192e819d6ef5218, 
-        0xd69906245565a910, 0xf40e35855771202a, 0x106aa07032bbd1b8, 0x19a4c116b8d2d0c8, 0x1e376c085141ab53, 
-        0x2748774cdf8eeb99, 0x34b0bcb5e19b48a8, 0x391c0cb3c5c95a63, 0x4ed8aa4ae3418acb, 0x5b9cca4f7763e373, 
-        0x682e6ff3d6b2b8a3, 0x748f82ee5defb2fc, 0x78a5636f43172f60, 0x84c87814a1f0ab72, 0x8cc702081a6439ec, 
-        0x90befffa23631e28, 0xa4506cebde82bde9, 0xbef9a3f7b2c67915, 0xc67178f2e372532b, 0xca273eceea26619c, 
-        0xd186b8c721c0c207, 0xeada7dd6cde0eb1e, 0xf57d4f7fee6ed178, 0x06f067aa72176fba, 0x0a637dc5a2c898a6, 
-        0x113f9804bef90dae, 0x1b710b35131c471b, 0x28db77f523047d84, 0x32caab7b40c72493, 0x3c9ebe0a15c9bebc, 
-        0x431d67c49c100d4c, 0x4cc5d4becb3e42b6, 0x597f299cfc657e2a, 0x5fcb6fab3ad6faec, 0x6c44198c4a475817
-    ];
-
     // Copy hash state
     let mut h = *self_h;
 
-    // Bitwise functions
-    let choose   = |x: u64, y: u64, z: u64| (x & y) | (!x & z);
-    let majority = |x: u64, y: u64, z: u64| (x & y) | (y & z) | (x & z);
-
-    for i in 0..80 {
+    let mut i = 0;
+    while i < 80 {
         let s1 = h[4].rotate_right(14) ^ h[4].rotate_right(18) ^ h[4].rotate_right(41);
-        let temp1 = h[7].wrapping_add(s1).wrapping_add(choose(h[4], h[5], h[6])).wrapping_add(K[i]).wrapping_add(w[i]);
+        let choose = (h[4] & h[5]) | (!h[4] & h[6]);
+        let temp1 = h[7].wrapping_add(s1).wrapping_add(choose).wrapping_add(SHA512_K[i]).wrapping_add(w[i]);
         let s0 = h[0].rotate_right(28) ^ h[0].rotate_right(34) ^ h[0].rotate_right(39);
-        let temp2 = s0.wrapping_add(majority(h[0], h[1], h[2]));
+        let majority = (h[0] & h[1]) | (h[1] & h[2]) | (h[0] & h[2]);
+        let temp2 = s0.wrapping_add(majority);
 
-        for i in (0..7).rev() {
-            h[i+1] = h[i];
-        }
-        h[4] = h[4].wrapping_add(temp1);
-        h[0] = temp1.wrapping_add(temp2);
+        h = [temp1.wrapping_add(temp2), h[0], h[1], h[2], h[3].wrapping_add(temp1), h[4], h[5], h[6]];
+        i += 1;
     }
 
     // Update hash state
-    for i in 0..8 {
+    let mut i = 0;
+    while i < 8 {
         self_h[i] = self_h[i].wrapping_add(h[i]);
+        i += 1;
+    }
+}
+
+// Number of messages hashed in lockstep by `compute_many`, chosen so that
+// one step across all lanes fills a 256-bit vector register
+const SHA256_LANES: usize = 8;
+const SHA512_LANES: usize = 4;
+
+// Processes one chunk for each of `N` independent hash states, where
+// `chunks` holds the chunks back to back. The working variables are stored
+// lane-major so that every step is a loop over lanes, which the compiler can
+// turn into vector instructions.
+#[allow(clippy::needless_range_loop)]
+fn sha256_update_lanes<const N: usize>(states: &mut [[u32; 8]; N], chunks: &[u8]) {
+    // Compute 64 words per lane
+    let mut w = [[0; N]; 64];
+    for l in 0..N {
+        let mut reader = Cursor::new(&chunks[l*64..(l + 1)*64]);
+        for i in 0..16 {
+            w[i][l] = reader.read_u32::<BigEndian>().unwrap();
+        }
+    }
+    for i in 16..64 {
+        for l in 0..N {
+            let s0 = w[i-15][l].rotate_right(7) ^ w[i-15][l].rotate_right(18) ^ (w[i-15][l] >> 3);
+            let s1 = w[i-2][l].rotate_right(17) ^ w[i-2][l].rotate_right(19) ^ (w[i-2][l] >> 10);
+            w[i][l] = w[i-16][l].wrapping_add(s0).wrapping_add(w[i-7][l]).wrapping_add(s1);
+        }
+    }
+
+    // Copy hash states
+    let mut h = [[0; N]; 8];
+    for l in 0..N {
+        for j in 0..8 {
+            h[j][l] = states[l][j];
+        }
+    }
+
+    for i in 0..64 {
+        let [a, b, c, d, e, f, g, hh] = h;
+        let mut new_a = [0; N];
+        let mut new_e = [0; N];
+        for l in 0..N {
+            let s1 = e[l].rotate_right(6) ^ e[l].rotate_right(11) ^ e[l].rotate_right(25);
+            let choose = (e[l] & f[l]) | (!e[l] & g[l]);
+            let temp1 = hh[l].wrapping_add(s1).wrapping_add(choose).wrapping_add(SHA256_K[i]).wrapping_add(w[i][l]);
+            let s0 = a[l].rotate_right(2) ^ a[l].rotate_right(13) ^ a[l].rotate_right(22);
+            let majority = (a[l] & b[l]) | (b[l] & c[l]) | (a[l] & c[l]);
+            let temp2 = s0.wrapping_add(majority);
+            new_a[l] = temp1.wrapping_add(temp2);
+            new_e[l] = d[l].wrapping_add(temp1);
+        }
+        h = [new_a, a, b, c, new_e, e, f, g];
+    }
+
+    // Update hash states
+    for l in 0..N {
+        for j in 0..8 {
+            states[l][j] = states[l][j].wrapping_add(h[j][l]);
+        }
+    }
+}
+
+#[allow(clippy::needless_range_loop)]
+fn sha512_update_lanes<const N: usize>(states: &mut [[u64; 8]; N], chunks: &[u8]) {
+    // Compute 80 words per lane
+    let mut w = [[0; N]; 80];
+    for l in 0..N {
+        let mut reader = Cursor::new(&chunks[l*128..(l + 1)*128]);
+        for i in 0..16 {
+            w[i][l] = reader.read_u64::<BigEndian>().unwrap();
+        }
+    }
+    for i in 16..80 {
+        for l in 0..N {
+            let s0 = w[i-15][l].rotate_right(1) ^ w[i-15][l].rotate_right(8) ^ (w[i-15][l] >> 7);
+            let s1 = w[i-2][l].rotate_right(19) ^ w[i-2][l].rotate_right(61) ^ (w[i-2][l] >> 6);
+            w[i][l] = w[i-16][l].wrapping_add(s0).wrapping_add(w[i-7][l]).wrapping_add(s1);
+        }
+    }
+
+    // Copy hash states
+    let mut h = [[0; N]; 8];
+    for l in 0..N {
+        for j in 0..8 {
+            h[j][l] = states[l][j];
+        }
+    }
+
+    for i in 0..80 {
+        let [a, b, c, d, e, f, g, hh] = h;
+        let mut new_a = [0; N];
+        let mut new_e = [0; N];
+        for l in 0..N {
+            let s1 = e[l].rotate_right(14) ^ e[l].rotate_right(18) ^ e[l].rotate_right(41);
+            let choose = (e[l] & f[l]) | (!e[l] & g[l]);
+            let temp1 = hh[l].wrapping_add(s1).wrapping_add(choose).wrapping_add(SHA512_K[i]).wrapping_add(w[i][l]);
+            let s0 = a[l].rotate_right(28) ^ a[l].rotate_right(34) ^ a[l].rotate_right(39);
+            let majority = (a[l] & b[l]) | (b[l] & c[l]) | (a[l] & c[l]);
+            let temp2 = s0.wrapping_add(majority);
+            new_a[l] = temp1.wrapping_add(temp2);
+            new_e[l] = d[l].wrapping_add(temp1);
+        }
+        h = [new_a, a, b, c, new_e, e, f, g];
+    }
+
+    // Update hash states
+    for l in 0..N {
+        for j in 0..8 {
+            states[l][j] = states[l][j].wrapping_add(h[j][l]);
+        }
+    }
+}
+
+// Number of chunks in a message of `len` bytes once padded
+const fn padded_chunk_count(len: usize, chunk_len: usize, padding: LengthPadding) -> usize {
+    (len + 1 + padding.length_bytes).div_ceil(chunk_len)
+}
+
+// Writes chunk `index` of the padded message into `chunk`. This is a const
+// fn so that `const_compute` can share it, hence the `while` loops.
+const fn padded_chunk(message: &[u8], index: usize, padding: LengthPadding, chunk: &mut [u8]) {
+    let chunk_len = chunk.len();
+    let start = index*chunk_len;
+    let mut i = 0;
+    while i < chunk_len {
+        chunk[i] = if start + i < message.len() {
+            message[start + i]
+        } else if start + i == message.len() {
+            // Append 1 bit
+            0x80
+        } else {
+            0
+        };
+        i += 1;
+    }
+    // Message length (in bits) at the end of the final chunk
+    if index + 1 == padded_chunk_count(message.len(), chunk_len, padding) {
+        let ml = (message.len() as u128)*8;
+        let mut i = 0;
+        let field = chunk_len - padding.length_bytes;
+        while i < padding.length_bytes {
+            let shift = match padding.endianness {
+                Endianness::Big => 8*(padding.length_bytes - 1 - i),
+                Endianness::Little => 8*i
+            };
+            chunk[field + i] = (ml >> shift) as u8;
+            i += 1;
+        }
+    }
+}
+
+// Hashes every message, keeping `N` lanes busy: whenever a lane finishes its
+// message it picks up the next one. Once there are too few messages left to
+// fill every lane, the remainder are finished one at a time.
+fn compute_many_lanes<S: Copy, const N: usize>(
+    messages: &[&[u8]],
+    iv: S,
+    chunk_len: usize,
+    padding: LengthPadding,
+    update_lanes: fn(&mut [S; N], &[u8]),
+    update_chunk: fn(&mut S, &[u8])
+) -> Vec<S> {
+    let mut results = vec![iv; messages.len()];
+    let mut next = 0;
+    // Message and chunk index being processed by each lane
+    let mut lanes: [Option<(usize, usize)>; N] = [None; N];
+    let mut states = [iv; N];
+    let mut chunks = vec![0; N*chunk_len];
+
+    loop {
+        for (lane, state) in lanes.iter_mut().zip(&mut states) {
+            if lane.is_none() && next < messages.len() {
+                *lane = Some((next, 0));
+                *state = iv;
+                next += 1;
+            }
+        }
+        if lanes.iter().any(Option::is_none) {
+            break;
+        }
+
+        for (lane, chunk) in lanes.iter().zip(chunks.chunks_mut(chunk_len)) {
+            let (m, index) = lane.unwrap();
+            padded_chunk(messages[m], index, padding, chunk);
+        }
+        update_lanes(&mut states, &chunks);
+        for (lane, state) in lanes.iter_mut().zip(&states) {
+            let (m, index) = lane.unwrap();
+            if index + 1 == padded_chunk_count(messages[m].len(), chunk_len, padding) {
+                results[m] = *state;
+                *lane = None;
+            } else {
+                *lane = Some((m, index + 1));
+            }
+        }
+    }
+
+    // Scalar fallback for whatever is left
+    let chunk = &mut chunks[..chunk_len];
+    for (lane, state) in lanes.iter().zip(&mut states) {
+        if let Some((m, start)) = *lane {
+            for index in start..padded_chunk_count(messages[m].len(), chunk_len, padding) {
+                padded_chunk(messages[m], index, padding, chunk);
+                update_chunk(state, chunk);
+            }
+            results[m] = *state;
+        }
     }
+    results
 }
 
 #[derive(Debug, Clone)]
@@ -130,10 +403,25 @@ pub struct SHA224Chunked {
     h: [u32; 8],
 }
 
+impl Zeroize for SHA224Chunked {
+    fn zeroize(&mut self) {
+        self.h.zeroize();
+    }
+}
+
+impl Drop for SHA224Chunked {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
 impl ChunkedDigestAlgorithm for SHA224Chunked {
     type Digest = SHA224Digest;
     type Chunk = SHA256Chunk;
 
+    const PADDING: LengthPadding = LengthPadding::BIG_ENDIAN_64;
+    const NAME: &'static str = "sha224";
+
     fn new() -> Self {
         SHA224Chunked {
             h: [0xc1059ed8, 0x367cd507, 0x3070dd17, 0xf70e5939, 0xffc00b31, 0x68581511, 0x64f98fa7, 0xbefa4fa4]
@@ -144,6 +432,10 @@ impl ChunkedDigestAlgorithm for SHA224Chunked {
         sha256_update_chunk(&mut self.h, chunk);
     }
 
+    fn update_chunks(&mut self, chunks: &[u8]) {
+        sha256_update_chunks(&mut self.h, chunks);
+    }
+
     fn digest(self) -> Self::Digest {
         let mut result = [0; 28];
         {
@@ -154,6 +446,26 @@ impl ChunkedDigestAlgorithm for SHA224Chunked {
         }
         SHA224Digest(result)
     }
+
+    fn export_state(&self, out: &mut Vec<u8>) {
+        for &h in &self.h {
+            out.write_u32::<BigEndian>(h).unwrap();
+        }
+    }
+
+    fn import_state(state: &[u8]) -> Option<Self> {
+        if state.len() != 32 {
+            return None;
+        }
+        let mut h = [0; 8];
+        let mut reader = Cursor::new(state);
+        for word in &mut h {
+            *word = reader.read_u32::<BigEndian>().unwrap();
+        }
+        Some(SHA224Chunked {
+            h
+        })
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -161,13 +473,28 @@ pub struct SHA256Chunked {
     h: [u32; 8],
 }
 
+impl Zeroize for SHA256Chunked {
+    fn zeroize(&mut self) {
+        self.h.zeroize();
+    }
+}
+
+impl Drop for SHA256Chunked {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
 impl ChunkedDigestAlgorithm for SHA256Chunked {
     type Digest = SHA256Digest;
     type Chunk = SHA256Chunk;
 
+    const PADDING: LengthPadding = LengthPadding::BIG_ENDIAN_64;
+    const NAME: &'static str = "sha256";
+
     fn new() -> Self {
         SHA256Chunked {
-            h: [0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a, 0x510e527f, 0x9b05688c, 0x1f83d9ab, 0x5be0cd19]
+            h: SHA256_IV
         }
     }
 
@@ -175,6 +502,10 @@ impl ChunkedDigestAlgorithm for SHA256Chunked {
         sha256_update_chunk(&mut self.h, chunk);
     }
 
+    fn update_chunks(&mut self, chunks: &[u8]) {
+        sha256_update_chunks(&mut self.h, chunks);
+    }
+
     fn digest(self) -> Self::Digest {
         let mut result = [0; 32];
         {
@@ -185,6 +516,26 @@ impl ChunkedDigestAlgorithm for SHA256Chunked {
         }
         SHA256Digest(result)
     }
+
+    fn export_state(&self, out: &mut Vec<u8>) {
+        for &h in &self.h {
+            out.write_u32::<BigEndian>(h).unwrap();
+        }
+    }
+
+    fn import_state(state: &[u8]) -> Option<Self> {
+        if state.len() != 32 {
+            return None;
+        }
+        let mut h = [0; 8];
+        let mut reader = Cursor::new(state);
+        for word in &mut h {
+            *word = reader.read_u32::<BigEndian>().unwrap();
+        }
+        Some(SHA256Chunked {
+            h
+        })
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -192,10 +543,25 @@ pub struct SHA384Chunked {
     h: [u64; 8],
 }
 
+impl Zeroize for SHA384Chunked {
+    fn zeroize(&mut self) {
+        self.h.zeroize();
+    }
+}
+
+impl Drop for SHA384Chunked {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
 impl ChunkedDigestAlgorithm for SHA384Chunked {
     type Digest = SHA384Digest;
     type Chunk = SHA512Chunk;
 
+    const PADDING: LengthPadding = LengthPadding::BIG_ENDIAN_128;
+    const NAME: &'static str = "sha384";
+
     fn new() -> Self {
         SHA384Chunked {
             h: [
@@ -219,6 +585,26 @@ impl ChunkedDigestAlgorithm for SHA384Chunked {
         }
         SHA384Digest(result)
     }
+
+    fn export_state(&self, out: &mut Vec<u8>) {
+        for &h in &self.h {
+            out.write_u64::<BigEndian>(h).unwrap();
+        }
+    }
+
+    fn import_state(state: &[u8]) -> Option<Self> {
+        if state.len() != 64 {
+            return None;
+        }
+        let mut h = [0; 8];
+        let mut reader = Cursor::new(state);
+        for word in &mut h {
+            *word = reader.read_u64::<BigEndian>().unwrap();
+        }
+        Some(SHA384Chunked {
+            h
+        })
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -226,16 +612,28 @@ pub struct SHA512Chunked {
     h: [u64; 8],
 }
 
+impl Zeroize for SHA512Chunked {
+    fn zeroize(&mut self) {
+        self.h.zeroize();
+    }
+}
+
+impl Drop for SHA512Chunked {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
 impl ChunkedDigestAlgorithm for SHA512Chunked {
     type Digest = SHA512Digest;
     type Chunk = SHA512Chunk;
 
+    const PADDING: LengthPadding = LengthPadding::BIG_ENDIAN_128;
+    const NAME: &'static str = "sha512";
+
     fn new() -> Self {
         SHA512Chunked {
-            h: [
-                0x6a09e667f3bcc908, 0xbb67ae8584caa73b, 0x3c6ef372fe94f82b, 0xa54ff53a5f1d36f1, 
-                0x510e527fade682d1, 0x9b05688c2b3e6c1f, 0x1f83d9abfb41bd6b, 0x5be0cd19137e2179
-            ]
+            h: SHA512_IV
         }
     }
 
@@ -253,6 +651,26 @@ impl ChunkedDigestAlgorithm for SHA512Chunked {
         }
         SHA512Digest(result)
     }
+
+    fn export_state(&self, out: &mut Vec<u8>) {
+        for &h in &self.h {
+            out.write_u64::<BigEndian>(h).unwrap();
+        }
+    }
+
+    fn import_state(state: &[u8]) -> Option<Self> {
+        if state.len() != 64 {
+            return None;
+        }
+        let mut h = [0; 8];
+        let mut reader = Cursor::new(state);
+        for word in &mut h {
+            *word = reader.read_u64::<BigEndian>().unwrap();
+        }
+        Some(SHA512Chunked {
+            h
+        })
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -260,10 +678,25 @@ pub struct SHA512IVGenChunked {
     h: [u64; 8],
 }
 
+impl Zeroize for SHA512IVGenChunked {
+    fn zeroize(&mut self) {
+        self.h.zeroize();
+    }
+}
+
+impl Drop for SHA512IVGenChunked {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
 impl ChunkedDigestAlgorithm for SHA512IVGenChunked {
     type Digest = SHA512IVGenDigest;
     type Chunk = SHA512Chunk;
 
+    const PADDING: LengthPadding = LengthPadding::BIG_ENDIAN_128;
+    const NAME: &'static str = "sha512-ivgen";
+
     fn new() -> Self {
         let mut h = SHA512Chunked::new().h;
         for elem in &mut h {
@@ -288,6 +721,26 @@ impl ChunkedDigestAlgorithm for SHA512IVGenChunked {
         }
         SHA512IVGenDigest(result)
     }
+
+    fn export_state(&self, out: &mut Vec<u8>) {
+        for &h in &self.h {
+            out.write_u64::<BigEndian>(h).unwrap();
+        }
+    }
+
+    fn import_state(state: &[u8]) -> Option<Self> {
+        if state.len() != 64 {
+            return None;
+        }
+        let mut h = [0; 8];
+        let mut reader = Cursor::new(state);
+        for word in &mut h {
+            *word = reader.read_u64::<BigEndian>().unwrap();
+        }
+        Some(SHA512IVGenChunked {
+            h
+        })
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -295,10 +748,25 @@ pub struct SHA512T224Chunked {
     h: [u64; 8],
 }
 
+impl Zeroize for SHA512T224Chunked {
+    fn zeroize(&mut self) {
+        self.h.zeroize();
+    }
+}
+
+impl Drop for SHA512T224Chunked {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
 impl ChunkedDigestAlgorithm for SHA512T224Chunked {
     type Digest = SHA512T224Digest;
     type Chunk = SHA512Chunk;
 
+    const PADDING: LengthPadding = LengthPadding::BIG_ENDIAN_128;
+    const NAME: &'static str = "sha512/224";
+
     fn new() -> Self {
         SHA512T224Chunked {
             h: [
@@ -323,6 +791,26 @@ impl ChunkedDigestAlgorithm for SHA512T224Chunked {
         }
         SHA512T224Digest(result)
     }
+
+    fn export_state(&self, out: &mut Vec<u8>) {
+        for &h in &self.h {
+            out.write_u64::<BigEndian>(h).unwrap();
+        }
+    }
+
+    fn import_state(state: &[u8]) -> Option<Self> {
+        if state.len() != 64 {
+            return None;
+        }
+        let mut h = [0; 8];
+        let mut reader = Cursor::new(state);
+        for word in &mut h {
+            *word = reader.read_u64::<BigEndian>().unwrap();
+        }
+        Some(SHA512T224Chunked {
+            h
+        })
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -330,10 +818,25 @@ pub struct SHA512T256Chunked {
     h: [u64; 8],
 }
 
+impl Zeroize for SHA512T256Chunked {
+    fn zeroize(&mut self) {
+        self.h.zeroize();
+    }
+}
+
+impl Drop for SHA512T256Chunked {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
 impl ChunkedDigestAlgorithm for SHA512T256Chunked {
     type Digest = SHA512T256Digest;
     type Chunk = SHA512Chunk;
 
+    const PADDING: LengthPadding = LengthPadding::BIG_ENDIAN_128;
+    const NAME: &'static str = "sha512/256";
+
     fn new() -> Self {
         SHA512T256Chunked {
             h: [
@@ -357,6 +860,149 @@ impl ChunkedDigestAlgorithm for SHA512T256Chunked {
         }
         SHA512T256Digest(result)
     }
+
+    fn export_state(&self, out: &mut Vec<u8>) {
+        for &h in &self.h {
+            out.write_u64::<BigEndian>(h).unwrap();
+        }
+    }
+
+    fn import_state(state: &[u8]) -> Option<Self> {
+        if state.len() != 64 {
+            return None;
+        }
+        let mut h = [0; 8];
+        let mut reader = Cursor::new(state);
+        for word in &mut h {
+            *word = reader.read_u64::<BigEndian>().unwrap();
+        }
+        Some(SHA512T256Chunked {
+            h
+        })
+    }
+}
+
+// Hashes `input` in full starting from `h`, in a constant expression
+const fn sha512_const_hash(mut h: [u64; 8], input: &[u8]) -> [u64; 8] {
+    let mut chunk = [0; 128];
+    let mut index = 0;
+    while index < padded_chunk_count(input.len(), 128, LengthPadding::BIG_ENDIAN_128) {
+        padded_chunk(input, index, LengthPadding::BIG_ENDIAN_128, &mut chunk);
+        sha512_update_chunk(&mut h, &chunk);
+        index += 1;
+    }
+    h
+}
+
+// The initial hash value for SHA-512/t: the `SHA512IVGen` hash of the string
+// "SHA-512/t", with t written in decimal
+const fn sha512_t_iv(t: usize) -> [u64; 8] {
+    let mut name = *b"SHA-512/000";
+    let mut len = 8;
+    let mut place = if t >= 100 { 100 } else if t >= 10 { 10 } else { 1 };
+    while place > 0 {
+        name[len] = b'0' + (t / place % 10) as u8;
+        len += 1;
+        place /= 10;
+    }
+
+    let mut h = SHA512_IV;
+    let mut i = 0;
+    while i < 8 {
+        h[i] ^= 0xa5a5a5a5a5a5a5a5;
+        i += 1;
+    }
+    sha512_const_hash(h, name.split_at(len).0)
+}
+
+// The leftmost `T` bits of a SHA-512/t hash. When `T` is not a multiple of 8
+// the unused low bits of the final byte are zero.
+define_digest!(SHA512TDigest<const T: usize>, T, 64);
+
+// SHA-512/t for any `T` permitted by FIPS 180-4 section 5.3.6. The initial
+// hash value is derived at compile time, once for each `T`.
+#[derive(Debug, Clone)]
+pub struct SHA512TChunked<const T: usize> {
+    h: [u64; 8],
+}
+
+impl<const T: usize> Zeroize for SHA512TChunked<T> {
+    fn zeroize(&mut self) {
+        self.h.zeroize();
+    }
+}
+
+impl<const T: usize> Drop for SHA512TChunked<T> {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
+impl<const T: usize> SHA512TChunked<T> {
+    const IV: [u64; 8] = {
+        assert!(T > 0 && T < 512 && T != 384, "SHA-512/t requires 0 < t < 512 and t != 384");
+        sha512_t_iv(T)
+    };
+}
+
+impl<const T: usize> ChunkedDigestAlgorithm for SHA512TChunked<T> {
+    type Digest = SHA512TDigest<T>;
+    type Chunk = SHA512Chunk;
+
+    const PADDING: LengthPadding = LengthPadding::BIG_ENDIAN_128;
+    // The value of t is part of the exported state instead
+    const NAME: &'static str = "sha512/t";
+
+    fn new() -> Self {
+        SHA512TChunked {
+            h: Self::IV
+        }
+    }
+
+    fn update_chunk(&mut self, chunk: &[u8]) {
+        sha512_update_chunk(&mut self.h, chunk);
+    }
+
+    fn digest(self) -> Self::Digest {
+        let mut result = [0; 64];
+        {
+            let mut writer = Cursor::new(&mut result[..]);
+            for &h in &self.h {
+                writer.write_u64::<BigEndian>(h).unwrap();
+            }
+        }
+        // Keep only the leftmost t bits
+        let len = T.div_ceil(8);
+        result[len..].fill_copy(0);
+        if !T.is_multiple_of(8) {
+            result[len - 1] &= 0xff << (8 - T % 8);
+        }
+        SHA512TDigest(result)
+    }
+
+    fn export_state(&self, out: &mut Vec<u8>) {
+        out.write_u16::<BigEndian>(T as u16).unwrap();
+        for &h in &self.h {
+            out.write_u64::<BigEndian>(h).unwrap();
+        }
+    }
+
+    fn import_state(state: &[u8]) -> Option<Self> {
+        if state.len() != 66 {
+            return None;
+        }
+        let mut reader = Cursor::new(state);
+        if reader.read_u16::<BigEndian>().unwrap() as usize != T {
+            return None;
+        }
+        let mut h = [0; 8];
+        for word in &mut h {
+            *word = reader.read_u64::<BigEndian>().unwrap();
+        }
+        Some(SHA512TChunked {
+            h
+        })
+    }
 }
 
 pub type SHA224 = ChunkedDigestWrapper<SHA224Chunked>;
@@ -366,10 +1012,72 @@ pub type SHA512 = ChunkedDigestWrapper<SHA512Chunked>;
 pub type SHA512IVGen = ChunkedDigestWrapper<SHA512IVGenChunked>;
 pub type SHA512T224 = ChunkedDigestWrapper<SHA512T224Chunked>;
 pub type SHA512T256 = ChunkedDigestWrapper<SHA512T256Chunked>;
+pub type SHA512T<const T: usize> = ChunkedDigestWrapper<SHA512TChunked<T>>;
+
+impl SHA256 {
+    // Usable in constant expressions, e.g. to embed the hash of a static asset
+    pub const fn const_compute(input: &[u8]) -> SHA256Digest {
+        let mut h = SHA256_IV;
+        let mut chunk = [0; 64];
+        let mut index = 0;
+        while index < padded_chunk_count(input.len(), 64, SHA256Chunked::PADDING) {
+            padded_chunk(input, index, SHA256Chunked::PADDING, &mut chunk);
+            sha256_rounds(&mut h, &sha256_schedule(&chunk));
+            index += 1;
+        }
+
+        let mut result = [0; 32];
+        let mut i = 0;
+        while i < 32 {
+            result[i] = (h[i/4] >> (24 - 8*(i % 4))) as u8;
+            i += 1;
+        }
+        SHA256Digest(result)
+    }
+
+    // Hashes many independent messages, several at a time
+    pub fn compute_many(messages: &[&[u8]]) -> Vec<SHA256Digest> {
+        compute_many_lanes::<_, SHA256_LANES>(
+            messages,
+            SHA256Chunked::new().h,
+            64,
+            SHA256Chunked::PADDING,
+            sha256_update_lanes,
+            sha256_update_chunk
+        ).into_iter().map(|h| SHA256Chunked { h }.digest()).collect()
+    }
+}
+
+impl SHA512 {
+    // Usable in constant expressions, e.g. to embed the hash of a static asset
+    pub const fn const_compute(input: &[u8]) -> SHA512Digest {
+        let h = sha512_const_hash(SHA512_IV, input);
+
+        let mut result = [0; 64];
+        let mut i = 0;
+        while i < 64 {
+            result[i] = (h[i/8] >> (56 - 8*(i % 8))) as u8;
+            i += 1;
+        }
+        SHA512Digest(result)
+    }
+
+    // Hashes many independent messages, several at a time
+    pub fn compute_many(messages: &[&[u8]]) -> Vec<SHA512Digest> {
+        compute_many_lanes::<_, SHA512_LANES>(
+            messages,
+            SHA512Chunked::new().h,
+            128,
+            SHA512Chunked::PADDING,
+            sha512_update_lanes,
+            sha512_update_chunk
+        ).into_iter().map(|h| SHA512Chunked { h }.digest()).collect()
+    }
+}
 
 #[cfg(test)]
 mod tests {
-    use super::super::DigestAlgorithm;
+    use super::super::{Digest, DigestAlgorithm, ParseDigestError};
     use super::*;
 
     fn test<A: DigestAlgorithm>(input: &[u8], expected: &[u8]) {
@@ -415,10 +1123,232 @@ mod tests {
         test::<SHA512T224>(b"The quick brown fox jumps over the lazy dog", base16!("944CD2847FB54558D4775DB0485A50003111C8E5DAA63FE722C6AA37"));
     }
 
+    #[test]
+    fn const_compute() {
+        const SHA256_ABC: SHA256Digest = SHA256::const_compute(b"abc");
+        const SHA512_ABC: SHA512Digest = SHA512::const_compute(b"abc");
+        assert!(SHA256_ABC == SHA256::compute(b"abc"));
+        assert!(SHA512_ABC == SHA512::compute(b"abc"));
+
+        // Cover every position of the padding within a chunk
+        let input: Vec<u8> = (0..300u32).map(|i| (i*7 + 3) as u8).collect();
+        for len in 0..input.len() {
+            assert!(SHA256::const_compute(&input[..len]) == SHA256::compute(&input[..len]));
+            assert!(SHA512::const_compute(&input[..len]) == SHA512::compute(&input[..len]));
+        }
+    }
+
+    #[test]
+    fn sha512_t() {
+        for input in &[&b""[..], b"abc", b"The quick brown fox jumps over the lazy dog"] {
+            assert!(SHA512T::<224>::compute(input).as_ref() == SHA512T224::compute(input).as_ref());
+            assert!(SHA512T::<256>::compute(input).as_ref() == SHA512T256::compute(input).as_ref());
+        }
+        // The derived initial hash values match the ones listed in FIPS 180-4
+        assert_eq!(SHA512TChunked::<224>::IV, SHA512T224Chunked::new().h);
+        assert_eq!(SHA512TChunked::<256>::IV, SHA512T256Chunked::new().h);
+        for &t in &[8, 100, 252] {
+            assert!(SHA512IVGen::compute(format!("SHA-512/{}", t).as_bytes()).as_ref()[..8] == sha512_t_iv(t)[0].to_be_bytes());
+        }
+        test::<SHA512T<8>>(b"abc", base16!("C5"));
+        test::<SHA512T<100>>(b"abc", base16!("36CC539A771DA9AD5726499D80"));
+        test::<SHA512T<252>>(b"abc", base16!("E549922E5E47CF602806D2E232230DB102B266D503DF2121FC8E93ECD347E430"));
+        test::<SHA512T<1>>(b"", base16!("80"));
+        test::<SHA512T<511>>(b"", base16!("DB699C2977EB1E5134AA569C2988AB433E7B7E5DF20C9B5D823DBFFD21D63751C66E25425EA33EFE5F72AA9C4157F7D99CCC08E13B9765789D55981E27FF7C4A"));
+        assert_eq!(<SHA512TDigest<100> as Digest>::LEN, 13);
+        assert_eq!(SHA512T::<100>::compute(b"abc").to_string(), "36cc539a771da9ad5726499d80");
+
+        // The state records t
+        let mut state = SHA512T::<100>::new();
+        state.update(b"ab");
+        let exported = state.export_state();
+        assert!(SHA512T::<252>::import_state(&exported).is_err());
+        let mut state = SHA512T::<100>::import_state(&exported).unwrap();
+        state.update(b"c");
+        assert!(state.digest() == SHA512T::<100>::compute(b"abc"));
+    }
+
     #[test]
     fn sha512_t256() {
         test::<SHA512T256>(b"", base16!("C672B8D1EF56ED28AB87C3622C5114069BDD3AD7B8F9737498D0C01ECEF0967A"));
         test::<SHA512T256>(b"The quick brown fox jumps over the lazy dog", base16!("DD9D67B371519C339ED8DBD25AF90E976A1EEEFD4AD3D889005E532FC5BEF04D"));
     }
 
+    // With 112 to 119 bytes in the final chunk, the 128-bit length field no
+    // longer fits after the 1 bit, so the padding must spill into a new chunk.
+    #[test]
+    fn sha512_length_field() {
+        test::<SHA512>(&[b'a'; 112], base16!("C01D080EFD492776A1C43BD23DD99D0A2E626D481E16782E75D54C2503B5DC32BD05F0F1BA33E568B88FD2D970929B719ECBB152F58F130A407C8830604B70CA"));
+        test::<SHA512>(&[b'a'; 113], base16!("55DDD8AC210A6E18BA1EE055AF84C966E0DBFF091C43580AE1BE703BDB85DA31ACF6948CF5BD90C55A20E5450F22FB89BD8D0085E39F85A86CC46ABBCA75E24D"));
+        test::<SHA512>(&[b'a'; 114], base16!("5E9EB0E4B270D086E77EEAF3CE8B1CFC615031B8C463DC34F5C139786F274F22ACCB4D89E8F40D1A0C2ACC84C4DC0F2BAB390A9D9495493BD617ED004271BB64"));
+        test::<SHA512>(&[b'a'; 115], base16!("EAA30F93760743AC7D0A6CB8ED5EF3B30C59097BC44D0EC337344301DEBA9FB92B20C488D55DE415F6AAED0DF4925B42894B81D2E1CDE89D91EC7F6CC67262B4"));
+        test::<SHA512>(&[b'a'; 116], base16!("A8BFF469314A1CE0C990BB3FD539D92ACCB6249CC674B559BC9D3898B7A126FEE597197FA42C971443470053C7D7F54B09371A59B0F7AF87B1917C5347E8F8E0"));
+        test::<SHA512>(&[b'a'; 117], base16!("C0C27AEA8DBE169C4CF25176CBF12DB708FD6303DB8CF94A1CFB402C1680D3D68F39BC5B9A10970DD5373CB0FE1CB36FA50E33165140D72933BA87AF9D5D1FFE"));
+        test::<SHA512>(&[b'a'; 118], base16!("D6F856C92A5A694DEC299F5A4765BED80E4E7431AA5505F82B21584DD1F1FE970F698BEC5A3F4FAA593D1AAC944A96C21B85463A773CDF3AD87C4A00FB9E5073"));
+        test::<SHA512>(&[b'a'; 119], base16!("130396A75CB483F2EEE8C56D8A668BB3D2641F5243212C0BEE2BD33DA096AD9EB8179FE18F9EAACF76E09FAE9DE4C3F14BA13341E345BE05BF76C182CC3468CB"));
+    }
+
+    #[test]
+    fn sha384_length_field() {
+        test::<SHA384>(&[b'a'; 112], base16!("187D4E07CB306103C69967BF544D0DFBE9042577599C73C330ABC0CB64C61236D5ED565EE19119D8C31779A38F791FCD"));
+        test::<SHA384>(&[b'a'; 113], base16!("1D6BED01626682961B50DA078A6B1DA707C1DA0C8A0A3226F159235BD45ED724A0622FA6F39FD70007A6C72A5CDA43AE"));
+        test::<SHA384>(&[b'a'; 114], base16!("71BF1D8B114E22AE79D07A408D541BA1F85273D275C9659A96516FD27A1F59E21E411264334C91B007DE12C65F930C12"));
+        test::<SHA384>(&[b'a'; 115], base16!("19FEEF0E8CFC9C4D50DFC01F72A3BC731156C28DC6BDC0BDB0EAE0FFB629F4BBEEDC4B6345F401FD4179FFFDDB2830A1"));
+        test::<SHA384>(&[b'a'; 116], base16!("165CEC38D11290308E5A0301033F659DE248168DC94C7B7E0A34687F752191CFB326C16B303FA48A26E104793A96CAB9"));
+        test::<SHA384>(&[b'a'; 117], base16!("A391EC897F0FC757AF583847A76226DCFCEF4BD948CC07C86BD4729FFE2622EE96321F398F29C0D0AF9C24CCC08947DD"));
+        test::<SHA384>(&[b'a'; 118], base16!("1EC4E9AF6DFB1B1F72A7D1CABF6DB0A289112AC54299E5CEB418EA72C47744770F7BD2BDE2AE7A9A4C5CF0DEAA78127F"));
+        test::<SHA384>(&[b'a'; 119], base16!("C2FBB1911D6889E3DB556B482236AB82F3C736F00A22C088641A09FDBBCA27E3F1E3B6235BAD20AEE1CA083C76AC590C"));
+    }
+
+    #[test]
+    fn sha512_t256_length_field() {
+        test::<SHA512T256>(&[b'a'; 112], base16!("9216B5303EDB66504570BEE90E48EA5BEAA5E9FE9F760BBD3E0460559FC005F6"));
+        test::<SHA512T256>(&[b'a'; 119], base16!("6CB452F832033489DE69B49D6A8E8A1E0018092F1986C132287F4CA4AB3EE545"));
+    }
+
+
+    #[test]
+    fn digest_encoding() {
+        use std::convert::TryFrom;
+
+        let hex = "d7a8fbb307d7809469ca9abcb0082e4f8d5651e46d3cdb762d02d0bf37c9e592";
+        let digest = SHA256::compute(b"The quick brown fox jumps over the lazy dog");
+        assert_eq!(digest.to_string(), hex);
+        assert_eq!(format!("{:X}", digest), hex.to_uppercase());
+        assert_eq!(hex.parse::<SHA256Digest>().unwrap(), digest);
+        assert_eq!(hex.to_uppercase().parse::<SHA256Digest>().unwrap(), digest);
+        assert_eq!(SHA256Digest::from_base64(&digest.to_base64()).unwrap(), digest);
+        assert_eq!(SHA256Digest::try_from(digest.as_ref()).unwrap(), digest);
+
+        assert_eq!(hex[..62].parse::<SHA256Digest>().unwrap_err(), ParseDigestError::InvalidLength);
+        assert_eq!(hex.replace('d', "x").parse::<SHA256Digest>().unwrap_err(), ParseDigestError::InvalidEncoding);
+        assert_eq!(SHA256Digest::try_from(&[0; 28][..]).unwrap_err(), ParseDigestError::InvalidLength);
+        assert!(!digest.ct_eq(&SHA256::compute(b"")));
+
+        let digest = SHA512T::<100>::compute(b"abc");
+        assert_eq!(format!("{:?}", digest), "SHA512TDigest<100>(\"36cc539a771da9ad5726499d80\")");
+        assert_eq!("36cc539a771da9ad5726499d80".parse::<SHA512TDigest<100>>().unwrap(), digest);
+        assert_eq!(SHA512TDigest::<100>::from_base64(&digest.to_base64()).unwrap(), digest);
+        assert_eq!(SHA512TDigest::<100>::try_from(&[0; 16][..]).unwrap_err(), ParseDigestError::InvalidLength);
+        // t = 100 leaves the low four bits of the last byte unused
+        assert_eq!("36cc539a771da9ad5726499d81".parse::<SHA512TDigest<100>>().unwrap_err(), ParseDigestError::InvalidEncoding);
+        assert_eq!("36cc539a771da9ad5726499d88".parse::<SHA512TDigest<100>>().unwrap_err(), ParseDigestError::InvalidEncoding);
+        let mut bytes = digest.as_ref().to_vec();
+        bytes[12] |= 0x01;
+        assert!(SHA512TDigest::<100>::from_base64(&::utils::base64::encode(&bytes)).is_err());
+    }
+
+    #[test]
+    fn compute_many() {
+        // Mixed lengths so that lanes finish at different times
+        let data: Vec<u8> = (0..300u32).map(|i| (i*7) as u8).collect();
+        let messages: Vec<&[u8]> = (0..40).map(|i| &data[..(i*37) % 300]).collect();
+
+        let actual = SHA256::compute_many(&messages);
+        assert_eq!(actual.len(), messages.len());
+        for (message, digest) in messages.iter().zip(&actual) {
+            assert_eq!(*digest, SHA256::compute(message));
+        }
+
+        let actual = SHA512::compute_many(&messages);
+        assert_eq!(actual.len(), messages.len());
+        for (message, digest) in messages.iter().zip(&actual) {
+            assert_eq!(*digest, SHA512::compute(message));
+        }
+
+        assert!(SHA256::compute_many(&[]).is_empty());
+        assert_eq!(SHA256::compute_many(&[b"abc"])[0], SHA256::compute(b"abc"));
+    }
+
+    #[test]
+    fn padded_chunk_length_field() {
+        // 0x123 bytes is 0x918 bits
+        let message = [0x61; 0x123];
+        let mut chunk = [0; 64];
+        padded_chunk(&message, 4, LengthPadding::BIG_ENDIAN_64, &mut chunk);
+        assert_eq!(&chunk[35..], base16!("8000000000000000000000000000000000000000000000000000000918"));
+        padded_chunk(&message, 4, LengthPadding::LITTLE_ENDIAN_64, &mut chunk);
+        assert_eq!(&chunk[35..], base16!("8000000000000000000000000000000000000000001809000000000000"));
+    }
+
+    #[test]
+    fn sha256_backends() {
+        use super::super::backend::Backend;
+
+        // Xorshift, so the inputs are reproducible
+        let mut seed = 0x2545f4914f6cdd1du64;
+        let mut next = || {
+            seed ^= seed << 13;
+            seed ^= seed >> 7;
+            seed ^= seed << 17;
+            seed as u32
+        };
+
+        for _ in 0..256 {
+            let mut h = [0; 8];
+            // Five chunks, so paired and single chunks are both covered
+            let mut chunks = [0; 320];
+            for word in &mut h {
+                *word = next();
+            }
+            for byte in &mut chunks[..] {
+                *byte = next() as u8;
+            }
+
+            let mut expected = h;
+            for chunk in chunks.chunks(64) {
+                sha256_update_chunk_with(Backend::Scalar, &mut expected, chunk);
+            }
+            for &backend in Backend::ALL {
+                if backend.is_available() {
+                    let mut actual = h;
+                    sha256_update_chunks_with(backend, &mut actual, &chunks);
+                    assert_eq!(actual, expected, "{:?}", backend);
+                }
+            }
+        }
+    }
+
+    fn test_bits<A: ChunkedDigestAlgorithm>(input: &[u8], bit_len: usize, expected: &[u8]) {
+        let mut state = ChunkedDigestWrapper::<A>::new();
+        state.update_bits(input, bit_len);
+        assert!(state.digest().as_ref() == expected);
+    }
+
+    // Bit-oriented vectors from the SHAVS response files
+    #[test]
+    fn bit_oriented() {
+        test_bits::<SHA224Chunked>(&[0x68], 5, base16!("E3B048552C3C387BCAB37F6EB06BB79B96A4AEE5FF27F51531A9551C"));
+        test_bits::<SHA256Chunked>(&[0x68], 5, base16!("D6D3E02A31A84A8CAA9718ED6C2057BE09DB45E7823EB5079CE7A573A3760F95"));
+        test_bits::<SHA256Chunked>(base16!("BE2746C6DB52765FDB2F88700F9A7360"), 123, base16!("77EC1DC89C821FF2A1279089FA091B35B8CD960BCAF7DE01C6A7680756BEB972"));
+        test_bits::<SHA384Chunked>(&[0x10], 5, base16!("8D17BE79E32B6718E07D8A603EB84BA0478F7FCFD1BB93995F7D1149E09143AC1FFCFC56820E469F3878D957A15A3FE4"));
+        test_bits::<SHA512Chunked>(&[0xB0], 5, base16!("D4EE29A9E90985446B913CF1D1376C836F4BE2C1CF3CADA0720A6BF4857D886A7ECB3C4E4C0FA8C7F95214E41DC1B0D21B22A84CC03BF8CE4845F34DD5BDBAD4"));
+
+        // Whole bytes behave like `update`, and unused low bits are ignored
+        test_bits::<SHA256Chunked>(b"abc", 24, SHA256::compute(b"abc").as_ref());
+        test_bits::<SHA256Chunked>(&[0x6f], 5, base16!("D6D3E02A31A84A8CAA9718ED6C2057BE09DB45E7823EB5079CE7A573A3760F95"));
+    }
+
+    #[test]
+    #[should_panic]
+    fn update_after_partial_byte() {
+        let mut state = SHA256::new();
+        state.update_bits(&[0x68], 5);
+        state.update(b"a");
+    }
+
+    #[test]
+    #[should_panic(expected = "input after a partial byte")]
+    fn partial_byte_after_partial_byte() {
+        let mut state = SHA256::new();
+        state.update_bits(&[0x68], 5);
+        state.update_bits(&[0x68], 3);
+    }
+
+    #[test]
+    #[should_panic(expected = "bit_len exceeds the length of data")]
+    fn update_bits_too_long() {
+        let mut state = SHA256::new();
+        state.update_bits(&[0x68], 9);
+    }
 }
@@ -0,0 +1,327 @@
+use std::cmp;
+use std::io::{self, Write};
+use byteorder::{ByteOrder, LittleEndian};
+use super::{DigestAlgorithm, XofAlgorithm, XofReader};
+
+// Round constants
+const RC: [u64; 24] = [
+    0x0000000000000001, 0x0000000000008082, 0x800000000000808a, 0x8000000080008000,
+    0x000000000000808b, 0x0000000080000001, 0x8000000080008081, 0x8000000000008009,
+    0x000000000000008a, 0x0000000000000088, 0x0000000080008009, 0x000000008000000a,
+    0x000000008000808b, 0x800000000000008b, 0x8000000000008089, 0x8000000000008003,
+    0x8000000000008002, 0x8000000000000080, 0x000000000000800a, 0x800000008000000a,
+    0x8000000080008081, 0x8000000000008080, 0x0000000080000001, 0x8000000080008008
+];
+
+// Rotation offsets and destination lanes for the combined rho and pi steps,
+// following the lane visited at each step of the pi cycle starting from lane 1.
+const RHO: [u32; 24] = [
+    1, 3, 6, 10, 15, 21, 28, 36, 45, 55, 2, 14, 27, 41, 56, 8, 25, 43, 62, 18, 39, 61, 20, 44
+];
+const PI: [usize; 24] = [
+    10, 7, 11, 17, 18, 3, 5, 16, 8, 21, 24, 4, 15, 23, 19, 13, 12, 2, 20, 14, 22, 9, 6, 1
+];
+
+// Domain separation suffixes from FIPS 202, combined with the first bit of padding
+const SHA3_DOMAIN: u8 = 0x06;
+const SHAKE_DOMAIN: u8 = 0x1f;
+
+pub fn keccak_f1600(a: &mut [u64; 25]) {
+    for &rc in &RC {
+        // Theta
+        let mut c = [0; 5];
+        for x in 0..5 {
+            c[x] = a[x] ^ a[x + 5] ^ a[x + 10] ^ a[x + 15] ^ a[x + 20];
+        }
+        for x in 0..5 {
+            let d = c[(x + 4) % 5] ^ c[(x + 1) % 5].rotate_left(1);
+            for y in 0..5 {
+                a[x + 5*y] ^= d;
+            }
+        }
+
+        // Rho and pi
+        let mut last = a[1];
+        for i in 0..24 {
+            let temp = a[PI[i]];
+            a[PI[i]] = last.rotate_left(RHO[i]);
+            last = temp;
+        }
+
+        // Chi
+        for y in 0..5 {
+            let mut row = [0; 5];
+            row.copy_from_slice(&a[5*y..5*y + 5]);
+            for x in 0..5 {
+                a[x + 5*y] = row[x] ^ (!row[(x + 1) % 5] & row[(x + 2) % 5]);
+            }
+        }
+
+        // Iota
+        a[0] ^= rc;
+    }
+}
+
+// Keccak sponge over Keccak-f[1600], with the rate given in bytes
+#[derive(Debug, Clone)]
+struct KeccakSponge {
+    a: [u64; 25],
+    rate: usize,
+    // Position within the current block, when absorbing or squeezing
+    offset: usize
+}
+
+impl KeccakSponge {
+    fn new(rate: usize) -> Self {
+        KeccakSponge {
+            a: [0; 25],
+            rate,
+            offset: 0
+        }
+    }
+
+    fn xor_byte(&mut self, index: usize, byte: u8) {
+        self.a[index/8] ^= (byte as u64) << (8*(index % 8));
+    }
+
+    fn absorb(&mut self, mut input: &[u8]) {
+        let rate = self.rate;
+        while !input.is_empty() {
+            if self.offset == 0 && input.len() >= rate {
+                // Whole blocks can be absorbed a lane at a time
+                for (lane, bytes) in self.a.iter_mut().zip(input[..rate].chunks(8)) {
+                    *lane ^= LittleEndian::read_u64(bytes);
+                }
+                keccak_f1600(&mut self.a);
+                input = &input[rate..];
+            } else {
+                let n = cmp::min(rate - self.offset, input.len());
+                for (i, &byte) in input[..n].iter().enumerate() {
+                    let index = self.offset + i;
+                    self.xor_byte(index, byte);
+                }
+                self.offset += n;
+                input = &input[n..];
+                if self.offset == rate {
+                    keccak_f1600(&mut self.a);
+                    self.offset = 0;
+                }
+            }
+        }
+    }
+
+    // Applies the domain suffix and pad10*1, switching the sponge to squeezing
+    fn finish(&mut self, domain: u8) {
+        let (offset, rate) = (self.offset, self.rate);
+        self.xor_byte(offset, domain);
+        self.xor_byte(rate - 1, 0x80);
+        keccak_f1600(&mut self.a);
+        self.offset = 0;
+    }
+
+    fn squeeze(&mut self, output: &mut [u8]) {
+        for byte in output {
+            if self.offset == self.rate {
+                keccak_f1600(&mut self.a);
+                self.offset = 0;
+            }
+            *byte = (self.a[self.offset/8] >> (8*(self.offset % 8))) as u8;
+            self.offset += 1;
+        }
+    }
+}
+
+macro_rules! define_sha3 {
+    ($name:ident, $digest:ident, $size:expr) => {
+        define_digest!($digest, $size);
+
+        #[derive(Debug, Clone)]
+        pub struct $name {
+            sponge: KeccakSponge
+        }
+
+        impl DigestAlgorithm for $name {
+            type Digest = $digest;
+
+            fn new() -> Self {
+                $name {
+                    sponge: KeccakSponge::new(Self::block_len())
+                }
+            }
+
+            // Capacity is twice the output size
+            fn block_len() -> usize {
+                200 - 2*$size
+            }
+
+            fn update(&mut self, input: &[u8]) {
+                self.sponge.absorb(input);
+            }
+
+            fn digest(mut self) -> Self::Digest {
+                let mut result = [0; $size];
+                self.sponge.finish(SHA3_DOMAIN);
+                self.sponge.squeeze(&mut result);
+                $digest(result)
+            }
+        }
+
+        impl Write for $name {
+            fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+                self.update(buf);
+                Ok(buf.len())
+            }
+
+            fn flush(&mut self) -> io::Result<()> {
+                Ok(())
+            }
+        }
+    }
+}
+
+macro_rules! define_shake {
+    ($name:ident, $reader:ident, $rate:expr) => {
+        #[derive(Debug, Clone)]
+        pub struct $name {
+            sponge: KeccakSponge
+        }
+
+        #[derive(Debug, Clone)]
+        pub struct $reader {
+            sponge: KeccakSponge
+        }
+
+        impl XofAlgorithm for $name {
+            type Reader = $reader;
+
+            fn new() -> Self {
+                $name {
+                    sponge: KeccakSponge::new($rate)
+                }
+            }
+
+            fn block_len() -> usize {
+                $rate
+            }
+
+            fn update(&mut self, input: &[u8]) {
+                self.sponge.absorb(input);
+            }
+
+            fn finalize(mut self) -> Self::Reader {
+                self.sponge.finish(SHAKE_DOMAIN);
+                $reader {
+                    sponge: self.sponge
+                }
+            }
+        }
+
+        impl XofReader for $reader {
+            fn squeeze(&mut self, output: &mut [u8]) {
+                self.sponge.squeeze(output);
+            }
+        }
+
+        impl Write for $name {
+            fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+                self.update(buf);
+                Ok(buf.len())
+            }
+
+            fn flush(&mut self) -> io::Result<()> {
+                Ok(())
+            }
+        }
+    }
+}
+
+define_sha3!(SHA3_224, SHA3_224Digest, 28);
+define_sha3!(SHA3_256, SHA3_256Digest, 32);
+define_sha3!(SHA3_384, SHA3_384Digest, 48);
+define_sha3!(SHA3_512, SHA3_512Digest, 64);
+
+define_shake!(SHAKE128, SHAKE128Reader, 168);
+define_shake!(SHAKE256, SHAKE256Reader, 136);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test<A: DigestAlgorithm>(input: &[u8], expected: &[u8]) {
+        let actual = A::compute(input);
+        println!("A: {:?}", actual.as_ref());
+        println!("B: {:?}", expected);
+        assert!(actual.as_ref() == expected)
+    }
+
+    fn test_xof<A: XofAlgorithm>(input: &[u8], expected: &[u8]) {
+        let actual = A::compute(input, expected.len());
+        println!("A: {:?}", actual);
+        println!("B: {:?}", expected);
+        assert!(&actual[..] == expected)
+    }
+
+    // 1600-bit message used by the NIST FIPS 202 examples
+    const A3_1600: &[u8] = &[0xa3; 200];
+
+    #[test]
+    fn sha3_224() {
+        test::<SHA3_224>(b"", base16!("6B4E03423667DBB73B6E15454F0EB1ABD4597F9A1B078E3F5B5A6BC7"));
+        test::<SHA3_224>(b"abc", base16!("E642824C3F8CF24AD09234EE7D3C766FC9A3A5168D0C94AD73B46FDF"));
+        test::<SHA3_224>(A3_1600, base16!("9376816ABA503F72F96CE7EB65AC095DEEE3BE4BF9BBC2A1CB7E11E0"));
+    }
+
+    #[test]
+    fn sha3_256() {
+        test::<SHA3_256>(b"", base16!("A7FFC6F8BF1ED76651C14756A061D662F580FF4DE43B49FA82D80A4B80F8434A"));
+        test::<SHA3_256>(b"abc", base16!("3A985DA74FE225B2045C172D6BD390BD855F086E3E9D525B46BFE24511431532"));
+        test::<SHA3_256>(A3_1600, base16!("79F38ADEC5C20307A98EF76E8324AFBFD46CFD81B22E3973C65FA1BD9DE31787"));
+    }
+
+    #[test]
+    fn sha3_384() {
+        test::<SHA3_384>(b"", base16!("0C63A75B845E4F7D01107D852E4C2485C51A50AAAA94FC61995E71BBEE983A2AC3713831264ADB47FB6BD1E058D5F004"));
+        test::<SHA3_384>(b"abc", base16!("EC01498288516FC926459F58E2C6AD8DF9B473CB0FC08C2596DA7CF0E49BE4B298D88CEA927AC7F539F1EDF228376D25"));
+        test::<SHA3_384>(A3_1600, base16!("1881DE2CA7E41EF95DC4732B8F5F002B189CC1E42B74168ED1732649CE1DBCDD76197A31FD55EE989F2D7050DD473E8F"));
+    }
+
+    #[test]
+    fn sha3_512() {
+        test::<SHA3_512>(b"", base16!("A69F73CCA23A9AC5C8B567DC185A756E97C982164FE25859E0D1DCC1475C80A615B2123AF1F5F94C11E3E9402C3AC558F500199D95B6D3E301758586281DCD26"));
+        test::<SHA3_512>(b"abc", base16!("B751850B1A57168A5693CD924B6B096E08F621827444F70D884F5D0240D2712E10E116E9192AF3C91A7EC57647E3934057340B4CF408D5A56592F8274EEC53F0"));
+        test::<SHA3_512>(A3_1600, base16!("E76DFAD22084A8B1467FCF2FFA58361BEC7628EDF5F3FDC0E4805DC48CAEECA81B7C13C30ADF52A3659584739A2DF46BE589C51CA1A4A8416DF6545A1CE8BA00"));
+    }
+
+    #[test]
+    fn shake128() {
+        test_xof::<SHAKE128>(b"", base16!("7F9C2BA4E88F827D616045507605853ED73B8093F6EFBC88EB1A6EACFA66EF26"));
+        test_xof::<SHAKE128>(b"abc", base16!("5881092DD818BF5CF8A3DDB793FBCBA74097D5C526A6D35F97B83351940F2CC8"));
+        test_xof::<SHAKE128>(A3_1600, base16!("131AB8D2B594946B9C81333F9BB6E0CE75C3B93104FA3469D3917457385DA037"));
+    }
+
+    #[test]
+    fn shake256() {
+        test_xof::<SHAKE256>(b"", base16!("46B9DD2B0BA88D13233B3FEB743EEB243FCD52EA62B81B82B50C27646ED5762FD75DC4DDD8C0F200CB05019D67B592F6FC821C49479AB48640292EACB3B7C4BE"));
+        test_xof::<SHAKE256>(b"abc", base16!("483366601360A8771C6863080CC4114D8DB44530F8F1E1EE4F94EA37E78B5739D5A15BEF186A5386C75744C0527E1FAA9F8726E462A12A4FEB06BD8801E751E4"));
+        test_xof::<SHAKE256>(A3_1600, base16!("CD8A920ED141AA0407A22D59288652E9D9F1A7EE0C1E7C1CA699424DA84A904D2D700CAAE7396ECE96604440577DA4F3AA22AEB8857F961C4CD8E06F0AE6610B"));
+    }
+
+    #[test]
+    fn incremental() {
+        let mut state = SHAKE128::new();
+        for part in A3_1600.chunks(17) {
+            state.update(part);
+        }
+        let mut reader = state.finalize();
+
+        // Squeeze 512 bytes in uneven pieces, crossing several block boundaries
+        let mut output = vec![0; 512];
+        let mut offset = 0;
+        for &n in [1, 166, 3, 200, 142].iter() {
+            reader.squeeze(&mut output[offset..offset + n]);
+            offset += n;
+        }
+        assert!(output == SHAKE128::compute(A3_1600, 512));
+        assert!(output[480..] == base16!("44C9FB359FD56AC0A9A75A743CFF6862F17D7259AB075216C0699511643B6439")[..]);
+    }
+}
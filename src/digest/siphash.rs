@@ -0,0 +1,369 @@
+// SipHash, a keyed hash for short inputs, with the 64-bit and 128-bit outputs
+// from the reference implementation. The number of compression and
+// finalization rounds are const parameters: SipHash-2-4 is the recommended
+// variant, and SipHash-1-3 is faster with a smaller security margin.
+//
+// The main use is in hash tables, where a secret key stops attackers from
+// picking keys that all collide. `SipBuildHasher` plugs into `HashMap`.
+
+use std::cmp;
+use std::fmt;
+use std::hash::{BuildHasher, Hasher};
+use std::io::{self, Write};
+use byteorder::{ByteOrder, LittleEndian};
+use utils::zeroize::Zeroize;
+
+define_digest!(SipHash64Digest, 8);
+define_digest!(SipHash128Digest, 16);
+
+#[derive(Clone)]
+struct SipState<const C: usize, const D: usize> {
+    v: [u64; 4],
+    // Input bytes that don't yet make up a whole word, little-endian
+    tail: u64,
+    tail_len: usize,
+    // Total input length, of which only the low byte is used
+    len: u64
+}
+
+impl<const C: usize, const D: usize> SipState<C, D> {
+    fn new(key: &[u8; 16], wide: bool) -> Self {
+        let k0 = LittleEndian::read_u64(&key[..8]);
+        let k1 = LittleEndian::read_u64(&key[8..]);
+        let mut v = [
+            k0 ^ 0x736f6d6570736575,
+            k1 ^ 0x646f72616e646f6d,
+            k0 ^ 0x6c7967656e657261,
+            k1 ^ 0x7465646279746573
+        ];
+        if wide {
+            v[1] ^= 0xee;
+        }
+        SipState {
+            v,
+            tail: 0,
+            tail_len: 0,
+            len: 0
+        }
+    }
+
+    fn rounds(&mut self, n: usize) {
+        let v = &mut self.v;
+        for _ in 0..n {
+            v[0] = v[0].wrapping_add(v[1]);
+            v[1] = v[1].rotate_left(13) ^ v[0];
+            v[0] = v[0].rotate_left(32);
+            v[2] = v[2].wrapping_add(v[3]);
+            v[3] = v[3].rotate_left(16) ^ v[2];
+            v[0] = v[0].wrapping_add(v[3]);
+            v[3] = v[3].rotate_left(21) ^ v[0];
+            v[2] = v[2].wrapping_add(v[1]);
+            v[1] = v[1].rotate_left(17) ^ v[2];
+            v[2] = v[2].rotate_left(32);
+        }
+    }
+
+    fn compress(&mut self, m: u64) {
+        self.v[3] ^= m;
+        self.rounds(C);
+        self.v[0] ^= m;
+    }
+
+    fn update(&mut self, mut input: &[u8]) {
+        self.len = self.len.wrapping_add(input.len() as u64);
+
+        if self.tail_len > 0 {
+            let n = cmp::min(8 - self.tail_len, input.len());
+            for (i, &byte) in input[..n].iter().enumerate() {
+                self.tail |= (byte as u64) << (8*(self.tail_len + i));
+            }
+            self.tail_len += n;
+            input = &input[n..];
+            if self.tail_len < 8 {
+                return;
+            }
+            let m = self.tail;
+            self.compress(m);
+            self.tail = 0;
+            self.tail_len = 0;
+        }
+
+        let mut words = input.chunks_exact(8);
+        for word in &mut words {
+            self.compress(LittleEndian::read_u64(word));
+        }
+        for (i, &byte) in words.remainder().iter().enumerate() {
+            self.tail |= (byte as u64) << (8*i);
+        }
+        self.tail_len = words.remainder().len();
+    }
+
+    fn output_word(&self) -> u64 {
+        self.v[0] ^ self.v[1] ^ self.v[2] ^ self.v[3]
+    }
+
+    // The last word holds the remaining input and the length in its top byte
+    fn finish(&mut self, wide: bool) -> u64 {
+        let m = (self.len << 56) | self.tail;
+        self.compress(m);
+        self.v[2] ^= if wide { 0xee } else { 0xff };
+        self.rounds(D);
+        self.output_word()
+    }
+
+    // Second half of the 128-bit output, after `finish`
+    fn finish_high(&mut self) -> u64 {
+        self.v[1] ^= 0xdd;
+        self.rounds(D);
+        self.output_word()
+    }
+}
+
+impl<const C: usize, const D: usize> Zeroize for SipState<C, D> {
+    fn zeroize(&mut self) {
+        self.v.zeroize();
+        self.tail.zeroize();
+    }
+}
+
+impl<const C: usize, const D: usize> Drop for SipState<C, D> {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
+// SipHash with 64-bit output, which is also what `Hasher::finish` returns
+#[derive(Clone)]
+pub struct SipHasher<const C: usize, const D: usize> {
+    state: SipState<C, D>
+}
+
+impl<const C: usize, const D: usize> SipHasher<C, D> {
+    pub fn new(key: &[u8; 16]) -> Self {
+        SipHasher {
+            state: SipState::new(key, false)
+        }
+    }
+
+    pub fn update(&mut self, input: &[u8]) {
+        self.state.update(input);
+    }
+
+    pub fn finalize(mut self) -> SipHash64Digest {
+        let mut result = [0; 8];
+        LittleEndian::write_u64(&mut result, self.state.finish(false));
+        SipHash64Digest(result)
+    }
+
+    pub fn compute(key: &[u8; 16], input: &[u8]) -> SipHash64Digest {
+        let mut state = Self::new(key);
+        state.update(input);
+        state.finalize()
+    }
+}
+
+impl<const C: usize, const D: usize> Hasher for SipHasher<C, D> {
+    fn write(&mut self, bytes: &[u8]) {
+        self.update(bytes);
+    }
+
+    fn finish(&self) -> u64 {
+        self.state.clone().finish(false)
+    }
+}
+
+// Debug output leaves out the state, from which the key can be recovered
+impl<const C: usize, const D: usize> fmt::Debug for SipHasher<C, D> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("SipHasher").finish_non_exhaustive()
+    }
+}
+
+impl<const C: usize, const D: usize> Write for SipHasher<C, D> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.update(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+// SipHash with 128-bit output. The state is initialised and finalized
+// differently from the 64-bit variant, so the first half of the output isn't
+// the 64-bit hash.
+#[derive(Clone)]
+pub struct SipHasher128<const C: usize, const D: usize> {
+    state: SipState<C, D>
+}
+
+impl<const C: usize, const D: usize> SipHasher128<C, D> {
+    pub fn new(key: &[u8; 16]) -> Self {
+        SipHasher128 {
+            state: SipState::new(key, true)
+        }
+    }
+
+    pub fn update(&mut self, input: &[u8]) {
+        self.state.update(input);
+    }
+
+    pub fn finalize(mut self) -> SipHash128Digest {
+        let mut result = [0; 16];
+        LittleEndian::write_u64(&mut result[..8], self.state.finish(true));
+        LittleEndian::write_u64(&mut result[8..], self.state.finish_high());
+        SipHash128Digest(result)
+    }
+
+    pub fn compute(key: &[u8; 16], input: &[u8]) -> SipHash128Digest {
+        let mut state = Self::new(key);
+        state.update(input);
+        state.finalize()
+    }
+}
+
+impl<const C: usize, const D: usize> fmt::Debug for SipHasher128<C, D> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("SipHasher128").finish_non_exhaustive()
+    }
+}
+
+impl<const C: usize, const D: usize> Write for SipHasher128<C, D> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.update(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+pub type SipHash24 = SipHasher<2, 4>;
+pub type SipHash13 = SipHasher<1, 3>;
+pub type SipHash128_24 = SipHasher128<2, 4>;
+pub type SipHash128_13 = SipHasher128<1, 3>;
+
+// Builds keyed SipHash hashers for `HashMap` and `HashSet`, using SipHash-2-4
+// unless other round counts are given:
+//
+//     let map: HashMap<K, V, SipBuildHasher> = HashMap::with_hasher(SipBuildHasher::new(&key));
+//
+// The key should be random and kept secret from anyone supplying the keys of
+// the map.
+#[derive(Clone)]
+pub struct SipBuildHasher<const C: usize = 2, const D: usize = 4> {
+    key: [u8; 16]
+}
+
+impl<const C: usize, const D: usize> SipBuildHasher<C, D> {
+    pub fn new(key: &[u8; 16]) -> Self {
+        SipBuildHasher {
+            key: *key
+        }
+    }
+}
+
+impl<const C: usize, const D: usize> BuildHasher for SipBuildHasher<C, D> {
+    type Hasher = SipHasher<C, D>;
+
+    fn build_hasher(&self) -> Self::Hasher {
+        SipHasher::new(&self.key)
+    }
+}
+
+impl<const C: usize, const D: usize> fmt::Debug for SipBuildHasher<C, D> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("SipBuildHasher").finish_non_exhaustive()
+    }
+}
+
+impl<const C: usize, const D: usize> Drop for SipBuildHasher<C, D> {
+    fn drop(&mut self) {
+        self.key.zeroize();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use super::*;
+
+    const KEY: &[u8; 16] = b"\x00\x01\x02\x03\x04\x05\x06\x07\x08\x09\x0a\x0b\x0c\x0d\x0e\x0f";
+
+    // From vectors.h in the reference implementation, with the input counting
+    // up from zero. There are no official SipHash-1-3 vectors, so those are
+    // from other implementations.
+    #[test]
+    fn reference_vectors() {
+        let cases: &[(usize, [&[u8]; 4])] = &[
+            (0, [base16!("310E0EDD47DB6F72"), base16!("DCC40F055801ACAB"), base16!("A3817F04BA25A8E66DF67214C7550293"), base16!("E77EBCB22788A5BEFD62DB6ADD303001")]),
+            (1, [base16!("FD67DC93C539F874"), base16!("93CA577DF39BF4C9"), base16!("DA87C1D86B99AF44347659119B22FC45"), base16!("FC6F370460D3EDA85E0573CC2B2FF063")]),
+            (7, [base16!("37D1018BF50002AB"), base16!("4011B19B987D92D3"), base16!("A1F1EBBED8DBC153C0B84AA61FF08239"), base16!("1084B923F2AAE0C3A62F2EC80848AB77")]),
+            (8, [base16!("6224939A79F5F593"), base16!("8E9A298D11959036"), base16!("3B62A9BA6258F5610F83E264F31497B4"), base16!("AA12FEE1D5E3DAB4724F16AB35F9C799")]),
+            (15, [base16!("E545BE4961CA29A1"), base16!("5699512A6DD820D3"), base16!("5493E99933B0A8117E08EC0F97CFC3D9"), base16!("C17E5505B2BD526C2921CDEC1E7E0109")]),
+            (16, [base16!("DB9BC2577FCC2A3F"), base16!("668B907D1ADD4FCC"), base16!("6EE2A4CA67B054BBFD3315BF85230577"), base16!("D0A8D95715518EEBB513B0F83D9E1793")]),
+            (63, [base16!("724506EB4C328A95"), base16!("A8B3BBB76290199D"), base16!("5150D1772F50834A503E069A973FBD7C"), base16!("4C5800E34EFE426F079F6B0AA75260AD")])
+        ];
+        let data: Vec<u8> = (0..64).collect();
+        for &(len, [sip24, sip13, sip128_24, sip128_13]) in cases {
+            let input = &data[..len];
+            assert!(SipHash24::compute(KEY, input).as_ref() == sip24);
+            assert!(SipHash13::compute(KEY, input).as_ref() == sip13);
+            assert!(SipHash128_24::compute(KEY, input).as_ref() == sip128_24);
+            assert!(SipHash128_13::compute(KEY, input).as_ref() == sip128_13);
+
+            let mut hasher = SipHash24::new(KEY);
+            Hasher::write(&mut hasher, input);
+            assert_eq!(hasher.finish(), LittleEndian::read_u64(sip24));
+        }
+    }
+
+    #[test]
+    fn incremental() {
+        let data: Vec<u8> = (0..64).collect();
+        let expected = SipHash128_24::compute(KEY, &data);
+        for &split in &[0, 1, 3, 7, 8, 9, 20, 63] {
+            let mut state = SipHash128_24::new(KEY);
+            state.update(&data[..split]);
+            state.update(&data[split..split + 1]);
+            state.update(&data[split + 1..]);
+            assert_eq!(state.finalize(), expected);
+        }
+
+        // `finish` doesn't consume the state
+        let mut hasher = SipHash13::new(KEY);
+        Hasher::write(&mut hasher, &data[..5]);
+        let first = hasher.finish();
+        assert_eq!(hasher.finish(), first);
+        Hasher::write(&mut hasher, &data[5..20]);
+        assert_eq!(hasher.finish(), LittleEndian::read_u64(SipHash13::compute(KEY, &data[..20]).as_ref()));
+    }
+
+    #[test]
+    fn build_hasher() {
+        let mut map: HashMap<&str, u32, SipBuildHasher> = HashMap::with_hasher(SipBuildHasher::new(KEY));
+        map.insert("one", 1);
+        map.insert("two", 2);
+        assert_eq!(map.get("one"), Some(&1));
+        assert_eq!(map.get("two"), Some(&2));
+        assert_eq!(map.get("three"), None);
+
+        let build = SipBuildHasher::<1, 3>::new(KEY);
+        let mut hasher = build.build_hasher();
+        Hasher::write(&mut hasher, b"abc");
+        assert_eq!(hasher.finish(), LittleEndian::read_u64(SipHash13::compute(KEY, b"abc").as_ref()));
+
+        // Different keys give different hashes
+        let other = SipBuildHasher::<2, 4>::new(b"another 16b key!");
+        assert!(SipBuildHasher::<2, 4>::new(KEY).hash_one("abc") != other.hash_one("abc"));
+    }
+
+    #[test]
+    fn debug_hides_key() {
+        assert_eq!(format!("{:?}", SipBuildHasher::<2, 4>::new(KEY)), "SipBuildHasher { .. }");
+        assert_eq!(format!("{:?}", SipHash24::new(KEY)), "SipHasher { .. }");
+        assert_eq!(format!("{:?}", SipHash128_24::new(KEY)), "SipHasher128 { .. }");
+    }
+}
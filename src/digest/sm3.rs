@@ -0,0 +1,155 @@
+use std::io::Cursor;
+use super::chunked::{ChunkedDigestAlgorithm, ChunkedDigestWrapper, LengthPadding};
+use byteorder::{ReadBytesExt, WriteBytesExt, BigEndian};
+use utils::zeroize::Zeroize;
+
+define_digest!(SM3Digest, 32);
+define_chunk!(SM3Chunk, 64);
+
+// Permutations used in compression and message expansion
+fn p0(x: u32) -> u32 {
+    x ^ x.rotate_left(9) ^ x.rotate_left(17)
+}
+
+fn p1(x: u32) -> u32 {
+    x ^ x.rotate_left(15) ^ x.rotate_left(23)
+}
+
+#[derive(Debug, Clone)]
+pub struct SM3Chunked {
+    h: [u32; 8],
+}
+
+impl Zeroize for SM3Chunked {
+    fn zeroize(&mut self) {
+        self.h.zeroize();
+    }
+}
+
+impl Drop for SM3Chunked {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
+impl ChunkedDigestAlgorithm for SM3Chunked {
+    type Digest = SM3Digest;
+    type Chunk = SM3Chunk;
+
+    const PADDING: LengthPadding = LengthPadding::BIG_ENDIAN_64;
+    const NAME: &'static str = "sm3";
+
+    fn new() -> Self {
+        SM3Chunked {
+            h: [
+                0x7380166f, 0x4914b2b9, 0x172442d7, 0xda8a0600,
+                0xa96f30bc, 0x163138aa, 0xe38dee4d, 0xb0fb0e4e
+            ]
+        }
+    }
+
+    fn update_chunk(&mut self, chunk: &[u8]) {
+        // Expand to 68 words
+        let mut w = [0; 68];
+        let mut reader = Cursor::new(chunk);
+        for word in &mut w[..16] {
+            *word = reader.read_u32::<BigEndian>().unwrap();
+        }
+        for j in 16..68 {
+            w[j] = p1(w[j-16] ^ w[j-9] ^ w[j-3].rotate_left(15)) ^ w[j-13].rotate_left(7) ^ w[j-6];
+        }
+
+        // Copy hash state
+        let mut h = self.h;
+
+        for j in 0..64 {
+            // The first 16 rounds use XOR in place of majority and choose
+            let (ff, gg, t) = if j < 16 {
+                (h[0] ^ h[1] ^ h[2], h[4] ^ h[5] ^ h[6], 0x79cc4519u32)
+            } else {
+                (
+                    (h[0] & h[1]) | (h[0] & h[2]) | (h[1] & h[2]),
+                    (h[4] & h[5]) | (!h[4] & h[6]),
+                    0x7a879d8a
+                )
+            };
+            let a12 = h[0].rotate_left(12);
+            let ss1 = a12
+                .wrapping_add(h[4])
+                .wrapping_add(t.rotate_left(j as u32 % 32))
+                .rotate_left(7);
+            let ss2 = ss1 ^ a12;
+            let tt1 = ff
+                .wrapping_add(h[3])
+                .wrapping_add(ss2)
+                .wrapping_add(w[j] ^ w[j+4]);
+            let tt2 = gg
+                .wrapping_add(h[7])
+                .wrapping_add(ss1)
+                .wrapping_add(w[j]);
+            h = [tt1, h[0], h[1].rotate_left(9), h[2], p0(tt2), h[4], h[5].rotate_left(19), h[6]];
+        }
+
+        // Update hash state
+        for (state, &value) in self.h.iter_mut().zip(&h) {
+            *state ^= value;
+        }
+    }
+
+    fn digest(self) -> Self::Digest {
+        let mut result = [0; 32];
+        {
+            let mut writer = Cursor::new(&mut result[..]);
+            for &h in &self.h {
+                writer.write_u32::<BigEndian>(h).unwrap();
+            }
+        }
+        SM3Digest(result)
+    }
+
+    fn export_state(&self, out: &mut Vec<u8>) {
+        for &h in &self.h {
+            out.write_u32::<BigEndian>(h).unwrap();
+        }
+    }
+
+    fn import_state(state: &[u8]) -> Option<Self> {
+        if state.len() != 32 {
+            return None;
+        }
+        let mut h = [0; 8];
+        let mut reader = Cursor::new(state);
+        for word in &mut h {
+            *word = reader.read_u32::<BigEndian>().unwrap();
+        }
+        Some(SM3Chunked {
+            h
+        })
+    }
+}
+
+pub type SM3 = ChunkedDigestWrapper<SM3Chunked>;
+
+#[cfg(test)]
+mod tests {
+    use super::super::DigestAlgorithm;
+    use super::*;
+
+    fn test_sm3(input: &[u8], expected: &[u8]) {
+        let actual = SM3::compute(input);
+        println!("A: {:?}", actual);
+        println!("B: {:?}", expected);
+        assert!(actual.as_ref() == expected)
+    }
+
+    #[test]
+    fn sm3() {
+        // Examples from appendix A of GB/T 32905-2016
+        test_sm3(b"abc", base16!("66C7F0F462EEEDD9D1F2D46BDC10E4E24167C4875CF2F7A2297DA02B8F4BA8E0"));
+        test_sm3(&b"abcd".repeat(16), base16!("DEBE9FF92275B8A138604889C18E5A4D6FDB70E5387E5765293DCBA39C0C5732"));
+
+        test_sm3(b"", base16!("1AB21D8355CFA17F8E61194831E81A8F22BEC8C728FEFB747ED035EB5082AA2B"));
+        test_sm3(b"The quick brown fox jumps over the lazy dog", base16!("5FDFE814B8573CA021983970FC79B2218C9570369B4859684E2E4C3FC76CB8EA"));
+        test_sm3(&[b'a'; 1000000], base16!("C8AAF89429554029E231941A2ACC0AD61FF2A5ACD8FADD25847A3A732B3B02C3"));
+    }
+}
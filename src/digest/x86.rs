@@ -0,0 +1,270 @@
+// x86_64 implementations of the SHA-1 and SHA-256 compression functions.
+// Callers must check that the required CPU features are present, see
+// `Backend::is_available`.
+
+use std::arch::x86_64::*;
+
+use super::sha2::SHA256_K;
+
+// Byte shuffle converting four big-endian words to native order
+unsafe fn load_be32x4(ptr: *const u8) -> __m128i {
+    let mask = _mm_set_epi64x(0x0c0d0e0f08090a0b, 0x0405060700010203);
+    _mm_shuffle_epi8(_mm_loadu_si128(ptr as *const __m128i), mask)
+}
+
+macro_rules! rotr {
+    ($x:expr, $n:expr) => {
+        _mm_or_si128(_mm_srli_epi32::<$n>($x), _mm_slli_epi32::<{32 - $n}>($x))
+    }
+}
+
+macro_rules! rotl {
+    ($x:expr, $n:expr) => {
+        _mm_or_si128(_mm_slli_epi32::<$n>($x), _mm_srli_epi32::<{32 - $n}>($x))
+    }
+}
+
+// SHA-256 message schedule, four words at a time
+#[target_feature(enable = "sse2,ssse3")]
+pub unsafe fn sha256_schedule_ssse3(chunk: &[u8]) -> [u32; 64] {
+    assert!(chunk.len() == 64);
+    let mut w = [0; 64];
+    let out = w.as_mut_ptr() as *mut __m128i;
+    let mut x = [_mm_setzero_si128(); 4];
+    for (i, x) in x.iter_mut().enumerate() {
+        *x = load_be32x4(chunk.as_ptr().add(16*i));
+        _mm_storeu_si128(out.add(i), *x);
+    }
+
+    let low = _mm_set_epi32(0, 0, -1, -1);
+    let high = _mm_set_epi32(-1, -1, 0, 0);
+    for i in 4..16 {
+        let [x0, x1, x2, x3] = x;
+        // w[i-15], w[i-7] for each of the four new words
+        let w15 = _mm_alignr_epi8::<4>(x1, x0);
+        let w7 = _mm_alignr_epi8::<4>(x3, x2);
+        let s0 = _mm_xor_si128(_mm_xor_si128(rotr!(w15, 7), rotr!(w15, 18)), _mm_srli_epi32::<3>(w15));
+        let mut t = _mm_add_epi32(_mm_add_epi32(x0, s0), w7);
+
+        // The first two words depend on w[i-2] from the previous group, the
+        // last two on the first two words of this group
+        let w2 = _mm_shuffle_epi32::<0b11_10_11_10>(x3);
+        let s1 = _mm_xor_si128(_mm_xor_si128(rotr!(w2, 17), rotr!(w2, 19)), _mm_srli_epi32::<10>(w2));
+        t = _mm_add_epi32(t, _mm_and_si128(s1, low));
+        let w2 = _mm_shuffle_epi32::<0b01_00_01_00>(t);
+        let s1 = _mm_xor_si128(_mm_xor_si128(rotr!(w2, 17), rotr!(w2, 19)), _mm_srli_epi32::<10>(w2));
+        t = _mm_add_epi32(t, _mm_and_si128(s1, high));
+
+        _mm_storeu_si128(out.add(i), t);
+        x = [x1, x2, x3, t];
+    }
+    w
+}
+
+// SHA-1 message schedule, four words at a time
+#[target_feature(enable = "sse2,ssse3")]
+pub unsafe fn sha1_schedule_ssse3(chunk: &[u8]) -> [u32; 80] {
+    assert!(chunk.len() == 64);
+    let mut w = [0; 80];
+    let out = w.as_mut_ptr() as *mut __m128i;
+    let mut x = [_mm_setzero_si128(); 4];
+    for (i, x) in x.iter_mut().enumerate() {
+        *x = load_be32x4(chunk.as_ptr().add(16*i));
+        _mm_storeu_si128(out.add(i), *x);
+    }
+
+    for i in 4..20 {
+        let [x0, x1, x2, x3] = x;
+        // w[i-3] is only available for the first three words
+        let w14 = _mm_alignr_epi8::<8>(x1, x0);
+        let w3 = _mm_srli_si128::<4>(x3);
+        let t = _mm_xor_si128(_mm_xor_si128(x0, w14), _mm_xor_si128(x2, w3));
+        let mut t = rotl!(t, 1);
+        // The last word also depends on the first word of this group
+        let first = _mm_slli_si128::<12>(t);
+        t = _mm_xor_si128(t, rotl!(first, 1));
+
+        _mm_storeu_si128(out.add(i), t);
+        x = [x1, x2, x3, t];
+    }
+    w
+}
+
+// As `load_be32x4`, for one chunk in each 128-bit lane
+#[target_feature(enable = "avx2")]
+unsafe fn load_be32x4x2(lo: *const u8, hi: *const u8) -> __m256i {
+    let mask = _mm256_set_epi64x(0x0c0d0e0f08090a0b, 0x0405060700010203, 0x0c0d0e0f08090a0b, 0x0405060700010203);
+    let x = _mm256_set_m128i(_mm_loadu_si128(hi as *const __m128i), _mm_loadu_si128(lo as *const __m128i));
+    _mm256_shuffle_epi8(x, mask)
+}
+
+#[target_feature(enable = "avx2")]
+unsafe fn store_x2(lo: *mut __m128i, hi: *mut __m128i, x: __m256i) {
+    _mm_storeu_si128(lo, _mm256_castsi256_si128(x));
+    _mm_storeu_si128(hi, _mm256_extracti128_si256::<1>(x));
+}
+
+macro_rules! rotr256 {
+    ($x:expr, $n:expr) => {
+        _mm256_or_si256(_mm256_srli_epi32::<$n>($x), _mm256_slli_epi32::<{32 - $n}>($x))
+    }
+}
+
+macro_rules! rotl256 {
+    ($x:expr, $n:expr) => {
+        _mm256_or_si256(_mm256_slli_epi32::<$n>($x), _mm256_srli_epi32::<{32 - $n}>($x))
+    }
+}
+
+// SHA-256 message schedules of two consecutive chunks, one in each 128-bit
+// lane. The steps are those of `sha256_schedule_ssse3`, since the lane-wise
+// AVX2 shuffles never mix the two chunks.
+#[target_feature(enable = "avx2")]
+pub unsafe fn sha256_schedule_avx2(chunks: &[u8]) -> [[u32; 64]; 2] {
+    assert!(chunks.len() == 128);
+    let mut w = [[0; 64]; 2];
+    let out = w.as_mut_ptr() as *mut __m128i;
+    let mut x = [_mm256_setzero_si256(); 4];
+    for (i, x) in x.iter_mut().enumerate() {
+        *x = load_be32x4x2(chunks.as_ptr().add(16*i), chunks.as_ptr().add(64 + 16*i));
+        store_x2(out.add(i), out.add(16 + i), *x);
+    }
+
+    let low = _mm256_set_epi32(0, 0, -1, -1, 0, 0, -1, -1);
+    let high = _mm256_set_epi32(-1, -1, 0, 0, -1, -1, 0, 0);
+    for i in 4..16 {
+        let [x0, x1, x2, x3] = x;
+        let w15 = _mm256_alignr_epi8::<4>(x1, x0);
+        let w7 = _mm256_alignr_epi8::<4>(x3, x2);
+        let s0 = _mm256_xor_si256(_mm256_xor_si256(rotr256!(w15, 7), rotr256!(w15, 18)), _mm256_srli_epi32::<3>(w15));
+        let mut t = _mm256_add_epi32(_mm256_add_epi32(x0, s0), w7);
+
+        let w2 = _mm256_shuffle_epi32::<0b11_10_11_10>(x3);
+        let s1 = _mm256_xor_si256(_mm256_xor_si256(rotr256!(w2, 17), rotr256!(w2, 19)), _mm256_srli_epi32::<10>(w2));
+        t = _mm256_add_epi32(t, _mm256_and_si256(s1, low));
+        let w2 = _mm256_shuffle_epi32::<0b01_00_01_00>(t);
+        let s1 = _mm256_xor_si256(_mm256_xor_si256(rotr256!(w2, 17), rotr256!(w2, 19)), _mm256_srli_epi32::<10>(w2));
+        t = _mm256_add_epi32(t, _mm256_and_si256(s1, high));
+
+        store_x2(out.add(i), out.add(16 + i), t);
+        x = [x1, x2, x3, t];
+    }
+    w
+}
+
+// SHA-1 message schedules of two consecutive chunks, one in each 128-bit lane
+#[target_feature(enable = "avx2")]
+pub unsafe fn sha1_schedule_avx2(chunks: &[u8]) -> [[u32; 80]; 2] {
+    assert!(chunks.len() == 128);
+    let mut w = [[0; 80]; 2];
+    let out = w.as_mut_ptr() as *mut __m128i;
+    let mut x = [_mm256_setzero_si256(); 4];
+    for (i, x) in x.iter_mut().enumerate() {
+        *x = load_be32x4x2(chunks.as_ptr().add(16*i), chunks.as_ptr().add(64 + 16*i));
+        store_x2(out.add(i), out.add(20 + i), *x);
+    }
+
+    for i in 4..20 {
+        let [x0, x1, x2, x3] = x;
+        let w14 = _mm256_alignr_epi8::<8>(x1, x0);
+        let w3 = _mm256_srli_si256::<4>(x3);
+        let t = _mm256_xor_si256(_mm256_xor_si256(x0, w14), _mm256_xor_si256(x2, w3));
+        let mut t = rotl256!(t, 1);
+        let first = _mm256_slli_si256::<12>(t);
+        t = _mm256_xor_si256(t, rotl256!(first, 1));
+
+        store_x2(out.add(i), out.add(20 + i), t);
+        x = [x1, x2, x3, t];
+    }
+    w
+}
+
+#[target_feature(enable = "sha,sse2,ssse3,sse4.1")]
+pub unsafe fn sha256_update_chunk_sha_ni(state: &mut [u32; 8], chunk: &[u8]) {
+    assert!(chunk.len() == 64);
+
+    // The SHA instructions expect the state split as ABEF and CDGH
+    let state_ptr = state.as_ptr() as *const __m128i;
+    let dcba = _mm_loadu_si128(state_ptr);
+    let hgfe = _mm_loadu_si128(state_ptr.add(1));
+    let cdab = _mm_shuffle_epi32::<0xb1>(dcba);
+    let efgh = _mm_shuffle_epi32::<0x1b>(hgfe);
+    let mut abef = _mm_alignr_epi8::<8>(cdab, efgh);
+    let mut cdgh = _mm_blend_epi16::<0xf0>(efgh, cdab);
+    let abef_save = abef;
+    let cdgh_save = cdgh;
+
+    let mut w = [_mm_setzero_si128(); 4];
+    for (i, w) in w.iter_mut().enumerate() {
+        *w = load_be32x4(chunk.as_ptr().add(16*i));
+    }
+
+    for i in 0..16 {
+        // Extend the message schedule in place, four words at a time
+        if i >= 4 {
+            let t = _mm_add_epi32(_mm_sha256msg1_epu32(w[i % 4], w[(i + 1) % 4]), _mm_alignr_epi8::<4>(w[(i + 3) % 4], w[(i + 2) % 4]));
+            w[i % 4] = _mm_sha256msg2_epu32(t, w[(i + 3) % 4]);
+        }
+        let k = _mm_loadu_si128(SHA256_K.as_ptr().add(4*i) as *const __m128i);
+        let wk = _mm_add_epi32(w[i % 4], k);
+        cdgh = _mm_sha256rnds2_epu32(cdgh, abef, wk);
+        abef = _mm_sha256rnds2_epu32(abef, cdgh, _mm_shuffle_epi32::<0x0e>(wk));
+    }
+
+    abef = _mm_add_epi32(abef, abef_save);
+    cdgh = _mm_add_epi32(cdgh, cdgh_save);
+
+    let feba = _mm_shuffle_epi32::<0x1b>(abef);
+    let dchg = _mm_shuffle_epi32::<0xb1>(cdgh);
+    let dcba = _mm_blend_epi16::<0xf0>(feba, dchg);
+    let hgfe = _mm_alignr_epi8::<8>(dchg, feba);
+    let state_ptr = state.as_mut_ptr() as *mut __m128i;
+    _mm_storeu_si128(state_ptr, dcba);
+    _mm_storeu_si128(state_ptr.add(1), hgfe);
+}
+
+#[target_feature(enable = "sha,sse2,ssse3,sse4.1")]
+pub unsafe fn sha1_update_chunk_sha_ni(state: &mut [u32; 5], chunk: &[u8]) {
+    assert!(chunk.len() == 64);
+
+    // A is held in the highest lane, E separately
+    let abcd_save = _mm_set_epi32(state[0] as i32, state[1] as i32, state[2] as i32, state[3] as i32);
+    let e_save = _mm_set_epi32(state[4] as i32, 0, 0, 0);
+
+    // Words are needed in reverse lane order
+    let mut w = [_mm_setzero_si128(); 4];
+    for (i, w) in w.iter_mut().enumerate() {
+        *w = _mm_shuffle_epi32::<0x1b>(load_be32x4(chunk.as_ptr().add(16*i)));
+    }
+
+    let mut abcd = abcd_save;
+    let mut abcd_prev = abcd_save;
+    for i in 0..20 {
+        // Extend the message schedule in place, four words at a time
+        if i >= 4 {
+            let t = _mm_xor_si128(_mm_sha1msg1_epu32(w[i % 4], w[(i + 1) % 4]), w[(i + 2) % 4]);
+            w[i % 4] = _mm_sha1msg2_epu32(t, w[(i + 3) % 4]);
+        }
+        // E for each group of four rounds is derived from A four rounds earlier
+        let e = if i == 0 {
+            _mm_add_epi32(e_save, w[0])
+        } else {
+            _mm_sha1nexte_epu32(abcd_prev, w[i % 4])
+        };
+        abcd_prev = abcd;
+        abcd = match i/5 {
+            0 => _mm_sha1rnds4_epu32::<0>(abcd, e),
+            1 => _mm_sha1rnds4_epu32::<1>(abcd, e),
+            2 => _mm_sha1rnds4_epu32::<2>(abcd, e),
+            _ => _mm_sha1rnds4_epu32::<3>(abcd, e)
+        };
+    }
+    let e = _mm_sha1nexte_epu32(abcd_prev, e_save);
+
+    abcd = _mm_add_epi32(abcd, abcd_save);
+    state[0] = _mm_extract_epi32::<3>(abcd) as u32;
+    state[1] = _mm_extract_epi32::<2>(abcd) as u32;
+    state[2] = _mm_extract_epi32::<1>(abcd) as u32;
+    state[3] = _mm_extract_epi32::<0>(abcd) as u32;
+    state[4] = _mm_extract_epi32::<3>(e) as u32;
+}
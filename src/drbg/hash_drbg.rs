@@ -0,0 +1,231 @@
+use std::fmt;
+use std::marker::PhantomData;
+
+use digest::{Digest, DigestAlgorithm};
+use utils::zeroize::Zeroize;
+use super::{security_strength, Drbg, DrbgError, DEFAULT_RESEED_INTERVAL, MAX_REQUEST_BYTES};
+
+// Hash_DRBG as defined in SP 800-90A section 10.1.1
+#[derive(Clone)]
+pub struct HashDrbg<D: DigestAlgorithm> {
+    v: Vec<u8>,
+    c: Vec<u8>,
+    reseed_counter: u64,
+    reseed_interval: u64,
+    _digest: PhantomData<D>
+}
+
+// Adds `x` to the big-endian integer `acc`, modulo 2^(8*acc.len())
+fn add_be(acc: &mut [u8], x: &[u8]) {
+    let mut carry = 0;
+    let mut x = x.iter().rev();
+    for byte in acc.iter_mut().rev() {
+        let sum = *byte as u16 + *x.next().unwrap_or(&0) as u16 + carry;
+        *byte = sum as u8;
+        carry = sum >> 8;
+    }
+}
+
+impl<D: DigestAlgorithm> HashDrbg<D> {
+    // Length of V and C in bytes, from table 2 of SP 800-90A
+    fn seed_len() -> usize {
+        if D::Digest::LEN <= 32 { 55 } else { 111 }
+    }
+
+    // Hash_df, deriving `seed_len` bytes from the concatenation of `inputs`
+    fn hash_df(inputs: &[&[u8]]) -> Vec<u8> {
+        let seed_len = Self::seed_len();
+        let mut result = Vec::with_capacity(seed_len + D::Digest::LEN);
+        let mut counter = 1u8;
+        while result.len() < seed_len {
+            let mut state = D::new();
+            state.update(&[counter]);
+            state.update(&((seed_len*8) as u32).to_be_bytes());
+            for input in inputs {
+                state.update(input);
+            }
+            result.extend_from_slice(state.digest().as_ref());
+            counter += 1;
+        }
+        result.truncate(seed_len);
+        result
+    }
+
+    fn hash(inputs: &[&[u8]]) -> D::Digest {
+        let mut state = D::new();
+        for input in inputs {
+            state.update(input);
+        }
+        state.digest()
+    }
+
+    fn set_seed(&mut self, v: Vec<u8>) {
+        self.c.zeroize();
+        self.c = Self::hash_df(&[&[0x00], &v]);
+        self.v.zeroize();
+        self.v = v;
+        self.reseed_counter = 1;
+    }
+
+    pub fn new(entropy: &[u8], nonce: &[u8], personalization: &[u8]) -> Result<Self, DrbgError> {
+        if entropy.len() < security_strength::<D>() {
+            return Err(DrbgError::InsufficientEntropy);
+        }
+        let mut result = HashDrbg {
+            v: Vec::new(),
+            c: Vec::new(),
+            reseed_counter: 0,
+            reseed_interval: DEFAULT_RESEED_INTERVAL,
+            _digest: PhantomData
+        };
+        result.set_seed(Self::hash_df(&[entropy, nonce, personalization]));
+        Ok(result)
+    }
+
+    // Lowers the number of requests allowed before `generate` fails with
+    // `DrbgError::ReseedRequired`
+    pub fn set_reseed_interval(&mut self, interval: u64) {
+        self.reseed_interval = interval.min(DEFAULT_RESEED_INTERVAL);
+    }
+}
+
+impl<D: DigestAlgorithm> Drbg for HashDrbg<D> {
+    fn security_strength(&self) -> usize {
+        security_strength::<D>()
+    }
+
+    fn reseed(&mut self, entropy: &[u8], additional_input: &[u8]) -> Result<(), DrbgError> {
+        if entropy.len() < security_strength::<D>() {
+            return Err(DrbgError::InsufficientEntropy);
+        }
+        let v = Self::hash_df(&[&[0x01], &self.v, entropy, additional_input]);
+        self.set_seed(v);
+        Ok(())
+    }
+
+    fn generate(&mut self, out: &mut [u8], additional_input: &[u8]) -> Result<(), DrbgError> {
+        if out.len() > MAX_REQUEST_BYTES {
+            return Err(DrbgError::RequestTooLarge);
+        }
+        if self.reseed_counter > self.reseed_interval {
+            return Err(DrbgError::ReseedRequired);
+        }
+
+        if !additional_input.is_empty() {
+            let w = Self::hash(&[&[0x02], &self.v, additional_input]);
+            add_be(&mut self.v, w.as_ref());
+        }
+
+        // Hashgen
+        let mut data = self.v.clone();
+        for block in out.chunks_mut(D::Digest::LEN) {
+            let w = Self::hash(&[&data]);
+            block.copy_from_slice(&w.as_ref()[..block.len()]);
+            add_be(&mut data, &[1]);
+        }
+        data.zeroize();
+
+        let h = Self::hash(&[&[0x03], &self.v]);
+        add_be(&mut self.v, h.as_ref());
+        add_be(&mut self.v, &self.c);
+        add_be(&mut self.v, &self.reseed_counter.to_be_bytes());
+        self.reseed_counter += 1;
+        Ok(())
+    }
+
+    fn reseed_counter(&self) -> u64 {
+        self.reseed_counter
+    }
+}
+
+// Leaves out V and C, which would reveal all future output
+impl<D: DigestAlgorithm> fmt::Debug for HashDrbg<D> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("HashDrbg")
+            .field("reseed_counter", &self.reseed_counter)
+            .field("reseed_interval", &self.reseed_interval)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<D: DigestAlgorithm> Drop for HashDrbg<D> {
+    fn drop(&mut self) {
+        self.v.zeroize();
+        self.c.zeroize();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use digest::sha1::SHA1;
+    use digest::sha2::{SHA256, SHA512};
+    use super::*;
+    use super::super::tests::{range, test, test_prediction_resistance};
+
+    fn test_nist(entropy: &[u8], nonce: &[u8], additional_input: [&[u8]; 2], expected: &[u8]) {
+        let mut drbg = HashDrbg::<SHA256>::new(entropy, nonce, b"").unwrap();
+        let mut out = vec![0; expected.len()];
+        drbg.generate(&mut out, additional_input[0]).unwrap();
+        drbg.generate(&mut out, additional_input[1]).unwrap();
+        println!("A: {:?}", out);
+        println!("B: {:?}", expected);
+        assert!(&out[..] == expected);
+    }
+
+    // Hash_DRBG.rsp from the NIST DRBG test vectors, SHA-256 without
+    // prediction resistance
+    #[test]
+    fn hash_drbg_nist() {
+        test_nist(
+            base16!("A65AD0F345DB4E0EFFE875C3A2E71F42C7129D620FF5C119A9EF55F05185E0FB"),
+            base16!("8581F9317517276E06E9607DDBCBCC2E"),
+            [b"", b""],
+            base16!("D3E160C35B99F340B2628264D1751060E0045DA383FF57A57D73A673D2B8D80DAAF6A6C35A91BB4579D73FD0C8FED111B0391306828ADFED528F018121B3FEBDC343E797B87DBB63DB1333DED9D1ECE177CFA6B71FE8AB1DA46624ED6415E51CCDE2C7CA86E283990EEAEB91120415528B2295910281B02DD431F4C9F70427DF"));
+    }
+
+    // The remaining expected outputs were cross-checked against OpenSSL's
+    // HASH-DRBG
+    #[test]
+    fn hash_drbg_sha1() {
+        test::<HashDrbg<SHA1>>(false, base16!("E418D803369010403DB1EFDBEB70C7226D9D6DC722BB89C08022B98807F8FE89842CD87665E9331EFB873356D640C09B3992A50EDC63EFB6DFC9F3AFC74577E1A4E6C131503CBA6DEB12F5AA1532E7C0"));
+        test::<HashDrbg<SHA1>>(true, base16!("BE4924EF61E90490B0EB2AF080413A2C774D4C386078FDE6FD9D980ED0C6277DD1F778E3E4F55413BF6417434800E25DFF7CF4F22FE447ADAAB1F6A1218B4B5127D9B022106E4D9FFD83133DD2AC1D39"));
+        test_prediction_resistance::<HashDrbg<SHA1>>(base16!("893B54DB5437D1CEA73D688C3AD2B5844042A7850820E25A265A08C1BF0CED6B9804ABE56F1E1E1AB7E6A86234EB4ABAE398D94062FCDE3C9C8E926E340D7E2FA88608E5836942D698C84020C555285B"));
+    }
+
+    #[test]
+    fn hash_drbg_sha256() {
+        test::<HashDrbg<SHA256>>(false, base16!("50311778D97595315CD8F5E6682A951070A7A098B23A44A3A74D331628B7CAEE69183D63FC96B12DF7D6ED9065106E3EA2F4713EED484566D56A59EE15E5039D341CC8D5CFD82427A7C07D53920A37981F5C380FEAC45D7A718E4F0E768DAE6800F4B861F12C39CFE11F5B684B9E6FA5598DB9AE66B18E3C7E92B25B2BD7A8FC"));
+        test::<HashDrbg<SHA256>>(true, base16!("D485DBDFC929B8E0FB8C661CBEB5D0C8BAA9EF4C6921AC4D30B4D2B0A0EE0E5C7E7D6C2316E4792C2624FFF05E864D0B999A8D1AEA166BC6D3F8349B012C31ECED27D5888072030806FA046579791DCF986B31F7B2C61C62546FFB1E006909E7E50CFE794B92890387F07364017A37A9BF17C9E0EC26051E6AF22E4F076E537D"));
+        test_prediction_resistance::<HashDrbg<SHA256>>(base16!("21A840E71EC32BFFFC53A2065CF682F4180A5EFA3DB2E495FA4194D1A8FF6515A228FDC014731A1B87C76B217072BFD602BFFED08704A75E4069EA2123276696628A56A2B7E47C6068E99F9CCE2CF5F6BC986606A87AE72ED75739CE75DA0DF4345134A2A9AFD94632749FD765086D5BE89EF004F350D553AC1CFA6C9403D83F"));
+
+        // Without personalization or additional input
+        let mut drbg = HashDrbg::<SHA256>::new(&range(0x00, 32), &range(0x20, 16), b"").unwrap();
+        let mut out = [0; 128];
+        drbg.generate(&mut out, b"").unwrap();
+        drbg.generate(&mut out, b"").unwrap();
+        assert!(out[..] == base16!("27A3342A35D4BBB8E1DCD8EC0FC1A0D1A25CF906F0445D3B974DBDDF4A3BA34E073302AB655234A703381741AF7B15191A96164CC087AD1EF8360960B94DFBA7451ADE5F57FF6F74AFEB737F8F539304C1CE58A98F3AD4B852B4CEC0ACEFFB2BD5F153F9395B593DC8D890C6D9CC570107B36CFD4B7081C42102EFD89752A1DE")[..]);
+    }
+
+    #[test]
+    fn hash_drbg_sha512() {
+        test::<HashDrbg<SHA512>>(false, base16!("0954A61E925A0E92BC5C05266D3CAC38DBAECE9604BC9057CC29924B5F00DE13F009801B4CF1E7DAAA0671713E94818074B3B7F2FB1FA09B27EAD2AB551EDA71BB4154DB72E814DA84DF1D38C8371B89084DF089421674CCC2AEB2A10C22A3460CC36CF987DF945AC2740A117F11A0D86929EC10D7C7E3A98AD801A3E525C5ECED32B7514B6AA5578B39ECA5BE8FF4F9BD908C7CE85BC028AFB766C0ABC40FC9FC1265C480CB626147FD3C7676C8A5DE002559EE9B25EDC3563D5BBA8CC664BF03034861B68E0DDAC80EB23B523F64394B2D51BEA70ABFA06238D3183BDC9DD50FE30BFBA53D844F1D9DF715F900FCE1C3AE5F64A9EB37B1515FEED0E1E9E660"));
+        test::<HashDrbg<SHA512>>(true, base16!("6E5C210E8B496CC1237309FA8C66CE971064B32E2505E407DE8CBB1A22801B5CBFD64A4C4B38BA400374EB3DD9416B4C22DEC7EFDB9389DC051BC36685D7BA741390150677A6C6AFD81B0CD0818B2116A0E9AC749063D62C991406B7D9C94E578114944F7B01874793AE5BBD689DCB774497148C56E157351C7C56B873348D7BA080E9B18DAA85018AC9589F3AC77C01D54A3DA65D17A46238F512653AD77CE2EEF0AD90AD8736F4A76AEDD2B7D8451C4B66099450739E19C800BAE12B58FAC7BA5C49834CA6E24EE1D9C9A981647D385D6D722ADBF18D3BDDDEA17DDB53AA82854CCFA97150B15E2D549A608BC51D0B2EF529211D79A8F4CFB09A3E872FCAD4"));
+        test_prediction_resistance::<HashDrbg<SHA512>>(base16!("331429A8399139407BFE76BCBCC6C57A354445A1B916018D06D57E3E50864789EF98D7BAEEBC62A23388011613116D646AB04F32E7085BF4782CEAFA48F3D206C26A20D9DC29CC07FDF94FD44C7A0989AE087C9012530962B6448E98BF6A302718369C70D85C6B50B85E9DBA85876D24758B1AC30B19F2084C95871829CD8FAF993C5CFCEF1ACFC6DB12EACB379D2866A8D99622BBE89BC003BA45E7EA03DFA95CDC6522CFAC9C8DA72C942B92A2DAFBE480513183979F475436724556582E3C256DDCD85108BDECF1E4E1CC61B94F8441654A89FD0CFEA0EAF82C4574637B079D0DF881FDC9DD8E15BBE1864DF86B8A21D7A7F1B565B6BD378456871B7432F8"));
+    }
+
+    #[test]
+    fn limits() {
+        assert_eq!(HashDrbg::<SHA256>::new(&[0; 31], b"", b"").err(), Some(DrbgError::InsufficientEntropy));
+
+        let mut drbg = HashDrbg::<SHA256>::new(&[0; 32], b"", b"").unwrap();
+        let mut out = vec![0; MAX_REQUEST_BYTES + 1];
+        assert_eq!(drbg.generate(&mut out, b""), Err(DrbgError::RequestTooLarge));
+
+        drbg.set_reseed_interval(2);
+        assert!(drbg.generate(&mut out[..32], b"").is_ok());
+        assert!(drbg.generate(&mut out[..32], b"").is_ok());
+        assert_eq!(drbg.generate(&mut out[..32], b""), Err(DrbgError::ReseedRequired));
+        drbg.reseed(&[1; 32], b"").unwrap();
+        assert!(drbg.generate(&mut out[..32], b"").is_ok());
+    }
+}
@@ -0,0 +1,182 @@
+use std::fmt;
+use std::marker::PhantomData;
+
+use digest::{Digest, DigestAlgorithm};
+use digest::hmac::Hmac;
+use utils::zeroize::Zeroize;
+use super::{security_strength, Drbg, DrbgError, DEFAULT_RESEED_INTERVAL, MAX_REQUEST_BYTES};
+
+// HMAC_DRBG as defined in SP 800-90A section 10.1.2
+#[derive(Clone)]
+pub struct HmacDrbg<D: DigestAlgorithm> {
+    key: Vec<u8>,
+    v: Vec<u8>,
+    reseed_counter: u64,
+    reseed_interval: u64,
+    _digest: PhantomData<D>
+}
+
+impl<D: DigestAlgorithm> HmacDrbg<D> {
+    fn hmac(&self, inputs: &[&[u8]]) -> Vec<u8> {
+        let mut state = Hmac::<D>::new(&self.key);
+        for input in inputs {
+            state.update(input);
+        }
+        state.finalize().as_ref().to_vec()
+    }
+
+    // HMAC_DRBG_Update, mixing the concatenation of `provided` into the state
+    fn update(&mut self, provided: &[&[u8]]) {
+        for round in &[[0x00u8], [0x01]] {
+            let mut inputs = vec![&self.v[..], round];
+            inputs.extend_from_slice(provided);
+            let key = self.hmac(&inputs);
+            self.key.zeroize();
+            self.key = key;
+            self.v = self.hmac(&[&self.v]);
+
+            if provided.iter().all(|input| input.is_empty()) {
+                break;
+            }
+        }
+    }
+
+    pub fn new(entropy: &[u8], nonce: &[u8], personalization: &[u8]) -> Result<Self, DrbgError> {
+        if entropy.len() < security_strength::<D>() {
+            return Err(DrbgError::InsufficientEntropy);
+        }
+        let mut result = HmacDrbg {
+            key: vec![0x00; D::Digest::LEN],
+            v: vec![0x01; D::Digest::LEN],
+            reseed_counter: 1,
+            reseed_interval: DEFAULT_RESEED_INTERVAL,
+            _digest: PhantomData
+        };
+        result.update(&[entropy, nonce, personalization]);
+        Ok(result)
+    }
+
+    // Lowers the number of requests allowed before `generate` fails with
+    // `DrbgError::ReseedRequired`
+    pub fn set_reseed_interval(&mut self, interval: u64) {
+        self.reseed_interval = interval.min(DEFAULT_RESEED_INTERVAL);
+    }
+}
+
+impl<D: DigestAlgorithm> Drbg for HmacDrbg<D> {
+    fn security_strength(&self) -> usize {
+        security_strength::<D>()
+    }
+
+    fn reseed(&mut self, entropy: &[u8], additional_input: &[u8]) -> Result<(), DrbgError> {
+        if entropy.len() < security_strength::<D>() {
+            return Err(DrbgError::InsufficientEntropy);
+        }
+        self.update(&[entropy, additional_input]);
+        self.reseed_counter = 1;
+        Ok(())
+    }
+
+    fn generate(&mut self, out: &mut [u8], additional_input: &[u8]) -> Result<(), DrbgError> {
+        if out.len() > MAX_REQUEST_BYTES {
+            return Err(DrbgError::RequestTooLarge);
+        }
+        if self.reseed_counter > self.reseed_interval {
+            return Err(DrbgError::ReseedRequired);
+        }
+
+        if !additional_input.is_empty() {
+            self.update(&[additional_input]);
+        }
+        for block in out.chunks_mut(D::Digest::LEN) {
+            self.v = self.hmac(&[&self.v]);
+            block.copy_from_slice(&self.v[..block.len()]);
+        }
+        self.update(&[additional_input]);
+        self.reseed_counter += 1;
+        Ok(())
+    }
+
+    fn reseed_counter(&self) -> u64 {
+        self.reseed_counter
+    }
+}
+
+// Leaves out key and V, which would reveal all future output
+impl<D: DigestAlgorithm> fmt::Debug for HmacDrbg<D> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("HmacDrbg")
+            .field("reseed_counter", &self.reseed_counter)
+            .field("reseed_interval", &self.reseed_interval)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<D: DigestAlgorithm> Drop for HmacDrbg<D> {
+    fn drop(&mut self) {
+        self.key.zeroize();
+        self.v.zeroize();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use digest::sha1::SHA1;
+    use digest::sha2::{SHA256, SHA512};
+    use super::*;
+    use super::super::tests::{test, test_prediction_resistance};
+
+    fn test_nist(entropy: &[u8], nonce: &[u8], additional_input: [&[u8]; 2], expected: &[u8]) {
+        let mut drbg = HmacDrbg::<SHA256>::new(entropy, nonce, b"").unwrap();
+        let mut out = vec![0; expected.len()];
+        drbg.generate(&mut out, additional_input[0]).unwrap();
+        drbg.generate(&mut out, additional_input[1]).unwrap();
+        println!("A: {:?}", out);
+        println!("B: {:?}", expected);
+        assert!(&out[..] == expected);
+    }
+
+    // HMAC_DRBG.rsp from the NIST DRBG test vectors, SHA-256 without
+    // prediction resistance
+    #[test]
+    fn hmac_drbg_nist() {
+        test_nist(
+            base16!("CA851911349384BFFE89DE1CBDC46E6831E44D34A4FB935EE285DD14B71A7488"),
+            base16!("659BA96C601DC69FC902940805EC0CA8"),
+            [b"", b""],
+            base16!("E528E9ABF2DECE54D47C7E75E5FE302149F817EA9FB4BEE6F4199697D04D5B89D54FBB978A15B5C443C9EC21036D2460B6F73EBAD0DC2ABA6E624ABF07745BC107694BB7547BB0995F70DE25D6B29E2D3011BB19D27676C07162C8B5CCDE0668961DF86803482CB37ED6D5C0BB8D50CF1F50D476AA0458BDABA806F48BE9DCB8"));
+        test_nist(
+            base16!("D3CC4D1ACF3DDE0C4BD2290D262337042DC632948223D3A2EAAB87DA44295FBD"),
+            base16!("0109B0E729F457328AA18569A9224921"),
+            [base16!("3C311848183C9A212A26F27F8C6647E40375E466A0857CC39C4E47575D53F1F6"), base16!("FCB9ABD19CCFBCCEF88C9C39BFB3DD7B1C12266C9808992E305BC3CFF566E4E4")],
+            base16!("9C7B758B212CD0FCECD5DAA489821712E3CDEA4467B560EF5DDC24AB47749A1F1FFDBBB118F4E62FCFCA3371B8FBFC5B0646B83E06BFBBAB5FAC30EA09EA2BC76F1EA568C9BE0444B2CC90517B20CA825F2D0ECCD88E7175538B85D90AB390183CA6395535D34473AF6B5A5B88F5A59EE7561573337EA819DA0DCC3573A22974"));
+        test_nist(
+            base16!("F97A3CFD91FAA046B9E61B9493D436C4931F604B22F1081521B3419151E8FF06"),
+            base16!("11F3A7D43595357D58120BD1E2DD8AED"),
+            [base16!("517289AFE444A0FE5ED1A41DBBB5EB17150079BDD31E29CF2FF30034D8268E3B"), base16!("88028D29EF80B4E6F0FE12F91D7449FE75062682E89C571440C0C9B52C42A6E0")],
+            base16!("C6871CFF0824FE55EA7689A52229886730450E5D362DA5BF590DCF9ACD67FED4CB32107DF5D03969A66B1F6494FDF5D63D5B4D0D34EA7399A07D0116126D0D518C7C55BA46E12F62EFC8FE28A51C9D428E6D371D7397AB319FC73DED4722E5B4F30004032A6128DF5E7497ECF82CA7B0A50E867EF6728A4F509A8C859087039C"));
+    }
+
+    // The remaining expected outputs were cross-checked against OpenSSL's
+    // HMAC-DRBG
+    #[test]
+    fn hmac_drbg_sha1() {
+        test::<HmacDrbg<SHA1>>(false, base16!("C676F8A0970B3BFBE2B9DD0A7D2CF0DC66DC1036D119EF3652FB5CAF28D6C9E8CD9447DCF4F1F90B38CABC37E6FCED993C06555016FB56575E040B3D3F58CA9179B4BBD4571BB96AEB250BF56D38DFFA"));
+        test::<HmacDrbg<SHA1>>(true, base16!("C62281F0D77C6732A590D5252CCA059680707E048599E169BD542EB78F75AFF5B50B0081EDE575EAED9FD919C2DDBE0893A445153487C076218CA71FE91786C4535C1EE043D3B13CE2B5BC9B9B651642"));
+        test_prediction_resistance::<HmacDrbg<SHA1>>(base16!("DB5B8CE70D7311FB270DC201A650767301B97306495D20A0801C7409A014854788E8991274D3DF93A0EE4843EB15D97307788F8009D3C7163E143FA280269C293376C6DA2650529EE48206829F14AC7B"));
+    }
+
+    #[test]
+    fn hmac_drbg_sha256() {
+        test::<HmacDrbg<SHA256>>(false, base16!("9DE19320AF8682520197E71E8972E4A3CCF798A04F2D9EA80F613D2543B04DD3E6559AC792E7AABE240A39B841CAB9C9F9C134F8347AA095EB3FE6EFA72BD4DD1ACD9CACB53A75E1731E30D0E23573DA6950144A6DAACA08177E05CC85EF4553E76234C7E4F7C96C0B490804EF680CC8AD6A5B91B55B20CAD77BF7794557BD4F"));
+        test::<HmacDrbg<SHA256>>(true, base16!("E184CCE548E391C6E80347BB6B5B8E9DE97057CEE36B34B78EDE0CA3AE47D431B049E1BD1C3B30379366F6131E76AB7AEFF58D210D7E8BD72F672084FA3665BC4D0522F41500F6C811074F9FD616CE264854B388D7B2B8AD43620C8E425EDD6B558A111E1F014BEDA9A3D7272CB5E28617525B3F02EF16DA389FB880AD517FA3"));
+        test_prediction_resistance::<HmacDrbg<SHA256>>(base16!("226138D418C1DA3437F88BD26E7AA08EE068E59FE966EC388ED9572FC29DD9551FAF1030D4479DEFFB8C8CB89789EF1EF232B4CEF58DABDD25B653640AA73726315E55CBDF600FCDB3300BE263E3B0FDF1A6637998B2B5FFA768A8FCD1D37043C3101292582353861A72C005ADFC8C95FF72E993597401601FA7F3C7AB2109DA"));
+    }
+
+    #[test]
+    fn hmac_drbg_sha512() {
+        test::<HmacDrbg<SHA512>>(false, base16!("3AF072928545D10D719DA16F0AA0A07DB104BD5559FE6874ECFCEBB37DF785B9D2B8688F0AA16A70BA7F5B4458EFAEE8875ACEC9D115B1682F37F2741003F2765D4ABF4D907B457046A5347F8DBA78ED8AE3F44C1AD90E2177FDA7AC37C394DDF2D4D094CFFD4647BED595037492FD38EA141CC85812911EA66415D890B59C6992B20B0FB0B531814C8572881EF45F328A0CA31B6CDB101E0FF42219D8D71917D727E900C1ACAB895924A75FCA4EA5F0F36E0C762B9C59DF04B874E1E6C805A7443AB4B120F1C660AAF3AA25CE92F1A3EEE52ADA24E3FF7C8DBDF6E94ED073ED9BF225CD7F9A7387CFA0816C3F19363CA36AA3E1EEE3F3D5F6B7B32ABF89A323"));
+        test::<HmacDrbg<SHA512>>(true, base16!("616F5D7BC5C491E10991587B74423BD09C498BE9C546351E3DA3AE4555E4F4002EF10B01DCD4ACC13592D63A71488E9AE2077898F296BB9B53F6F79034CF21740B4BBF0A38E4561C0F4BAA91028E605B6DAC53DC2576E4E556577221B407B42B29AD785A5F78A256D8CD8E8F53CD88F323E18AA2418D52F6C1DFFF095EAD699C47DB5213B8012B4F873E799A01B5D1E7A0B5A2F2593775C4FA3FAFEF6237C6B46049F2F9F298B2546E6E49D1318BB36AB75727724CCC61293BA85E6DAC25FC6C5240A7396EF8DF58BA9CE7D4F603FEE38684C6FEA97FB84DB6BF3EC29D24770C44727FED6A9D7C103273B79555FEC66AAA81C7C1CF9AAA18D9AC947AA9954353"));
+        test_prediction_resistance::<HmacDrbg<SHA512>>(base16!("0D88E458F32A5F9A0895956135925927D3A07C0658C4CCF2F8229D895062A44FD6D7A3431831057807A053C8601C20D6790A2C1F891E5A8A0371B71F048E4ED8675B4885834DCE6AC691C3D6723D039CBEA3D87F9CEE099B4E9768264322CCED42D998256618575B3296617728E461586F29BE04E38CDBE00F93DE022C77BB5BDA170BF7D63D4E65EE1C4A1549B9499BD34CCA39BCD023D17B60E19F503E8F88F9F93A71B3FBE80432EBB6D85389A907308B4FA2F5FEA7A40CC212D65CDDBEB56B302588849FF9AB6CC96AC39FA3176C3B0229C560EC2C1DCAEF8FA447692221D7F3C5775D800FDC0DD4A9F27D2D23F47F6647FAA0915BBC0335E1E7C74401EE"));
+    }
+}
@@ -0,0 +1,156 @@
+// Deterministic random bit generators from NIST SP 800-90A
+
+use std::error::Error;
+use std::fmt;
+
+use digest::{Digest, DigestAlgorithm};
+
+pub mod hash_drbg;
+pub mod hmac_drbg;
+
+// Largest request permitted by a single call to `generate` (2^19 bits)
+pub const MAX_REQUEST_BYTES: usize = 1 << 16;
+// Number of requests allowed between reseeds, unless overridden
+pub const DEFAULT_RESEED_INTERVAL: u64 = 1 << 48;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DrbgError {
+    // Entropy input is shorter than the security strength
+    InsufficientEntropy,
+    // More than `MAX_REQUEST_BYTES` were requested at once
+    RequestTooLarge,
+    // The reseed interval has been reached, so `reseed` must be called first
+    ReseedRequired
+}
+
+impl fmt::Display for DrbgError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            DrbgError::InsufficientEntropy => write!(f, "DRBG entropy input is too short"),
+            DrbgError::RequestTooLarge => write!(f, "DRBG request exceeds the maximum length"),
+            DrbgError::ReseedRequired => write!(f, "DRBG must be reseeded")
+        }
+    }
+}
+
+impl Error for DrbgError {}
+
+// Supplies fresh entropy when prediction resistance is requested
+pub trait EntropySource {
+    fn fill_entropy(&mut self, out: &mut [u8]);
+}
+
+impl<F: FnMut(&mut [u8])> EntropySource for F {
+    fn fill_entropy(&mut self, out: &mut [u8]) {
+        self(out)
+    }
+}
+
+pub trait Drbg {
+    // Security strength in bytes, which is also the minimum entropy input length
+    fn security_strength(&self) -> usize;
+    fn reseed(&mut self, entropy: &[u8], additional_input: &[u8]) -> Result<(), DrbgError>;
+    fn generate(&mut self, out: &mut [u8], additional_input: &[u8]) -> Result<(), DrbgError>;
+    // Number of requests since the last (re)seed, starting from 1
+    fn reseed_counter(&self) -> u64;
+
+    // Reseeds from `source` before generating, so that the output does not
+    // depend on any earlier compromise of the internal state
+    fn generate_with_prediction_resistance<E: EntropySource + ?Sized>(&mut self, source: &mut E, out: &mut [u8], additional_input: &[u8]) -> Result<(), DrbgError> where Self: Sized {
+        let mut entropy = vec![0; self.security_strength()];
+        source.fill_entropy(&mut entropy);
+        self.reseed(&entropy, additional_input)?;
+        self.generate(out, &[])
+    }
+}
+
+// Security strength of a DRBG built on `D`, according to SP 800-57
+fn security_strength<D: DigestAlgorithm>() -> usize {
+    match D::Digest::LEN {
+        0..=20 => 16,
+        21..=28 => 24,
+        _ => 32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use digest::DigestAlgorithm;
+    use digest::sha2::SHA256;
+    use super::*;
+    use super::hash_drbg::HashDrbg;
+    use super::hmac_drbg::HmacDrbg;
+
+    // Instantiation isn't part of `Drbg`, since each mechanism defines its own
+    pub trait TestDrbg: Drbg + Sized {
+        type Digest: DigestAlgorithm;
+
+        fn instantiate(entropy: &[u8], nonce: &[u8], personalization: &[u8]) -> Result<Self, DrbgError>;
+    }
+
+    impl<D: DigestAlgorithm> TestDrbg for HashDrbg<D> {
+        type Digest = D;
+
+        fn instantiate(entropy: &[u8], nonce: &[u8], personalization: &[u8]) -> Result<Self, DrbgError> {
+            HashDrbg::new(entropy, nonce, personalization)
+        }
+    }
+
+    impl<D: DigestAlgorithm> TestDrbg for HmacDrbg<D> {
+        type Digest = D;
+
+        fn instantiate(entropy: &[u8], nonce: &[u8], personalization: &[u8]) -> Result<Self, DrbgError> {
+            HmacDrbg::new(entropy, nonce, personalization)
+        }
+    }
+
+    pub fn range(start: u8, len: u8) -> Vec<u8> {
+        (start..start + len).collect()
+    }
+
+    fn instantiate<G: TestDrbg>() -> G {
+        let strength = security_strength::<G::Digest>() as u8;
+        G::instantiate(&range(0x00, strength), &range(0x20, strength/2), &range(0x40, strength)).unwrap()
+    }
+
+    // Instantiates with personalization, then checks the second of two
+    // requests with additional input, as in the NIST DRBG vectors. If
+    // `reseed` is set, the state is reseeded after instantiation.
+    pub fn test<G: TestDrbg>(reseed: bool, expected: &[u8]) {
+        let strength = security_strength::<G::Digest>() as u8;
+        let mut drbg = instantiate::<G>();
+        if reseed {
+            drbg.reseed(&range(0xa0, strength), &range(0x30, strength)).unwrap();
+        }
+        let mut out = vec![0; expected.len()];
+        drbg.generate(&mut out, &range(0x60, strength)).unwrap();
+        drbg.generate(&mut out, &range(0x80, strength)).unwrap();
+        println!("A: {:?}", out);
+        println!("B: {:?}", expected);
+        assert!(&out[..] == expected);
+        assert_eq!(drbg.reseed_counter(), 3);
+    }
+
+    // As `test`, but with fresh entropy for each request
+    pub fn test_prediction_resistance<G: TestDrbg>(expected: &[u8]) {
+        let strength = security_strength::<G::Digest>() as u8;
+        let mut drbg = instantiate::<G>();
+        let mut next = 0xa0;
+        let mut source = |out: &mut [u8]| {
+            out.copy_from_slice(&range(next, out.len() as u8));
+            next += 0x20;
+        };
+        let mut out = vec![0; expected.len()];
+        drbg.generate_with_prediction_resistance(&mut source, &mut out, &range(0x60, strength)).unwrap();
+        drbg.generate_with_prediction_resistance(&mut source, &mut out, &range(0x80, strength)).unwrap();
+        assert!(&out[..] == expected);
+    }
+
+    #[test]
+    fn debug_hides_state() {
+        let drbg = instantiate::<HashDrbg<SHA256>>();
+        assert_eq!(format!("{:?}", drbg), "HashDrbg { reseed_counter: 1, reseed_interval: 281474976710656, .. }");
+        let drbg = instantiate::<HmacDrbg<SHA256>>();
+        assert_eq!(format!("{:?}", drbg), "HmacDrbg { reseed_counter: 1, reseed_interval: 281474976710656, .. }");
+    }
+}
@@ -0,0 +1,148 @@
+use std::error::Error;
+use std::fmt;
+use std::marker::PhantomData;
+
+use digest::DigestAlgorithm;
+use digest::hmac::Hmac;
+use utils::zeroize::Zeroize;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HkdfError {
+    // More than 255*HashLen bytes of output were requested
+    OutputTooLong
+}
+
+impl fmt::Display for HkdfError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            HkdfError::OutputTooLong => write!(f, "HKDF output length exceeds 255 blocks")
+        }
+    }
+}
+
+impl Error for HkdfError {}
+
+// Pseudorandom key produced by the extract step of RFC 5869
+#[derive(Clone)]
+pub struct Prk<D: DigestAlgorithm> {
+    prk: Box<[u8]>,
+    _digest: PhantomData<D>
+}
+
+// HKDF-Extract(salt, IKM). An empty salt is equivalent to HashLen zero bytes,
+// since HMAC zero-pads its key either way.
+pub fn extract<D: DigestAlgorithm>(salt: &[u8], ikm: &[u8]) -> Prk<D> {
+    Prk {
+        prk: Hmac::<D>::compute(salt, ikm).into(),
+        _digest: PhantomData
+    }
+}
+
+impl<D: DigestAlgorithm + Clone> Prk<D> {
+    // HKDF-Expand(PRK, info, L)
+    pub fn expand(&self, info: &[u8], out_len: usize) -> Result<Vec<u8>, HkdfError> {
+        let mut result = vec![0; out_len];
+        self.expand_into(info, &mut result)?;
+        Ok(result)
+    }
+
+    pub fn expand_into(&self, info: &[u8], out: &mut [u8]) -> Result<(), HkdfError> {
+        let hash_len = self.prk.len();
+        if out.len() > 255*hash_len {
+            return Err(HkdfError::OutputTooLong);
+        }
+
+        // Key the HMAC once and clone the state for each block
+        let keyed = Hmac::<D>::new(&self.prk);
+        let mut prev: Option<D::Digest> = None;
+        for (i, block) in out.chunks_mut(hash_len).enumerate() {
+            let mut state = keyed.clone();
+            if let Some(ref t) = prev {
+                state.update(t.as_ref());
+            }
+            state.update(info);
+            state.update(&[(i + 1) as u8]);
+            let t = state.finalize();
+            block.copy_from_slice(&t.as_ref()[..block.len()]);
+            prev = Some(t);
+        }
+        Ok(())
+    }
+}
+
+impl<D: DigestAlgorithm> AsRef<[u8]> for Prk<D> {
+    fn as_ref(&self) -> &[u8] {
+        &self.prk
+    }
+}
+
+// Every output key is derived from the PRK, so it is left out
+impl<D: DigestAlgorithm> fmt::Debug for Prk<D> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Prk").finish_non_exhaustive()
+    }
+}
+
+impl<D: DigestAlgorithm> Drop for Prk<D> {
+    fn drop(&mut self) {
+        self.prk.zeroize();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use digest::sha1::SHA1;
+    use digest::sha2::SHA256;
+    use super::*;
+
+    fn test<D: DigestAlgorithm + Clone>(salt: &[u8], ikm: &[u8], info: &[u8], expected_prk: &[u8], expected_okm: &[u8]) {
+        let prk = extract::<D>(salt, ikm);
+        let okm = prk.expand(info, expected_okm.len()).unwrap();
+        println!("A: {:?}", okm);
+        println!("B: {:?}", expected_okm);
+        assert!(prk.as_ref() == expected_prk);
+        assert!(&okm[..] == expected_okm);
+    }
+
+    fn range(start: u8, end: u16) -> Vec<u8> {
+        (start as u16..end).map(|b| b as u8).collect()
+    }
+
+    // RFC 5869 appendix A
+    #[test]
+    fn hkdf_sha256() {
+        test::<SHA256>(&range(0x00, 0x0d), &[0x0b; 22], &range(0xf0, 0xfa),
+            base16!("077709362C2E32DF0DDC3F0DC47BBA6390B6C73BB50F9C3122EC844AD7C2B3E5"),
+            base16!("3CB25F25FAACD57A90434F64D0362F2A2D2D0A90CF1A5A4C5DB02D56ECC4C5BF34007208D5B887185865"));
+        test::<SHA256>(&range(0x60, 0xb0), &range(0x00, 0x50), &range(0xb0, 0x100),
+            base16!("06A6B88C5853361A06104C9CEB35B45CEF760014904671014A193F40C15FC244"),
+            base16!("B11E398DC80327A1C8E7F78C596A49344F012EDA2D4EFAD8A050CC4C19AFA97C59045A99CAC7827271CB41C65E590E09DA3275600C2F09B8367793A9ACA3DB71CC30C58179EC3E87C14C01D5C1F3434F1D87"));
+        test::<SHA256>(b"", &[0x0b; 22], b"",
+            base16!("19EF24A32C717B167F33A91D6F648BDF96596776AFDB6377AC434C1C293CCB04"),
+            base16!("8DA4E775A563C18F715F802A063C5A31B8A11F5C5EE1879EC3454E5F3C738D2D9D201395FAA4B61A96C8"));
+    }
+
+    #[test]
+    fn hkdf_sha1() {
+        test::<SHA1>(&range(0x00, 0x0d), &[0x0b; 11], &range(0xf0, 0xfa),
+            base16!("9B6C18C432A7BF8F0E71C8EB88F4B30BAA2BA243"),
+            base16!("085A01EA1B10F36933068B56EFA5AD81A4F14B822F5B091568A9CDD4F155FDA2C22E422478D305F3F896"));
+        test::<SHA1>(&range(0x60, 0xb0), &range(0x00, 0x50), &range(0xb0, 0x100),
+            base16!("8ADAE09A2A307059478D309B26C4115A224CFAF6"),
+            base16!("0BD770A74D1160F7C9F12CD5912A06EBFF6ADCAE899D92191FE4305673BA2FFE8FA3F1A4E5AD79F3F334B3B202B2173C486EA37CE3D397ED034C7F9DFEB15C5E927336D0441F4C4300E2CFF0D0900B52D3B4"));
+        test::<SHA1>(b"", &[0x0b; 22], b"",
+            base16!("DA8C8A73C7FA77288EC6F5E7C297786AA0D32D01"),
+            base16!("0AC1AF7002B3D761D1E55298DA9D0506B9AE52057220A306E07B6B87E8DF21D0EA00033DE03984D34918"));
+        test::<SHA1>(&[0; 20], &[0x0c; 22], b"",
+            base16!("2ADCCADA18779E7C2077AD2EB19D3F3E731385DD"),
+            base16!("2C91117204D745F3500D636A62F64F0AB3BAE548AA53D423B0D1F27EBBA6F5E5673A081D70CCE7ACFC48"));
+    }
+
+    #[test]
+    fn output_length_limit() {
+        let prk = extract::<SHA256>(b"salt", b"input key material");
+        assert!(prk.expand(b"", 255*32).is_ok());
+        assert_eq!(prk.expand(b"", 255*32 + 1), Err(HkdfError::OutputTooLong));
+        assert_eq!(format!("{:?}", prk), "Prk { .. }");
+    }
+}
@@ -0,0 +1,308 @@
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+
+use byteorder::{BigEndian, ByteOrder};
+
+use digest::DigestAlgorithm;
+use digest::hmac::Hmac;
+use digest::sha1::SHA1;
+use digest::sha2::{SHA256, SHA512};
+use utils::base64;
+use utils::constant_time;
+use utils::slice_ext::SliceExt;
+
+// PBKDF2 as defined in RFC 8018 section 5.2, using HMAC as the PRF.
+//
+// Panics if `iterations` is zero.
+pub fn pbkdf2<D: DigestAlgorithm + Clone>(password: &[u8], salt: &[u8], iterations: u32, out: &mut [u8]) {
+    assert!(iterations > 0, "PBKDF2 requires at least one iteration");
+
+    // The password is the HMAC key, so the padded key blocks only need to be
+    // hashed once. Every PRF invocation starts from a copy of this state.
+    let keyed = Hmac::<D>::new(password);
+
+    let mut block_index = [0; 4];
+    let mut offset = 0;
+    let mut index: u32 = 1;
+    while offset < out.len() {
+        // U_1 = PRF(P, S || INT(i))
+        BigEndian::write_u32(&mut block_index, index);
+        let mut state = keyed.clone();
+        state.update(salt);
+        state.update(&block_index);
+        let mut u = state.finalize();
+        let mut t = u.as_ref().to_vec();
+
+        // U_j = PRF(P, U_{j-1}), T_i = U_1 ^ U_2 ^ ... ^ U_c
+        for _ in 1..iterations {
+            let mut state = keyed.clone();
+            state.update(u.as_ref());
+            u = state.finalize();
+            for (a, b) in t.iter_mut().zip(u.as_ref()) {
+                *a ^= b;
+            }
+        }
+
+        let n = ::std::cmp::min(t.len(), out.len() - offset);
+        out[offset..offset + n].copy_from_slice(&t[..n]);
+        t.fill_copy(0);
+        offset += n;
+        index = index.checked_add(1).expect("PBKDF2 output too long");
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Pbkdf2Prf {
+    HmacSha1,
+    HmacSha256,
+    HmacSha512
+}
+
+impl Pbkdf2Prf {
+    // Algorithm identifier used in PHC strings
+    pub fn phc_id(self) -> &'static str {
+        match self {
+            Pbkdf2Prf::HmacSha1 => "pbkdf2",
+            Pbkdf2Prf::HmacSha256 => "pbkdf2-sha256",
+            Pbkdf2Prf::HmacSha512 => "pbkdf2-sha512"
+        }
+    }
+
+    pub fn from_phc_id(id: &str) -> Option<Self> {
+        match id {
+            "pbkdf2" => Some(Pbkdf2Prf::HmacSha1),
+            "pbkdf2-sha256" => Some(Pbkdf2Prf::HmacSha256),
+            "pbkdf2-sha512" => Some(Pbkdf2Prf::HmacSha512),
+            _ => None
+        }
+    }
+
+    // Default derived key length, equal to the PRF output length
+    pub fn output_len(self) -> usize {
+        match self {
+            Pbkdf2Prf::HmacSha1 => 20,
+            Pbkdf2Prf::HmacSha256 => 32,
+            Pbkdf2Prf::HmacSha512 => 64
+        }
+    }
+
+    // Panics if `iterations` is zero, like `pbkdf2`
+    pub fn derive(self, password: &[u8], salt: &[u8], iterations: u32, out: &mut [u8]) {
+        match self {
+            Pbkdf2Prf::HmacSha1 => pbkdf2::<SHA1>(password, salt, iterations, out),
+            Pbkdf2Prf::HmacSha256 => pbkdf2::<SHA256>(password, salt, iterations, out),
+            Pbkdf2Prf::HmacSha512 => pbkdf2::<SHA512>(password, salt, iterations, out)
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PhcError {
+    // The string is not of the form `$id$params$salt$hash`
+    InvalidFormat,
+    UnknownAlgorithm,
+    InvalidParameter,
+    InvalidBase64
+}
+
+impl fmt::Display for PhcError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            PhcError::InvalidFormat => write!(f, "malformed PHC string"),
+            PhcError::UnknownAlgorithm => write!(f, "unknown PHC algorithm identifier"),
+            PhcError::InvalidParameter => write!(f, "missing or invalid PHC parameter"),
+            PhcError::InvalidBase64 => write!(f, "invalid base64 in PHC string")
+        }
+    }
+}
+
+impl Error for PhcError {}
+
+// A self-describing password hash, stored as a PHC string such as
+// `$pbkdf2-sha256$i=100000$<salt>$<hash>`, with the salt and hash
+// encoded as unpadded base64.
+#[derive(Clone, PartialEq, Eq)]
+pub struct PasswordHash {
+    pub prf: Pbkdf2Prf,
+    pub iterations: u32,
+    pub salt: Vec<u8>,
+    pub hash: Vec<u8>
+}
+
+impl PasswordHash {
+    // Fails with `PhcError::InvalidParameter` if `iterations` is zero, which
+    // could not be stored in a PHC string
+    pub fn new(prf: Pbkdf2Prf, password: &[u8], salt: &[u8], iterations: u32) -> Result<Self, PhcError> {
+        if iterations == 0 {
+            return Err(PhcError::InvalidParameter);
+        }
+        let mut hash = vec![0; prf.output_len()];
+        prf.derive(password, salt, iterations, &mut hash);
+        Ok(PasswordHash {
+            prf,
+            iterations,
+            salt: salt.to_vec(),
+            hash
+        })
+    }
+
+    // Recomputes the hash with the stored parameters and compares it in constant time
+    pub fn verify(&self, password: &[u8]) -> bool {
+        // No password matches a hash with invalid parameters
+        if self.iterations == 0 || self.hash.is_empty() {
+            return false;
+        }
+        let mut actual = vec![0; self.hash.len()];
+        self.prf.derive(password, &self.salt, self.iterations, &mut actual);
+        let result = constant_time::eq(&actual, &self.hash);
+        actual.fill_copy(0);
+        result
+    }
+}
+
+// Leaves out the salt and hash, which would allow an offline search for the
+// password
+impl fmt::Debug for PasswordHash {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("PasswordHash")
+            .field("prf", &self.prf)
+            .field("iterations", &self.iterations)
+            .finish_non_exhaustive()
+    }
+}
+
+impl fmt::Display for PasswordHash {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "${}$i={}$", self.prf.phc_id(), self.iterations)?;
+        write!(f, "{}${}", base64::encode_unpadded(&self.salt), base64::encode_unpadded(&self.hash))
+    }
+}
+
+impl FromStr for PasswordHash {
+    type Err = PhcError;
+
+    fn from_str(s: &str) -> Result<Self, PhcError> {
+        let parts: Vec<&str> = s.split('$').collect();
+        if parts.len() != 5 || !parts[0].is_empty() {
+            return Err(PhcError::InvalidFormat);
+        }
+        let prf = Pbkdf2Prf::from_phc_id(parts[1]).ok_or(PhcError::UnknownAlgorithm)?;
+
+        let mut iterations = None;
+        let mut length = None;
+        for param in parts[2].split(',') {
+            let mut kv = param.splitn(2, '=');
+            let key = kv.next().unwrap();
+            let value: u32 = kv.next()
+                .and_then(|v| v.parse().ok())
+                .ok_or(PhcError::InvalidParameter)?;
+            match key {
+                "i" if iterations.is_none() => iterations = Some(value),
+                "l" if length.is_none() => length = Some(value),
+                _ => return Err(PhcError::InvalidParameter)
+            }
+        }
+        let iterations = match iterations {
+            Some(i) if i > 0 => i,
+            _ => return Err(PhcError::InvalidParameter)
+        };
+
+        let salt = base64::decode_unpadded(parts[3]).ok_or(PhcError::InvalidBase64)?;
+        let hash = base64::decode_unpadded(parts[4]).ok_or(PhcError::InvalidBase64)?;
+        if hash.is_empty() || length.is_some_and(|l| l as usize != hash.len()) {
+            return Err(PhcError::InvalidParameter);
+        }
+
+        Ok(PasswordHash {
+            prf,
+            iterations,
+            salt,
+            hash
+        })
+    }
+}
+
+// Parses a stored PHC string and checks the password against it
+pub fn verify_password(phc: &str, password: &[u8]) -> Result<bool, PhcError> {
+    Ok(phc.parse::<PasswordHash>()?.verify(password))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test<D: DigestAlgorithm + Clone>(password: &[u8], salt: &[u8], iterations: u32, expected: &[u8]) {
+        let mut actual = vec![0; expected.len()];
+        pbkdf2::<D>(password, salt, iterations, &mut actual);
+        println!("A: {:?}", actual);
+        println!("B: {:?}", expected);
+        assert!(&actual[..] == expected);
+    }
+
+    // RFC 6070 section 2
+    #[test]
+    fn pbkdf2_sha1() {
+        test::<SHA1>(b"password", b"salt", 1, base16!("0C60C80F961F0E71F3A9B524AF6012062FE037A6"));
+        test::<SHA1>(b"password", b"salt", 2, base16!("EA6C014DC72D6F8CCD1ED92ACE1D41F0D8DE8957"));
+        test::<SHA1>(b"password", b"salt", 4096, base16!("4B007901B765489ABEAD49D926F721D065A429C1"));
+        test::<SHA1>(b"passwordPASSWORDpassword", b"saltSALTsaltSALTsaltSALTsaltSALTsalt", 4096, base16!("3D2EEC4FE41C849B80C8D83662C0E44A8B291A964CF2F07038"));
+        test::<SHA1>(b"pass\0word", b"sa\0lt", 4096, base16!("56FA6AA75548099DCC37D7F03425E0C3"));
+    }
+
+    // RFC 7914 section 11
+    #[test]
+    fn pbkdf2_sha256() {
+        test::<SHA256>(b"passwd", b"salt", 1, base16!("55AC046E56E3089FEC1691C22544B605F94185216DDE0465E68B9D57C20DACBC49CA9CCCF179B645991664B39D77EF317C71B845B1E30BD509112041D3A19783"));
+    }
+
+    #[test]
+    fn pbkdf2_sha512() {
+        test::<SHA512>(b"password", b"salt", 1, base16!("867F70CF1ADE02CFF3752599A3A53DC4AF34C7A669815AE5D513554E1C8CF252C02D470A285A0501BAD999BFE943C08F050235D7D68B1DA55E63F73B60A57FCE"));
+        test::<SHA512>(b"password", b"salt", 2, base16!("E1D9C16AA681708A45F5C7C4E215CEB66E011A2E9F0040713F18AEFDB866D53CF76CAB2868A39B9F7840EDCE4FEF5A82BE67335C77A6068E04112754F27CCF4E"));
+    }
+
+    #[test]
+    fn phc_round_trip() {
+        let phc = "$pbkdf2-sha256$i=1000$c2FsdHlzYWx0c2FsdDEyMw$wq81cg+plE5/iDQQeihr5kETIEUIFi9f0XcKkHutXMI";
+        let hash = PasswordHash::new(Pbkdf2Prf::HmacSha256, b"hunter2", b"saltysaltsalt123", 1000).unwrap();
+        assert_eq!(hash.to_string(), phc);
+        assert_eq!(phc.parse::<PasswordHash>(), Ok(hash));
+        assert_eq!(verify_password(phc, b"hunter2"), Ok(true));
+        assert_eq!(verify_password(phc, b"hunter3"), Ok(false));
+    }
+
+    #[test]
+    fn zero_iterations() {
+        assert_eq!(PasswordHash::new(Pbkdf2Prf::HmacSha256, b"hunter2", b"salt", 0), Err(PhcError::InvalidParameter));
+
+        let mut hash = PasswordHash::new(Pbkdf2Prf::HmacSha256, b"hunter2", b"salt", 1).unwrap();
+        hash.iterations = 0;
+        assert!(!hash.verify(b"hunter2"));
+    }
+
+    #[test]
+    fn empty_hash() {
+        let mut hash = PasswordHash::new(Pbkdf2Prf::HmacSha256, b"hunter2", b"salt", 1).unwrap();
+        hash.hash.clear();
+        assert!(!hash.verify(b"hunter2"));
+        assert!(!hash.verify(b"anything"));
+    }
+
+    #[test]
+    fn debug_hides_hash() {
+        let hash = PasswordHash::new(Pbkdf2Prf::HmacSha256, b"hunter2", b"salt", 1).unwrap();
+        assert_eq!(format!("{:?}", hash), "PasswordHash { prf: HmacSha256, iterations: 1, .. }");
+    }
+
+    #[test]
+    fn phc_invalid() {
+        let parse = |s: &str| s.parse::<PasswordHash>().err();
+        assert_eq!(parse("pbkdf2-sha256$i=1000$c2FsdA$c2FsdA"), Some(PhcError::InvalidFormat));
+        assert_eq!(parse("$pbkdf2-md5$i=1000$c2FsdA$c2FsdA"), Some(PhcError::UnknownAlgorithm));
+        assert_eq!(parse("$pbkdf2-sha256$i=0$c2FsdA$c2FsdA"), Some(PhcError::InvalidParameter));
+        assert_eq!(parse("$pbkdf2-sha256$r=8$c2FsdA$c2FsdA"), Some(PhcError::InvalidParameter));
+        assert_eq!(parse("$pbkdf2-sha256$i=1000,l=5$c2FsdA$c2FsdA"), Some(PhcError::InvalidParameter));
+        assert_eq!(parse("$pbkdf2-sha256$i=1000$c2FsdA$c2Fsd!"), Some(PhcError::InvalidBase64));
+    }
+}
@@ -5,8 +5,13 @@ extern crate binary_macros;
 
 pub mod utils;
 pub mod digest;
+pub mod kdf;
+pub mod drbg;
+pub mod otp;
 pub mod bigint;
 
+pub use digest::siphash::SipBuildHasher;
+
 #[cfg(test)]
 mod tests {
     #[test]
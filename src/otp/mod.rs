@@ -0,0 +1,269 @@
+// One-time passwords: HOTP (RFC 4226) and TOTP (RFC 6238)
+
+use std::fmt;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use digest::DigestAlgorithm;
+use digest::hmac::Hmac;
+use digest::sha1::SHA1;
+use digest::sha2::{SHA256, SHA512};
+use utils::base32;
+use utils::constant_time;
+use utils::zeroize::Zeroize;
+
+pub mod uri;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum OtpAlgorithm {
+    Sha1,
+    Sha256,
+    Sha512
+}
+
+impl OtpAlgorithm {
+    pub const ALL: &'static [OtpAlgorithm] = &[OtpAlgorithm::Sha1, OtpAlgorithm::Sha256, OtpAlgorithm::Sha512];
+
+    // Name used by `otpauth://` URIs
+    pub fn name(self) -> &'static str {
+        match self {
+            OtpAlgorithm::Sha1 => "SHA1",
+            OtpAlgorithm::Sha256 => "SHA256",
+            OtpAlgorithm::Sha512 => "SHA512"
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<OtpAlgorithm> {
+        OtpAlgorithm::ALL.iter().cloned().find(|a| a.name().eq_ignore_ascii_case(name))
+    }
+
+    fn hmac(self, key: &[u8], input: &[u8]) -> Vec<u8> {
+        fn compute<D: DigestAlgorithm>(key: &[u8], input: &[u8]) -> Vec<u8> {
+            Hmac::<D>::compute(key, input).as_ref().to_vec()
+        }
+        match self {
+            OtpAlgorithm::Sha1 => compute::<SHA1>(key, input),
+            OtpAlgorithm::Sha256 => compute::<SHA256>(key, input),
+            OtpAlgorithm::Sha512 => compute::<SHA512>(key, input)
+        }
+    }
+}
+
+impl fmt::Display for OtpAlgorithm {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+// Counter-based one-time passwords, as defined in RFC 4226
+#[derive(Clone)]
+pub struct Hotp {
+    pub algorithm: OtpAlgorithm,
+    // Length of each code, from 6 to 9
+    pub digits: u32,
+    secret: Vec<u8>
+}
+
+impl Hotp {
+    // Uses HMAC-SHA-1 and 6 digits, which is what most authenticators expect
+    pub fn new(secret: &[u8]) -> Self {
+        Hotp {
+            algorithm: OtpAlgorithm::Sha1,
+            digits: 6,
+            secret: secret.to_vec()
+        }
+    }
+
+    pub fn from_base32(secret: &str) -> Option<Self> {
+        decode_secret(secret).map(|secret| Hotp::new(&secret))
+    }
+
+    pub fn secret(&self) -> &[u8] {
+        &self.secret
+    }
+
+    pub fn secret_base32(&self) -> String {
+        base32::encode_unpadded(&self.secret)
+    }
+
+    pub fn generate(&self, counter: u64) -> String {
+        assert!(self.digits >= 6 && self.digits <= 9, "HOTP codes must have 6 to 9 digits");
+        let mac = self.algorithm.hmac(&self.secret, &counter.to_be_bytes());
+
+        // Dynamic truncation: the low 4 bits of the last byte select where to
+        // read 31 bits from
+        let offset = (mac[mac.len() - 1] & 0xf) as usize;
+        let value = u32::from_be_bytes([mac[offset], mac[offset + 1], mac[offset + 2], mac[offset + 3]]) & 0x7fffffff;
+        format!("{:01$}", value % 10u32.pow(self.digits), self.digits as usize)
+    }
+
+    // Checks `code` against counters from `counter` to `counter + look_ahead`,
+    // returning the counter that matched. The caller should then store the
+    // following counter, so that the code can't be used again.
+    pub fn verify(&self, code: &str, counter: u64, look_ahead: u64) -> Option<u64> {
+        let mut result = None;
+        for c in counter..=counter.saturating_add(look_ahead) {
+            // Check every counter, so the time taken doesn't reveal which matched
+            if constant_time::eq(self.generate(c).as_bytes(), code.as_bytes()) && result.is_none() {
+                result = Some(c);
+            }
+        }
+        result
+    }
+}
+
+// Leaves out the secret, so it doesn't end up in logs
+impl fmt::Debug for Hotp {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Hotp")
+            .field("algorithm", &self.algorithm)
+            .field("digits", &self.digits)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Drop for Hotp {
+    fn drop(&mut self) {
+        self.secret.zeroize();
+    }
+}
+
+// Time-based one-time passwords, as defined in RFC 6238
+#[derive(Debug, Clone)]
+pub struct Totp {
+    pub hotp: Hotp,
+    // Length of each time step in seconds
+    pub period: u64,
+    // Number of time steps either side of the current one that are also
+    // accepted by `verify`, to allow for clock drift
+    pub skew: u64
+}
+
+impl Totp {
+    // Uses HMAC-SHA-1, 6 digits and a 30 second period, and accepts codes
+    // from one time step either side of the current one
+    pub fn new(secret: &[u8]) -> Self {
+        Totp {
+            hotp: Hotp::new(secret),
+            period: 30,
+            skew: 1
+        }
+    }
+
+    pub fn from_base32(secret: &str) -> Option<Self> {
+        Hotp::from_base32(secret).map(|hotp| Totp {
+            hotp,
+            period: 30,
+            skew: 1
+        })
+    }
+
+    // Time step containing `time`, in seconds since the Unix epoch
+    pub fn counter_at(&self, time: u64) -> u64 {
+        time/self.period
+    }
+
+    pub fn generate_at(&self, time: u64) -> String {
+        self.hotp.generate(self.counter_at(time))
+    }
+
+    pub fn generate(&self) -> String {
+        self.generate_at(now())
+    }
+
+    // Returns the time step that matched, which the caller should record to
+    // reject replays of the same code
+    pub fn verify_at(&self, code: &str, time: u64) -> Option<u64> {
+        let counter = self.counter_at(time);
+        let start = counter.saturating_sub(self.skew);
+        self.hotp.verify(code, start, counter - start + self.skew)
+    }
+
+    pub fn verify(&self, code: &str) -> Option<u64> {
+        self.verify_at(code, now())
+    }
+}
+
+// Secrets are usually exchanged in base32, with any padding omitted and
+// sometimes split into groups by spaces
+fn decode_secret(secret: &str) -> Option<Vec<u8>> {
+    let secret: String = secret.chars().filter(|&c| c != ' ').collect();
+    base32::decode_unpadded(secret.trim_end_matches('='))
+}
+
+fn now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).expect("system clock is before 1970").as_secs()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // RFC 4226 appendix D
+    #[test]
+    fn hotp() {
+        let hotp = Hotp::new(b"12345678901234567890");
+        let expected = ["755224", "287082", "359152", "969429", "338314", "254676", "287922", "162583", "399871", "520489"];
+        for (counter, &code) in expected.iter().enumerate() {
+            assert_eq!(hotp.generate(counter as u64), code);
+        }
+
+        assert_eq!(hotp.verify("969429", 0, 5), Some(3));
+        assert_eq!(hotp.verify("969429", 4, 5), None);
+        assert_eq!(hotp.verify("969429", 0, 2), None);
+        assert_eq!(hotp.verify("96942", 0, 5), None);
+    }
+
+    fn test_totp(algorithm: OtpAlgorithm, secret: &[u8], expected: &[&str]) {
+        let times = [59, 1111111109, 1111111111, 1234567890, 2000000000, 20000000000];
+        let mut totp = Totp::new(secret);
+        totp.hotp.algorithm = algorithm;
+        totp.hotp.digits = 8;
+        for (&time, &code) in times.iter().zip(expected) {
+            assert_eq!(totp.generate_at(time), code);
+        }
+    }
+
+    // RFC 6238 appendix B
+    #[test]
+    fn totp() {
+        test_totp(OtpAlgorithm::Sha1, b"12345678901234567890",
+            &["94287082", "07081804", "14050471", "89005924", "69279037", "65353130"]);
+        test_totp(OtpAlgorithm::Sha256, b"12345678901234567890123456789012",
+            &["46119246", "68084774", "67062674", "91819424", "90698825", "77737706"]);
+        test_totp(OtpAlgorithm::Sha512, b"1234567890123456789012345678901234567890123456789012345678901234",
+            &["90693936", "25091201", "99943326", "93441116", "38618901", "47863826"]);
+    }
+
+    #[test]
+    fn totp_skew() {
+        let mut totp = Totp::from_base32("GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ").unwrap();
+        totp.hotp.digits = 8;
+        // Code for the time step starting at 1111111110
+        assert_eq!(totp.verify_at("14050471", 1111111111), Some(37037037));
+        assert_eq!(totp.verify_at("14050471", 1111111139), Some(37037037));
+        assert_eq!(totp.verify_at("14050471", 1111111170), None);
+        assert_eq!(totp.verify_at("14050471", 1111111080), Some(37037037));
+        assert_eq!(totp.verify_at("14050471", 1111111079), None);
+
+        totp.skew = 0;
+        assert_eq!(totp.verify_at("14050471", 1111111109), None);
+
+        // Times near the epoch don't underflow
+        assert_eq!(Totp::new(b"12345678901234567890").verify_at("000000", 0), None);
+    }
+
+    #[test]
+    fn debug_hides_secret() {
+        let totp = Totp::new(b"12345678901234567890");
+        let debug = format!("{:?}", totp);
+        assert!(!debug.contains("secret") && !debug.contains("49, 50"), "{}", debug);
+    }
+
+    #[test]
+    fn base32_secret() {
+        let hotp = Hotp::from_base32("gezd gnbv gy3t qojq gezd gnbv gy3t qojq").unwrap();
+        assert_eq!(hotp.secret(), b"12345678901234567890");
+        assert_eq!(hotp.secret_base32(), "GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ");
+        assert!(Hotp::from_base32("GEZDGNBVGY3TQOJ1").is_none());
+    }
+}
@@ -0,0 +1,291 @@
+// `otpauth://` URIs, in the key URI format used by authenticator apps:
+//
+//     otpauth://totp/Issuer:account?secret=BASE32&issuer=Issuer&period=30
+
+use std::error::Error;
+use std::fmt::{self, Write};
+use std::str::FromStr;
+
+use utils::base32;
+use utils::zeroize::Zeroize;
+use super::{decode_secret, Hotp, OtpAlgorithm, Totp};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OtpUriError {
+    // Not an `otpauth://` URI
+    InvalidScheme,
+    // Neither `hotp` nor `totp`
+    InvalidType,
+    // Malformed percent-encoding
+    InvalidEncoding,
+    MissingParameter(&'static str),
+    InvalidParameter(&'static str)
+}
+
+impl fmt::Display for OtpUriError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            OtpUriError::InvalidScheme => write!(f, "not an otpauth URI"),
+            OtpUriError::InvalidType => write!(f, "unknown one-time password type"),
+            OtpUriError::InvalidEncoding => write!(f, "invalid percent-encoding in otpauth URI"),
+            OtpUriError::MissingParameter(name) => write!(f, "otpauth URI has no {} parameter", name),
+            OtpUriError::InvalidParameter(name) => write!(f, "invalid {} parameter in otpauth URI", name)
+        }
+    }
+}
+
+impl Error for OtpUriError {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OtpKind {
+    Hotp { counter: u64 },
+    Totp { period: u64 }
+}
+
+#[derive(Clone, PartialEq, Eq)]
+pub struct OtpUri {
+    pub kind: OtpKind,
+    pub issuer: Option<String>,
+    pub account: String,
+    pub algorithm: OtpAlgorithm,
+    pub digits: u32,
+    pub secret: Vec<u8>
+}
+
+impl OtpUri {
+    pub fn from_hotp(hotp: &Hotp, counter: u64, issuer: Option<&str>, account: &str) -> Self {
+        OtpUri {
+            kind: OtpKind::Hotp { counter },
+            issuer: issuer.map(str::to_string),
+            account: account.to_string(),
+            algorithm: hotp.algorithm,
+            digits: hotp.digits,
+            secret: hotp.secret().to_vec()
+        }
+    }
+
+    pub fn from_totp(totp: &Totp, issuer: Option<&str>, account: &str) -> Self {
+        let mut result = OtpUri::from_hotp(&totp.hotp, 0, issuer, account);
+        result.kind = OtpKind::Totp { period: totp.period };
+        result
+    }
+
+    pub fn hotp(&self) -> Hotp {
+        let mut result = Hotp::new(&self.secret);
+        result.algorithm = self.algorithm;
+        result.digits = self.digits;
+        result
+    }
+
+    // Returns `None` for HOTP URIs
+    pub fn totp(&self) -> Option<Totp> {
+        match self.kind {
+            OtpKind::Totp { period } => {
+                let mut result = Totp::new(&self.secret);
+                result.hotp = self.hotp();
+                result.period = period;
+                Some(result)
+            },
+            OtpKind::Hotp { .. } => None
+        }
+    }
+}
+
+// Leaves out the secret, so it doesn't end up in logs
+impl fmt::Debug for OtpUri {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("OtpUri")
+            .field("kind", &self.kind)
+            .field("issuer", &self.issuer)
+            .field("account", &self.account)
+            .field("algorithm", &self.algorithm)
+            .field("digits", &self.digits)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Drop for OtpUri {
+    fn drop(&mut self) {
+        self.secret.zeroize();
+    }
+}
+
+// Escapes everything except the unreserved characters of RFC 3986
+fn percent_encode(f: &mut fmt::Formatter, s: &str) -> fmt::Result {
+    for &byte in s.as_bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => f.write_char(byte as char)?,
+            _ => write!(f, "%{:02X}", byte)?
+        }
+    }
+    Ok(())
+}
+
+fn percent_decode(s: &str) -> Result<String, OtpUriError> {
+    let bytes = s.as_bytes();
+    let mut result = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            let hex = s.get(i + 1..i + 3).ok_or(OtpUriError::InvalidEncoding)?;
+            result.push(u8::from_str_radix(hex, 16).map_err(|_| OtpUriError::InvalidEncoding)?);
+            i += 3;
+        } else {
+            result.push(bytes[i]);
+            i += 1;
+        }
+    }
+    String::from_utf8(result).map_err(|_| OtpUriError::InvalidEncoding)
+}
+
+fn parse_number<T: FromStr>(value: &str, name: &'static str) -> Result<T, OtpUriError> {
+    value.parse().map_err(|_| OtpUriError::InvalidParameter(name))
+}
+
+impl fmt::Display for OtpUri {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let kind = match self.kind {
+            OtpKind::Hotp { .. } => "hotp",
+            OtpKind::Totp { .. } => "totp"
+        };
+        write!(f, "otpauth://{}/", kind)?;
+        if let Some(ref issuer) = self.issuer {
+            percent_encode(f, issuer)?;
+            f.write_char(':')?;
+        }
+        percent_encode(f, &self.account)?;
+
+        write!(f, "?secret={}", base32::encode_unpadded(&self.secret))?;
+        if let Some(ref issuer) = self.issuer {
+            f.write_str("&issuer=")?;
+            percent_encode(f, issuer)?;
+        }
+        write!(f, "&algorithm={}&digits={}", self.algorithm, self.digits)?;
+        match self.kind {
+            OtpKind::Hotp { counter } => write!(f, "&counter={}", counter),
+            OtpKind::Totp { period } => write!(f, "&period={}", period)
+        }
+    }
+}
+
+// Parameters other than those in `OtpUri` are ignored. Missing algorithm,
+// digits and period parameters take the usual defaults.
+impl FromStr for OtpUri {
+    type Err = OtpUriError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let scheme = "otpauth://";
+        if !s.get(..scheme.len()).is_some_and(|prefix| prefix.eq_ignore_ascii_case(scheme)) {
+            return Err(OtpUriError::InvalidScheme);
+        }
+        let rest = &s[scheme.len()..];
+        let (kind, rest) = rest.split_at(rest.find('/').ok_or(OtpUriError::InvalidType)?);
+        let is_hotp = match &kind.to_ascii_lowercase()[..] {
+            "hotp" => true,
+            "totp" => false,
+            _ => return Err(OtpUriError::InvalidType)
+        };
+        let mut parts = rest[1..].splitn(2, '?');
+        let label = percent_decode(parts.next().unwrap_or(""))?;
+        let query = parts.next().unwrap_or("");
+
+        // The label is either "account" or "issuer:account"
+        let (mut issuer, account) = match label.find(':') {
+            Some(i) => (Some(label[..i].to_string()), label[i + 1..].trim_start().to_string()),
+            None => (None, label.clone())
+        };
+
+        let mut secret = None;
+        let mut algorithm = OtpAlgorithm::Sha1;
+        let mut digits = 6;
+        let mut counter = None;
+        let mut period = 30;
+        for pair in query.split('&').filter(|pair| !pair.is_empty()) {
+            let mut pair = pair.splitn(2, '=');
+            let name = pair.next().unwrap_or("");
+            let value = percent_decode(pair.next().unwrap_or(""))?;
+            match name {
+                "secret" => secret = Some(decode_secret(&value).ok_or(OtpUriError::InvalidParameter("secret"))?),
+                // Takes precedence over the label prefix
+                "issuer" => issuer = Some(value),
+                "algorithm" => algorithm = OtpAlgorithm::from_name(&value).ok_or(OtpUriError::InvalidParameter("algorithm"))?,
+                "digits" => digits = parse_number(&value, "digits")?,
+                "counter" => counter = Some(parse_number(&value, "counter")?),
+                "period" => period = parse_number(&value, "period")?,
+                _ => {}
+            }
+        }
+
+        if !(6..=9).contains(&digits) {
+            return Err(OtpUriError::InvalidParameter("digits"));
+        }
+        if period == 0 {
+            return Err(OtpUriError::InvalidParameter("period"));
+        }
+        let kind = if is_hotp {
+            OtpKind::Hotp { counter: counter.ok_or(OtpUriError::MissingParameter("counter"))? }
+        } else {
+            OtpKind::Totp { period }
+        };
+        Ok(OtpUri {
+            kind,
+            issuer,
+            account,
+            algorithm,
+            digits,
+            secret: secret.ok_or(OtpUriError::MissingParameter("secret"))?
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn format() {
+        let mut totp = Totp::new(b"12345678901234567890");
+        totp.hotp.digits = 8;
+        let uri = OtpUri::from_totp(&totp, Some("ACME Co"), "john.doe@example.com");
+        assert_eq!(uri.to_string(), "otpauth://totp/ACME%20Co:john.doe%40example.com?secret=GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ&issuer=ACME%20Co&algorithm=SHA1&digits=8&period=30");
+        assert_eq!(uri.to_string().parse::<OtpUri>(), Ok(uri.clone()));
+        assert_eq!(uri.totp().unwrap().generate_at(59), "94287082");
+        assert!(!format!("{:?}", uri).contains("secret"));
+
+        let uri = OtpUri::from_hotp(&Hotp::new(b"12345678901234567890"), 3, None, "alice");
+        assert_eq!(uri.to_string(), "otpauth://hotp/alice?secret=GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ&algorithm=SHA1&digits=6&counter=3");
+        assert_eq!(uri.to_string().parse::<OtpUri>(), Ok(uri.clone()));
+        assert!(uri.totp().is_none());
+        assert_eq!(uri.hotp().generate(3), "969429");
+    }
+
+    #[test]
+    fn parse() {
+        let uri: OtpUri = "otpauth://totp/Example:alice@google.com?secret=JBSWY3DPEHPK3PXP&issuer=Example".parse().unwrap();
+        assert_eq!(uri.kind, OtpKind::Totp { period: 30 });
+        assert_eq!(uri.issuer.as_ref().map(|s| &s[..]), Some("Example"));
+        assert_eq!(uri.account, "alice@google.com");
+        assert_eq!(uri.algorithm, OtpAlgorithm::Sha1);
+        assert_eq!(uri.digits, 6);
+        assert_eq!(uri.secret, b"Hello!\xde\xad\xbe\xef");
+
+        let uri: OtpUri = "otpauth://TOTP/Big%20Corp%3A%20bob?secret=jbswy3dpehpk3pxp&algorithm=sha256&period=60&image=x".parse().unwrap();
+        assert_eq!(uri.issuer.as_ref().map(|s| &s[..]), Some("Big Corp"));
+        assert_eq!(uri.account, "bob");
+        assert_eq!(uri.algorithm, OtpAlgorithm::Sha256);
+        assert_eq!(uri.kind, OtpKind::Totp { period: 60 });
+    }
+
+    #[test]
+    fn invalid() {
+        assert_eq!("https://totp/a?secret=JBSWY3DPEHPK3PXP".parse::<OtpUri>(), Err(OtpUriError::InvalidScheme));
+        assert_eq!("otpauth:/\u{e9}".parse::<OtpUri>(), Err(OtpUriError::InvalidScheme));
+        assert_eq!("otp".parse::<OtpUri>(), Err(OtpUriError::InvalidScheme));
+        assert_eq!("otpauth://motp/a?secret=JBSWY3DPEHPK3PXP".parse::<OtpUri>(), Err(OtpUriError::InvalidType));
+        assert_eq!("otpauth://totp/a".parse::<OtpUri>(), Err(OtpUriError::MissingParameter("secret")));
+        assert_eq!("otpauth://hotp/a?secret=JBSWY3DPEHPK3PXP".parse::<OtpUri>(), Err(OtpUriError::MissingParameter("counter")));
+        assert_eq!("otpauth://totp/a?secret=JBSWY3DPEHPK3PX1".parse::<OtpUri>(), Err(OtpUriError::InvalidParameter("secret")));
+        assert_eq!("otpauth://totp/a?secret=JBSWY3DPEHPK3PXP&digits=5".parse::<OtpUri>(), Err(OtpUriError::InvalidParameter("digits")));
+        assert_eq!("otpauth://totp/a%2?secret=JBSWY3DPEHPK3PXP".parse::<OtpUri>(), Err(OtpUriError::InvalidEncoding));
+    }
+}
@@ -0,0 +1,118 @@
+// Base32 alphabet from RFC 4648 section 6
+const ALPHABET: &[u8; 32] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
+
+// Lowercase letters are accepted, since secrets are often written that way
+fn decode_char(c: u8) -> Option<u8> {
+    match c {
+        b'A'..=b'Z' => Some(c - b'A'),
+        b'a'..=b'z' => Some(c - b'a'),
+        b'2'..=b'7' => Some(c - b'2' + 26),
+        _ => None
+    }
+}
+
+// Number of characters needed for `n` bytes, without padding
+fn encoded_len(n: usize) -> usize {
+    (n*8).div_ceil(5)
+}
+
+pub fn encode(input: &[u8]) -> String {
+    let mut result = encode_unpadded(input);
+    while !result.len().is_multiple_of(8) {
+        result.push('=');
+    }
+    result
+}
+
+pub fn encode_unpadded(input: &[u8]) -> String {
+    let mut result = String::with_capacity(encoded_len(input.len()));
+    for group in input.chunks(5) {
+        let mut bits = 0u64;
+        for (i, &byte) in group.iter().enumerate() {
+            bits |= (byte as u64) << (32 - i*8);
+        }
+        for i in 0..encoded_len(group.len()) {
+            result.push(ALPHABET[((bits >> (35 - i*5)) & 0x1f) as usize] as char);
+        }
+    }
+    result
+}
+
+pub fn decode(input: &str) -> Option<Vec<u8>> {
+    if !input.len().is_multiple_of(8) {
+        return None;
+    }
+    let trimmed = input.trim_end_matches('=');
+    if input.len() - trimmed.len() > 6 {
+        return None;
+    }
+    decode_unpadded(trimmed)
+}
+
+// Rejects non-canonical encodings, where the unused low bits of the final
+// character are not zero.
+pub fn decode_unpadded(input: &str) -> Option<Vec<u8>> {
+    let input = input.as_bytes();
+    let mut result = Vec::with_capacity(input.len()*5/8);
+    for group in input.chunks(8) {
+        // Only some group lengths correspond to a whole number of bytes
+        let byte_count = group.len()*5/8;
+        if encoded_len(byte_count) != group.len() {
+            return None;
+        }
+        let mut bits = 0u64;
+        for (i, &c) in group.iter().enumerate() {
+            bits |= (decode_char(c)? as u64) << (35 - i*5);
+        }
+        if bits & (0xff_ffff_ffff >> (byte_count*8)) != 0 {
+            return None;
+        }
+        for i in 0..byte_count {
+            result.push((bits >> (32 - i*8)) as u8);
+        }
+    }
+    Some(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // RFC 4648 section 10
+    const VECTORS: &[(&[u8], &str)] = &[
+        (b"", ""),
+        (b"f", "MY======"),
+        (b"fo", "MZXQ===="),
+        (b"foo", "MZXW6==="),
+        (b"foob", "MZXW6YQ="),
+        (b"fooba", "MZXW6YTB"),
+        (b"foobar", "MZXW6YTBOI======")
+    ];
+
+    #[test]
+    fn padded() {
+        for &(raw, encoded) in VECTORS {
+            assert_eq!(encode(raw), encoded);
+            assert_eq!(decode(encoded).unwrap(), raw);
+        }
+    }
+
+    #[test]
+    fn unpadded() {
+        for &(raw, encoded) in VECTORS {
+            let encoded = encoded.trim_end_matches('=');
+            assert_eq!(encode_unpadded(raw), encoded);
+            assert_eq!(decode_unpadded(encoded).unwrap(), raw);
+            assert_eq!(decode_unpadded(&encoded.to_ascii_lowercase()).unwrap(), raw);
+        }
+    }
+
+    #[test]
+    fn invalid() {
+        assert_eq!(decode("MY====="), None);
+        assert_eq!(decode("M======="), None);
+        assert_eq!(decode_unpadded("MZ"), None);
+        assert_eq!(decode_unpadded("MZX"), None);
+        assert_eq!(decode_unpadded("MZXW1"), None);
+    }
+}
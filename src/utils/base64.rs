@@ -0,0 +1,114 @@
+// Standard base64 alphabet from RFC 4648 section 4
+const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+fn decode_char(c: u8) -> Option<u8> {
+    match c {
+        b'A'..=b'Z' => Some(c - b'A'),
+        b'a'..=b'z' => Some(c - b'a' + 26),
+        b'0'..=b'9' => Some(c - b'0' + 52),
+        b'+' => Some(62),
+        b'/' => Some(63),
+        _ => None
+    }
+}
+
+pub fn encode(input: &[u8]) -> String {
+    let mut result = encode_unpadded(input);
+    while !result.len().is_multiple_of(4) {
+        result.push('=');
+    }
+    result
+}
+
+pub fn encode_unpadded(input: &[u8]) -> String {
+    let mut result = String::with_capacity((input.len()*4).div_ceil(3));
+    for group in input.chunks(3) {
+        let mut bits = 0u32;
+        for (i, &byte) in group.iter().enumerate() {
+            bits |= (byte as u32) << (16 - i*8);
+        }
+        // Each input byte contributes to one more output character
+        for i in 0..group.len() + 1 {
+            result.push(ALPHABET[((bits >> (18 - i*6)) & 0x3f) as usize] as char);
+        }
+    }
+    result
+}
+
+pub fn decode(input: &str) -> Option<Vec<u8>> {
+    if !input.len().is_multiple_of(4) {
+        return None;
+    }
+    let trimmed = input.trim_end_matches('=');
+    if input.len() - trimmed.len() > 2 {
+        return None;
+    }
+    decode_unpadded(trimmed)
+}
+
+// Rejects non-canonical encodings, where the unused low bits of the final
+// character are not zero.
+pub fn decode_unpadded(input: &str) -> Option<Vec<u8>> {
+    let input = input.as_bytes();
+    if input.len() % 4 == 1 {
+        return None;
+    }
+    let mut result = Vec::with_capacity(input.len()*3/4);
+    for group in input.chunks(4) {
+        let mut bits = 0u32;
+        for (i, &c) in group.iter().enumerate() {
+            bits |= (decode_char(c)? as u32) << (18 - i*6);
+        }
+        let byte_count = group.len() - 1;
+        if bits & (0xffffff >> (byte_count*8)) != 0 {
+            return None;
+        }
+        for i in 0..byte_count {
+            result.push((bits >> (16 - i*8)) as u8);
+        }
+    }
+    Some(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // RFC 4648 section 10
+    const VECTORS: &[(&[u8], &str)] = &[
+        (b"", ""),
+        (b"f", "Zg=="),
+        (b"fo", "Zm8="),
+        (b"foo", "Zm9v"),
+        (b"foob", "Zm9vYg=="),
+        (b"fooba", "Zm9vYmE="),
+        (b"foobar", "Zm9vYmFy")
+    ];
+
+    #[test]
+    fn padded() {
+        for &(raw, encoded) in VECTORS {
+            assert_eq!(encode(raw), encoded);
+            assert_eq!(decode(encoded).unwrap(), raw);
+        }
+    }
+
+    #[test]
+    fn unpadded() {
+        for &(raw, encoded) in VECTORS {
+            let encoded = encoded.trim_end_matches('=');
+            assert_eq!(encode_unpadded(raw), encoded);
+            assert_eq!(decode_unpadded(encoded).unwrap(), raw);
+        }
+    }
+
+    #[test]
+    fn invalid() {
+        assert_eq!(decode("Zg="), None);
+        assert_eq!(decode("Zg=a"), None);
+        assert_eq!(decode("Z==="), None);
+        assert_eq!(decode_unpadded("Zh"), None);
+        assert_eq!(decode_unpadded("Zm9v!"), None);
+        assert_eq!(decode_unpadded("Zm9vY"), None);
+    }
+}
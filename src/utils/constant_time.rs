@@ -0,0 +1,12 @@
+// Compares two byte slices without branching on their contents, so that
+// the time taken does not reveal the position of the first difference.
+pub fn eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    let mut diff = 0;
+    for (x, y) in a.iter().zip(b) {
+        diff |= x ^ y;
+    }
+    diff == 0
+}
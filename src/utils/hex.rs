@@ -0,0 +1,56 @@
+const LOWER: &[u8; 16] = b"0123456789abcdef";
+const UPPER: &[u8; 16] = b"0123456789ABCDEF";
+
+fn encode_with(input: &[u8], alphabet: &[u8; 16]) -> String {
+    let mut result = String::with_capacity(input.len()*2);
+    for &byte in input {
+        result.push(alphabet[(byte >> 4) as usize] as char);
+        result.push(alphabet[(byte & 0xf) as usize] as char);
+    }
+    result
+}
+
+pub fn encode(input: &[u8]) -> String {
+    encode_with(input, LOWER)
+}
+
+pub fn encode_upper(input: &[u8]) -> String {
+    encode_with(input, UPPER)
+}
+
+// Accepts either case
+pub fn decode(input: &str) -> Option<Vec<u8>> {
+    let input = input.as_bytes();
+    if !input.len().is_multiple_of(2) {
+        return None;
+    }
+    let mut result = Vec::with_capacity(input.len()/2);
+    for pair in input.chunks(2) {
+        let high = (pair[0] as char).to_digit(16)?;
+        let low = (pair[1] as char).to_digit(16)?;
+        result.push((high << 4 | low) as u8);
+    }
+    Some(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let raw = [0x00, 0x1f, 0xa0, 0xff];
+        assert_eq!(encode(&raw), "001fa0ff");
+        assert_eq!(encode_upper(&raw), "001FA0FF");
+        assert_eq!(decode("001fa0ff").unwrap(), raw);
+        assert_eq!(decode("001FA0fF").unwrap(), raw);
+        assert_eq!(decode("").unwrap(), []);
+    }
+
+    #[test]
+    fn invalid() {
+        assert!(decode("abc").is_none());
+        assert!(decode("0g").is_none());
+        assert!(decode("+1").is_none());
+    }
+}
@@ -1 +1,6 @@
+pub mod base32;
+pub mod base64;
+pub mod constant_time;
+pub mod hex;
 pub mod slice_ext;
+pub mod zeroize;
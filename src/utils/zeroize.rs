@@ -0,0 +1,60 @@
+use std::ptr;
+use std::sync::atomic::{compiler_fence, Ordering};
+
+// Overwrites secret data with zeros. The writes are volatile, so they are
+// kept even when the value is about to be dropped and never read again.
+pub trait Zeroize {
+    fn zeroize(&mut self);
+}
+
+macro_rules! impl_zeroize_int {
+    ($($ty:ty),*) => {
+        $(
+            impl Zeroize for $ty {
+                fn zeroize(&mut self) {
+                    unsafe { ptr::write_volatile(self, 0) };
+                    // Keep later code from being reordered before the wipe
+                    compiler_fence(Ordering::SeqCst);
+                }
+            }
+        )*
+    }
+}
+
+impl_zeroize_int!(u8, u16, u32, u64, u128, usize);
+
+impl<T: Zeroize> Zeroize for [T] {
+    fn zeroize(&mut self) {
+        for elem in self {
+            elem.zeroize();
+        }
+    }
+}
+
+impl<T: Zeroize, const N: usize> Zeroize for [T; N] {
+    fn zeroize(&mut self) {
+        self[..].zeroize();
+    }
+}
+
+impl<T: Zeroize + ?Sized> Zeroize for Box<T> {
+    fn zeroize(&mut self) {
+        (**self).zeroize();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn zeroize() {
+        let mut words = [0x0123456789abcdefu64; 4];
+        words.zeroize();
+        assert_eq!(words, [0; 4]);
+
+        let mut bytes = vec![0xffu8; 100].into_boxed_slice();
+        bytes.zeroize();
+        assert!(bytes.iter().all(|&b| b == 0));
+    }
+}